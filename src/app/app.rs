@@ -9,12 +9,12 @@ use tokio::sync::RwLock;
 
 use crate::{
     configurations::*,
+    http_proxy_pass::WebSocketConnections,
     settings::{ConnectionsSettingsModel, SettingsModel},
-    ssh_to_http_port_forward_pool::SshToHttpPortForwardPool,
+    ssh_reverse::SshReverseStatuses,
+    ssh_sessions_pool::SshSessionsPool,
 };
 
-use super::LocalPortAllocator;
-
 pub const APP_NAME: &'static str = env!("CARGO_PKG_NAME");
 pub const APP_VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
@@ -26,9 +26,9 @@ pub struct AppContext {
     pub token_secret_key: AesKey,
     current_app_configuration: RwLock<Option<Arc<AppConfiguration>>>,
     pub states: Arc<AppStates>,
-    pub local_port_allocator: LocalPortAllocator,
-
-    pub ssh_to_http_port_forward_pool: SshToHttpPortForwardPool,
+    pub ssh_sessions_pool: SshSessionsPool,
+    pub ssh_reverse_statuses: SshReverseStatuses,
+    pub web_socket_connections: Arc<WebSocketConnections>,
 }
 
 impl AppContext {
@@ -49,12 +49,16 @@ impl AppContext {
             token_secret_key,
             current_app_configuration: RwLock::new(None),
             states: Arc::new(AppStates::create_initialized()),
-            local_port_allocator: LocalPortAllocator::new(),
-            ssh_to_http_port_forward_pool: SshToHttpPortForwardPool::new(),
+            ssh_sessions_pool: SshSessionsPool::new(),
+            ssh_reverse_statuses: SshReverseStatuses::new(),
+            web_socket_connections: Arc::new(WebSocketConnections::new()),
         }
     }
 
     pub async fn set_current_app_configuration(&self, app_config: AppConfiguration) {
+        // Content types are detected by file executors, which do not have access to the configuration
+        crate::http_content_source::register_mime_types(app_config.mime_types.clone());
+
         let mut current_app_configuration = self.current_app_configuration.write().await;
         *current_app_configuration = Some(Arc::new(app_config));
     }
@@ -1,4 +1,2 @@
 mod app;
 pub use app::*;
-mod local_port_allocator;
-pub use local_port_allocator::*;
@@ -0,0 +1,326 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const MAX_WINDOW_RESULTS: usize = 10_000;
+
+pub struct CircuitBreakerParams {
+    pub consecutive_failures: Option<usize>,
+    // Percent of failed requests within the window
+    pub error_rate: Option<f64>,
+    pub min_requests: usize,
+    pub window: Duration,
+    pub open_duration: Duration,
+    pub half_open_requests: usize,
+    pub failure_status_codes: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        }
+    }
+}
+
+pub struct CircuitBreakerSnapshot {
+    pub upstream: String,
+    pub state: CircuitState,
+    pub consecutive_failures: usize,
+    pub error_rate: f64,
+    pub requests_in_window: usize,
+    pub opened: usize,
+    pub open_remaining: Option<Duration>,
+}
+
+struct CircuitBreakerInner {
+    state: CircuitState,
+    consecutive_failures: usize,
+    // Request results within the window. true - request failed
+    results: VecDeque<(Instant, bool)>,
+    opened_at: Instant,
+    probes_in_flight: usize,
+    probes_succeeded: usize,
+    opened: usize,
+}
+
+pub struct CircuitBreaker {
+    pub upstream: String,
+    params: Arc<CircuitBreakerParams>,
+    inner: Mutex<CircuitBreakerInner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(upstream: String, params: Arc<CircuitBreakerParams>) -> Self {
+        Self {
+            upstream,
+            params,
+            inner: Mutex::new(CircuitBreakerInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                results: VecDeque::new(),
+                opened_at: Instant::now(),
+                probes_in_flight: 0,
+                probes_succeeded: 0,
+                opened: 0,
+            }),
+        }
+    }
+
+    pub fn is_failure_status_code(&self, status_code: u16) -> bool {
+        self.params.failure_status_codes.contains(&status_code)
+    }
+
+    // Returns Err with the time after which upstream is going to be probed again
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    pub fn on_success(&self) {
+        self.on_result_at(false, Instant::now());
+    }
+
+    pub fn on_failure(&self) {
+        self.on_result_at(true, Instant::now());
+    }
+
+    // Request is finished without the result which tells about upstream health
+    pub fn release(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state == CircuitState::HalfOpen && inner.probes_in_flight > 0 {
+            inner.probes_in_flight -= 1;
+        }
+    }
+
+    pub fn get_snapshot(&self) -> CircuitBreakerSnapshot {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        self.remove_expired(&mut inner, now);
+
+        let open_remaining = if inner.state == CircuitState::Open {
+            Some(
+                self.params
+                    .open_duration
+                    .saturating_sub(now.duration_since(inner.opened_at)),
+            )
+        } else {
+            None
+        };
+
+        CircuitBreakerSnapshot {
+            upstream: self.upstream.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            error_rate: get_error_rate(&inner.results),
+            requests_in_window: inner.results.len(),
+            opened: inner.opened,
+            open_remaining,
+        }
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let elapsed = now.duration_since(inner.opened_at);
+
+                if elapsed < self.params.open_duration {
+                    return Err(self.params.open_duration - elapsed);
+                }
+
+                inner.state = CircuitState::HalfOpen;
+                inner.opened_at = now;
+                inner.probes_in_flight = 1;
+                inner.probes_succeeded = 0;
+                Ok(())
+            }
+            CircuitState::HalfOpen => {
+                // Probes which never reported the result do not block the upstream forever
+                if inner.probes_in_flight >= self.params.half_open_requests
+                    && now.duration_since(inner.opened_at) >= self.params.open_duration
+                {
+                    inner.probes_in_flight = 0;
+                    inner.opened_at = now;
+                }
+
+                if inner.probes_in_flight >= self.params.half_open_requests {
+                    return Err(self.params.open_duration);
+                }
+
+                inner.probes_in_flight += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn on_result_at(&self, failed: bool, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.state {
+            CircuitState::Closed => {
+                if failed {
+                    inner.consecutive_failures += 1;
+                } else {
+                    inner.consecutive_failures = 0;
+                }
+
+                inner.results.push_back((now, failed));
+                if inner.results.len() > MAX_WINDOW_RESULTS {
+                    inner.results.pop_front();
+                }
+                self.remove_expired(&mut inner, now);
+
+                if failed && self.is_threshold_reached(&inner) {
+                    self.open(&mut inner, now);
+                }
+            }
+            CircuitState::Open => {}
+            CircuitState::HalfOpen => {
+                if inner.probes_in_flight > 0 {
+                    inner.probes_in_flight -= 1;
+                }
+
+                if failed {
+                    self.open(&mut inner, now);
+                    return;
+                }
+
+                inner.probes_succeeded += 1;
+
+                if inner.probes_succeeded >= self.params.half_open_requests {
+                    inner.state = CircuitState::Closed;
+                    inner.consecutive_failures = 0;
+                    inner.results.clear();
+                    inner.probes_in_flight = 0;
+                }
+            }
+        }
+    }
+
+    fn is_threshold_reached(&self, inner: &CircuitBreakerInner) -> bool {
+        if let Some(consecutive_failures) = self.params.consecutive_failures {
+            if inner.consecutive_failures >= consecutive_failures {
+                return true;
+            }
+        }
+
+        if let Some(error_rate) = self.params.error_rate {
+            if inner.results.len() >= self.params.min_requests
+                && get_error_rate(&inner.results) >= error_rate
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn open(&self, inner: &mut CircuitBreakerInner, now: Instant) {
+        inner.state = CircuitState::Open;
+        inner.opened_at = now;
+        inner.probes_in_flight = 0;
+        inner.probes_succeeded = 0;
+        inner.opened += 1;
+
+        println!(
+            "Circuit breaker for upstream {} is open for {:?}",
+            self.upstream, self.params.open_duration
+        );
+    }
+
+    fn remove_expired(&self, inner: &mut CircuitBreakerInner, now: Instant) {
+        while let Some((moment, _)) = inner.results.front() {
+            if now.duration_since(*moment) <= self.params.window {
+                break;
+            }
+            inner.results.pop_front();
+        }
+    }
+}
+
+fn get_error_rate(results: &VecDeque<(Instant, bool)>) -> f64 {
+    if results.is_empty() {
+        return 0.0;
+    }
+
+    let failed = results.iter().filter(|(_, failed)| *failed).count();
+    failed as f64 * 100.0 / results.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_params() -> Arc<CircuitBreakerParams> {
+        Arc::new(CircuitBreakerParams {
+            consecutive_failures: Some(3),
+            error_rate: Some(50.0),
+            min_requests: 4,
+            window: Duration::from_secs(10),
+            open_duration: Duration::from_secs(5),
+            half_open_requests: 1,
+            failure_status_codes: vec![502, 503, 504],
+        })
+    }
+
+    #[test]
+    fn test_consecutive_failures_and_half_open() {
+        let circuit_breaker =
+            CircuitBreaker::new("http://localhost:5000".to_string(), create_params());
+        let now = Instant::now();
+
+        circuit_breaker.on_result_at(true, now);
+        circuit_breaker.on_result_at(true, now);
+        assert!(circuit_breaker.try_acquire_at(now).is_ok());
+
+        circuit_breaker.on_result_at(true, now);
+        assert_eq!(
+            5,
+            circuit_breaker.try_acquire_at(now).unwrap_err().as_secs()
+        );
+
+        // Only one probe is let through after open duration
+        let now = now + Duration::from_secs(5);
+        assert!(circuit_breaker.try_acquire_at(now).is_ok());
+        assert!(circuit_breaker.try_acquire_at(now).is_err());
+
+        circuit_breaker.on_result_at(true, now);
+        assert!(circuit_breaker.try_acquire_at(now).is_err());
+
+        let now = now + Duration::from_secs(5);
+        assert!(circuit_breaker.try_acquire_at(now).is_ok());
+        circuit_breaker.on_result_at(false, now);
+
+        assert_eq!(CircuitState::Closed, circuit_breaker.get_snapshot().state);
+        assert!(circuit_breaker.try_acquire_at(now).is_ok());
+    }
+
+    #[test]
+    fn test_error_rate() {
+        let circuit_breaker =
+            CircuitBreaker::new("http://localhost:5000".to_string(), create_params());
+        let now = Instant::now();
+
+        circuit_breaker.on_result_at(false, now);
+        circuit_breaker.on_result_at(true, now);
+        circuit_breaker.on_result_at(false, now);
+        assert!(circuit_breaker.try_acquire_at(now).is_ok());
+
+        // 2 of 4 requests are failed
+        circuit_breaker.on_result_at(true, now);
+        assert!(circuit_breaker.try_acquire_at(now).is_err());
+    }
+}
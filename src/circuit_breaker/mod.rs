@@ -0,0 +1,2 @@
+mod circuit_breaker;
+pub use circuit_breaker::*;
@@ -18,8 +18,14 @@ pub struct AppConfiguration {
     pub http_endpoints: BTreeMap<u16, HttpListenPortConfiguration>,
     pub tcp_endpoints: BTreeMap<u16, Arc<TcpEndpointHostConfig>>,
     pub tcp_over_ssh_endpoints: BTreeMap<u16, Arc<TcpOverSshEndpointHostConfig>>,
+    pub socks5_endpoints: BTreeMap<u16, Arc<ForwardProxyEndpointHostConfig>>,
+    pub http_forward_proxy_endpoints: BTreeMap<u16, Arc<ForwardProxyEndpointHostConfig>>,
+    pub udp_endpoints: BTreeMap<u16, Arc<UdpEndpointHostConfig>>,
+    pub unix_socket_http_endpoints: BTreeMap<String, Arc<UnixSocketHttpEndpointConfig>>,
+    pub ssh_reverse_endpoints: Vec<Arc<SshReverseEndpointConfig>>,
     pub crl: HashMap<String, FileSource>,
     pub list_of_crl: Mutex<ListOfCrl>,
+    pub mime_types: HashMap<String, String>,
 }
 
 impl AppConfiguration {
@@ -65,6 +71,17 @@ impl AppConfiguration {
         }
     }
 
+    // Endpoint is known before the first request only if it is the only one on the port
+    pub fn get_single_http_endpoint_info(&self, listen_port: u16) -> Option<Arc<HttpEndpointInfo>> {
+        let listen_port_config = self.http_endpoints.get(&listen_port)?;
+
+        if listen_port_config.endpoint_info.len() != 1 {
+            return None;
+        }
+
+        listen_port_config.endpoint_info.first().cloned()
+    }
+
     pub fn get_http_endpoint_info(
         &self,
         listen_port: u16,
@@ -0,0 +1,10 @@
+use std::sync::Arc;
+
+use crate::circuit_breaker::CircuitBreaker;
+
+pub struct CircuitBreakerLocationConfig {
+    // One per upstream: proxy_pass_to first, then retry upstreams in the same order
+    pub circuit_breakers: Vec<Arc<CircuitBreaker>>,
+    // Path of the location of the same endpoint which serves requests when all the circuits are open
+    pub fallback: Option<String>,
+}
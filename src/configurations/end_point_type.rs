@@ -6,6 +6,9 @@ pub enum EndpointType {
     Http(HttpEndpointInfo),
     Tcp(Arc<TcpEndpointHostConfig>),
     TcpOverSsh(Arc<TcpOverSshEndpointHostConfig>),
+    Socks5(Arc<ForwardProxyEndpointHostConfig>),
+    HttpForwardProxy(Arc<ForwardProxyEndpointHostConfig>),
+    Udp(Arc<UdpEndpointHostConfig>),
 }
 
 /*
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+pub struct ErrorPage {
+    pub content_type: String,
+    pub content: Bytes,
+}
+
+pub struct ErrorPagesConfig {
+    pages: HashMap<u16, ErrorPage>,
+}
+
+impl ErrorPagesConfig {
+    pub fn new() -> Self {
+        Self {
+            pages: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, status_code: u16, error_page: ErrorPage) {
+        self.pages.insert(status_code, error_page);
+    }
+
+    pub fn get(&self, status_code: u16) -> Option<&ErrorPage> {
+        self.pages.get(&status_code)
+    }
+}
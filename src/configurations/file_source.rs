@@ -1,7 +1,6 @@
 use std::{collections::HashMap, time::Duration};
 
 use my_settings_reader::flurl::FlUrl;
-use my_ssh::SshSession;
 use rust_extensions::StrOrString;
 
 use crate::{files_cache::FilesCache, variables_reader::VariablesReader};
@@ -107,7 +106,7 @@ impl FileSource {
                         ssh_credentials.credentials.to_string(),
                         path
                     );
-                    let ssh_session = SshSession::new(ssh_credentials.credentials.clone().into());
+                    let ssh_session = ssh_credentials.connect(Duration::from_secs(5)).await?;
 
                     let result = ssh_session
                         .download_remote_file(&path, Duration::from_secs(5))
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::{rate_limit::ConnectionsLimit, types::AllowedDestinations, types::WhiteListedIpList};
+
+pub struct ForwardProxyEndpointHostConfig {
+    pub host: EndpointHttpHostString,
+    pub credentials: Option<(String, String)>,
+    pub allowed_destinations: AllowedDestinations,
+    // If not set - destinations are connected directly
+    pub ssh: Option<SshSessionConfiguration>,
+    pub debug: bool,
+    pub whitelisted_ip: WhiteListedIpList,
+    pub connections_limit: Option<Arc<ConnectionsLimit>>,
+}
+
+impl ForwardProxyEndpointHostConfig {
+    pub fn get_egress_as_string(&self) -> String {
+        match self.ssh.as_ref() {
+            Some(ssh) => format!("ssh:{}", ssh.to_string()),
+            None => "direct".to_string(),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#[derive(Debug, Clone)]
+pub struct GrpcLocationConfig {
+    pub grpc_web: bool,
+}
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+pub const UNIX_SOCKET_PREFIX: &str = "unix:";
+
 #[derive(Clone)]
 pub struct EndpointHttpHostString {
     src: Arc<String>,
@@ -8,6 +10,18 @@ pub struct EndpointHttpHostString {
 
 impl EndpointHttpHostString {
     pub fn new(host: String) -> Result<Self, String> {
+        // Unix socket endpoint has no port. It serves any host name
+        if let Some(unix_socket_path) = host.strip_prefix(UNIX_SOCKET_PREFIX) {
+            if unix_socket_path.is_empty() {
+                return Err(format!("Unix socket path is empty for host: {}", host));
+            }
+
+            return Ok(Self {
+                src: Arc::new(host),
+                port: 0,
+            });
+        }
+
         let port: u16 = match host.split(':').last().unwrap().parse() {
             Ok(result) => result,
             Err(_) => {
@@ -24,6 +38,10 @@ impl EndpointHttpHostString {
     }
 
     pub fn is_my_server_name(&self, server_name: &str) -> bool {
+        if self.get_unix_socket_path().is_some() {
+            return true;
+        }
+
         let index = self.src.find(':');
 
         match index {
@@ -42,4 +60,8 @@ impl EndpointHttpHostString {
     pub fn get_port(&self) -> u16 {
         self.port
     }
+
+    pub fn get_unix_socket_path(&self) -> Option<&str> {
+        self.src.strip_prefix(UNIX_SOCKET_PREFIX)
+    }
 }
@@ -1,7 +1,9 @@
 use std::{net::SocketAddr, sync::Arc};
 
 use crate::{
+    http_compression::CompressionConfig,
     http_proxy_pass::AllowedUserList,
+    rate_limit::{ConnectionPermit, ConnectionsLimit, RateLimiter},
     settings::{GoogleAuthSettings, HttpEndpointModifyHeadersSettings},
 };
 
@@ -16,7 +18,12 @@ pub struct HttpEndpointInfo {
     pub client_certificate_id: Option<SslCertificateId>,
     pub locations: Vec<Arc<ProxyPassLocationConfig>>,
     pub allowed_user_list: Option<Arc<AllowedUserList>>,
+    pub identity_headers: Option<Arc<IdentityHeadersConfig>>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub connections_limit: Option<Arc<ConnectionsLimit>>,
     pub modify_headers_settings: HttpEndpointModifyHeadersSettings,
+    pub error_pages: Option<Arc<ErrorPagesConfig>>,
+    pub compression: Option<Arc<CompressionConfig>>,
 }
 
 impl HttpEndpointInfo {
@@ -29,7 +36,12 @@ impl HttpEndpointInfo {
         client_certificate_id: Option<SslCertificateId>,
         locations: Vec<Arc<ProxyPassLocationConfig>>,
         allowed_user_list: Option<Arc<AllowedUserList>>,
+        identity_headers: Option<Arc<IdentityHeadersConfig>>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        connections_limit: Option<Arc<ConnectionsLimit>>,
         modify_headers_settings: HttpEndpointModifyHeadersSettings,
+        error_pages: Option<Arc<ErrorPagesConfig>>,
+        compression: Option<Arc<CompressionConfig>>,
     ) -> Self {
         Self {
             host_endpoint,
@@ -39,8 +51,13 @@ impl HttpEndpointInfo {
             client_certificate_id,
             locations,
             allowed_user_list,
+            identity_headers,
+            rate_limiter,
+            connections_limit,
             modify_headers_settings,
             ssl_certificate_id,
+            error_pages,
+            compression,
         }
     }
 
@@ -52,6 +69,26 @@ impl HttpEndpointInfo {
         self.host_endpoint.as_str()
     }
 
+    // Err - connection is over the limit and has to be closed
+    pub fn try_acquire_connection(
+        &self,
+        socket_addr: SocketAddr,
+    ) -> Result<Option<ConnectionPermit>, String> {
+        let connections_limit = match self.connections_limit.as_ref() {
+            Some(connections_limit) => connections_limit,
+            None => return Ok(None),
+        };
+
+        match connections_limit.try_acquire(socket_addr.ip()) {
+            Some(permit) => Ok(Some(permit)),
+            None => Err(format!(
+                "Connection from {} to {} is over the connections limit",
+                socket_addr,
+                self.as_str()
+            )),
+        }
+    }
+
     pub fn get_listening_port_info(&self, socket_addr: SocketAddr) -> HttpListenPortInfo {
         HttpListenPortInfo {
             http_type: self.http_type,
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+pub enum IdentityHeadersSignType {
+    None,
+    Hmac,
+    Jwt { ttl: Duration },
+}
+
+impl IdentityHeadersSignType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentityHeadersSignType::None => "none",
+            IdentityHeadersSignType::Hmac => "hmac",
+            IdentityHeadersSignType::Jwt { .. } => "jwt",
+        }
+    }
+}
+
+pub struct IdentityHeadersConfig {
+    pub sign: IdentityHeadersSignType,
+    pub key: Vec<u8>,
+}
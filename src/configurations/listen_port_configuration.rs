@@ -6,6 +6,9 @@ pub enum ListenPortConfiguration {
     Http(HttpListenPortConfiguration),
     Tcp(Arc<TcpEndpointHostConfig>),
     TcpOverSsh(Arc<TcpOverSshEndpointHostConfig>),
+    Socks5(Arc<ForwardProxyEndpointHostConfig>),
+    HttpForwardProxy(Arc<ForwardProxyEndpointHostConfig>),
+    Udp(Arc<UdpEndpointHostConfig>),
 }
 
 impl ListenPortConfiguration {
@@ -24,6 +27,15 @@ impl ListenPortConfiguration {
             ListenPortConfiguration::TcpOverSsh(tcp_over_ssh_endpoint_host_config) => {
                 tcp_over_ssh_endpoint_host_config.host.as_str()
             }
+            ListenPortConfiguration::Socks5(socks5_endpoint_host_config) => {
+                socks5_endpoint_host_config.host.as_str()
+            }
+            ListenPortConfiguration::HttpForwardProxy(http_forward_proxy_host_config) => {
+                http_forward_proxy_host_config.host.as_str()
+            }
+            ListenPortConfiguration::Udp(udp_endpoint_host_config) => {
+                udp_endpoint_host_config.host.as_str()
+            }
         }
     }
 
@@ -50,6 +62,27 @@ impl ListenPortConfiguration {
                     http_endpoint_info.host_endpoint.as_str()
                 ));
             }
+            ListenPortConfiguration::Socks5(_) => {
+                return Err(format!(
+                    "Cannot add http endpoint {} info to socks5 endpoint {}",
+                    host_str,
+                    http_endpoint_info.host_endpoint.as_str()
+                ));
+            }
+            ListenPortConfiguration::HttpForwardProxy(_) => {
+                return Err(format!(
+                    "Cannot add http endpoint {} info to http forward proxy endpoint {}",
+                    host_str,
+                    http_endpoint_info.host_endpoint.as_str()
+                ));
+            }
+            ListenPortConfiguration::Udp(_) => {
+                return Err(format!(
+                    "Cannot add http endpoint {} info to udp endpoint {}",
+                    host_str,
+                    http_endpoint_info.host_endpoint.as_str()
+                ));
+            }
         }
 
         Ok(())
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use crate::http_client::HTTP_CLIENT_TIMEOUT;
+
+// Timeouts which are not set fall back to the global ones
+#[derive(Debug, Clone, Default)]
+pub struct LocationTimeouts {
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+    // Covers all the attempts of the request including retries and backoff delays
+    pub total: Option<Duration>,
+}
+
+impl LocationTimeouts {
+    pub fn get_connect_timeout(&self) -> Duration {
+        self.connect.unwrap_or(HTTP_CLIENT_TIMEOUT)
+    }
+
+    pub fn get_read_timeout(&self) -> Duration {
+        self.read.unwrap_or(HTTP_CLIENT_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::http_client::HTTP_CLIENT_TIMEOUT;
+
+    use super::LocationTimeouts;
+
+    #[test]
+    fn test_not_set_timeouts_fall_back_to_http_client_timeout() {
+        let timeouts = LocationTimeouts::default();
+
+        assert_eq!(HTTP_CLIENT_TIMEOUT, timeouts.get_connect_timeout());
+        assert_eq!(HTTP_CLIENT_TIMEOUT, timeouts.get_read_timeout());
+
+        let timeouts = LocationTimeouts {
+            connect: Some(Duration::from_secs(1)),
+            read: Some(Duration::from_secs(2)),
+            total: None,
+        };
+
+        assert_eq!(Duration::from_secs(1), timeouts.get_connect_timeout());
+        assert_eq!(Duration::from_secs(2), timeouts.get_read_timeout());
+    }
+}
@@ -32,3 +32,31 @@ mod host_str;
 pub use host_str::*;
 mod ssl_certificate_id;
 pub use ssl_certificate_id::*;
+mod identity_headers_config;
+pub use identity_headers_config::*;
+mod ssh_reverse_endpoint_config;
+pub use ssh_reverse_endpoint_config::*;
+mod forward_proxy_endpoint_host_config;
+pub use forward_proxy_endpoint_host_config::*;
+mod udp_endpoint_host_config;
+pub use udp_endpoint_host_config::*;
+mod error_pages_config;
+pub use error_pages_config::*;
+mod unix_socket_http_endpoint_config;
+pub use unix_socket_http_endpoint_config::*;
+mod upstream_tls_config;
+pub use upstream_tls_config::*;
+mod grpc_location_config;
+pub use grpc_location_config::*;
+mod web_socket_location_config;
+pub use web_socket_location_config::*;
+mod streaming_config;
+pub use streaming_config::*;
+mod location_timeouts;
+pub use location_timeouts::*;
+mod retry_policy;
+pub use retry_policy::*;
+mod circuit_breaker_location_config;
+pub use circuit_breaker_location_config::*;
+mod traffic_split_config;
+pub use traffic_split_config::*;
@@ -1,11 +1,14 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
+    http_cache::HttpCache,
     http_content_source::{
         LocalPathContentSrc, PathOverSshContentSource, RemoteHttpContentSource, StaticContentSrc,
     },
     http_proxy_pass::{HttpProxyPassContentSource, HttpProxyPassRemoteEndpoint},
+    rate_limit::RateLimiter,
     settings::{ModifyHttpHeadersSettings, ProxyPassTo},
+    traffic_mirror::TrafficMirror,
     types::WhiteListedIpList,
 };
 
@@ -18,10 +21,22 @@ pub struct ProxyPassLocationConfig {
     pub whitelisted_ip: WhiteListedIpList,
     pub remote_type: HttpType,
     pub domain_name: Option<String>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub upstream_tls: Option<Arc<UpstreamTlsConfig>>,
+    pub grpc: Option<GrpcLocationConfig>,
+    pub web_socket: WebSocketLocationConfig,
+    pub streaming: Option<StreamingConfig>,
+    pub timeouts: LocationTimeouts,
+    pub retry: Option<Arc<RetryPolicy>>,
+    pub circuit_breaker: Option<CircuitBreakerLocationConfig>,
+    pub cache: Option<Arc<HttpCache>>,
+    pub mirror: Option<Arc<TrafficMirror>>,
+    pub split: Option<TrafficSplitConfig>,
     proxy_pass_to: ProxyPassTo,
 }
 
 impl ProxyPassLocationConfig {
+    // Features of the location are off by default. They are set by the fields after the config is created
     pub fn new(
         id: i64,
         path: String,
@@ -39,12 +54,28 @@ impl ProxyPassLocationConfig {
             proxy_pass_to,
             remote_type,
             domain_name,
+            rate_limiter: None,
+            upstream_tls: None,
+            grpc: None,
+            web_socket: WebSocketLocationConfig::default(),
+            streaming: None,
+            timeouts: LocationTimeouts::default(),
+            retry: None,
+            circuit_breaker: None,
+            cache: None,
+            mirror: None,
+            split: None,
         }
     }
+
     pub fn get_proxy_pass_to_as_string(&self) -> String {
         self.proxy_pass_to.to_string()
     }
 
+    pub fn is_remote_http(&self) -> bool {
+        self.proxy_pass_to.is_remote_http()
+    }
+
     /*
     pub fn is_my_uri(&self, uri: &Uri) -> bool {
         let result = rust_extensions::str_utils::starts_with_case_insensitive(
@@ -61,7 +92,46 @@ impl ProxyPassLocationConfig {
         debug: bool,
         timeout: Duration,
     ) -> HttpProxyPassContentSource {
-        match &self.proxy_pass_to {
+        self.create_content_source_for(&self.proxy_pass_to, debug, timeout)
+    }
+
+    pub fn create_retry_content_sources(
+        &self,
+        debug: bool,
+        timeout: Duration,
+    ) -> Vec<HttpProxyPassContentSource> {
+        match self.retry.as_ref() {
+            Some(retry) => retry
+                .upstreams
+                .iter()
+                .map(|proxy_pass_to| self.create_content_source_for(proxy_pass_to, debug, timeout))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn create_mirror_content_source(&self, debug: bool) -> Option<RemoteHttpContentSource> {
+        let mirror = self.mirror.as_ref()?;
+
+        match self.create_content_source_for(
+            &mirror.proxy_pass_to,
+            debug,
+            self.timeouts.get_read_timeout(),
+        ) {
+            HttpProxyPassContentSource::Http(remote_http_content_source) => {
+                Some(remote_http_content_source)
+            }
+            _ => None,
+        }
+    }
+
+    fn create_content_source_for(
+        &self,
+        proxy_pass_to: &ProxyPassTo,
+        debug: bool,
+        timeout: Duration,
+    ) -> HttpProxyPassContentSource {
+        match proxy_pass_to {
             ProxyPassTo::Static(static_content_model) => {
                 HttpProxyPassContentSource::Static(StaticContentSrc::new(
                     static_content_model.status_code,
@@ -73,6 +143,8 @@ impl ProxyPassLocationConfig {
                 HttpProxyPassContentSource::Http(RemoteHttpContentSource::new(
                     self.id,
                     HttpProxyPassRemoteEndpoint::Http(RemoteHost::new(remote_host.to_string())),
+                    self.upstream_tls.clone(),
+                    self.timeouts.get_connect_timeout(),
                     debug,
                 ))
             }
@@ -81,12 +153,35 @@ impl ProxyPassLocationConfig {
                 HttpProxyPassContentSource::Http(RemoteHttpContentSource::new(
                     self.id,
                     HttpProxyPassRemoteEndpoint::Http2(RemoteHost::new(remote_host.to_string())),
+                    self.upstream_tls.clone(),
+                    self.timeouts.get_connect_timeout(),
+                    debug,
+                ))
+            }
+            ProxyPassTo::UnixSocket(model) => {
+                let remote_endpoint = if model.http2 {
+                    HttpProxyPassRemoteEndpoint::Http2UnixSocket(model.unix_socket_path.clone())
+                } else {
+                    HttpProxyPassRemoteEndpoint::Http1UnixSocket(model.unix_socket_path.clone())
+                };
+
+                HttpProxyPassContentSource::Http(RemoteHttpContentSource::new(
+                    self.id,
+                    remote_endpoint,
+                    None,
+                    self.timeouts.get_connect_timeout(),
                     debug,
                 ))
             }
-            ProxyPassTo::LocalPath(model) => HttpProxyPassContentSource::LocalPath(
-                LocalPathContentSrc::new(&model.local_path, model.default_file.clone()),
-            ),
+            ProxyPassTo::LocalPath(model) => {
+                HttpProxyPassContentSource::LocalPath(LocalPathContentSrc::new(
+                    &model.local_path,
+                    model.default_file.clone(),
+                    model.autoindex,
+                    model.try_files.clone(),
+                    model.precompressed,
+                ))
+            }
             ProxyPassTo::Ssh(model) => match &model.ssh_config.remote_content {
                 SshContent::RemoteHost(remote_host) => {
                     if model.http2 {
@@ -94,8 +189,12 @@ impl ProxyPassLocationConfig {
                             self.id,
                             HttpProxyPassRemoteEndpoint::Http2OverSsh {
                                 ssh_credentials: model.ssh_config.credentials.clone(),
+                                ssh_jump_hosts: model.ssh_config.jump_hosts.clone(),
+                                ssh_host_keys: model.ssh_config.host_keys.clone(),
                                 remote_host: remote_host.clone(),
                             },
+                            None,
+                            self.timeouts.get_connect_timeout(),
                             debug,
                         ))
                     } else {
@@ -103,8 +202,12 @@ impl ProxyPassLocationConfig {
                             self.id,
                             HttpProxyPassRemoteEndpoint::Http1OverSsh {
                                 ssh_credentials: model.ssh_config.credentials.clone(),
+                                ssh_jump_hosts: model.ssh_config.jump_hosts.clone(),
+                                ssh_host_keys: model.ssh_config.host_keys.clone(),
                                 remote_host: remote_host.clone(),
                             },
+                            None,
+                            self.timeouts.get_connect_timeout(),
                             debug,
                         ))
                     }
@@ -112,6 +215,8 @@ impl ProxyPassLocationConfig {
                 SshContent::FilePath(file_path) => {
                     HttpProxyPassContentSource::PathOverSsh(PathOverSshContentSource::new(
                         model.ssh_config.credentials.clone(),
+                        model.ssh_config.jump_hosts.clone(),
+                        model.ssh_config.host_keys.clone(),
                         file_path.clone(),
                         model.default_file.clone(),
                         timeout,
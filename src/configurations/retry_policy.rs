@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use hyper::Method;
+
+use crate::{http_client::HttpClientError, http_proxy_pass::ProxyPassError, settings::ProxyPassTo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOnError {
+    Connect,
+    Timeout,
+    Reset,
+}
+
+impl RetryOnError {
+    pub fn parse(src: &str) -> Option<Self> {
+        match src {
+            "connect" => Some(Self::Connect),
+            "timeout" => Some(Self::Timeout),
+            "reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    pub fn from_error(err: &ProxyPassError) -> Option<Self> {
+        match err {
+            ProxyPassError::Timeout => Some(Self::Timeout),
+            ProxyPassError::HttpClientError(HttpClientError::TimeOut) => Some(Self::Timeout),
+            ProxyPassError::HttpClientError(_) => Some(Self::Connect),
+            ProxyPassError::SshSessionError(_) => Some(Self::Connect),
+            ProxyPassError::SshConnectError(_) => Some(Self::Connect),
+            ProxyPassError::HyperError(_) => Some(Self::Reset),
+            ProxyPassError::IoError(_) => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+
+pub struct RetryPolicy {
+    // Includes the first attempt
+    pub max_attempts: usize,
+    pub methods: Vec<Method>,
+    pub status_codes: Vec<u16>,
+    pub errors: Vec<RetryOnError>,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    // Upstreams which are tried in turn after the location proxy_pass_to fails
+    pub upstreams: Vec<ProxyPassTo>,
+}
+
+impl RetryPolicy {
+    pub fn get_default_methods() -> Vec<Method> {
+        vec![
+            Method::GET,
+            Method::HEAD,
+            Method::OPTIONS,
+            Method::PUT,
+            Method::DELETE,
+            Method::TRACE,
+        ]
+    }
+
+    pub fn can_retry(&self, method: &Method, attempts_done: usize) -> bool {
+        attempts_done < self.max_attempts && self.methods.contains(method)
+    }
+
+    pub fn is_retry_status_code(&self, status_code: u16) -> bool {
+        self.status_codes.contains(&status_code)
+    }
+
+    pub fn is_retry_error(&self, err: &ProxyPassError) -> bool {
+        match RetryOnError::from_error(err) {
+            Some(retry_on_error) => self.errors.contains(&retry_on_error),
+            None => false,
+        }
+    }
+
+    pub fn get_backoff(&self, attempts_done: usize) -> Duration {
+        let mut result = self.backoff;
+
+        for _ in 1..attempts_done {
+            result = result.saturating_mul(2);
+            if result >= self.max_backoff {
+                return self.max_backoff;
+            }
+        }
+
+        result.min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hyper::Method;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn test_backoff_and_attempts() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            methods: RetryPolicy::get_default_methods(),
+            status_codes: vec![502, 503],
+            errors: vec![],
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            upstreams: vec![],
+        };
+
+        assert_eq!(Duration::from_millis(100), policy.get_backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.get_backoff(2));
+        assert_eq!(Duration::from_millis(300), policy.get_backoff(3));
+        assert_eq!(Duration::from_millis(300), policy.get_backoff(10));
+
+        assert!(policy.can_retry(&Method::GET, 2));
+        assert!(!policy.can_retry(&Method::GET, 3));
+        assert!(!policy.can_retry(&Method::POST, 1));
+    }
+}
@@ -1,10 +1,18 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
 use serde::*;
 
-use crate::{populate_variable::populate_variable, variables_reader::VariablesReader};
+use crate::{
+    populate_variable::populate_variable,
+    ssh_host_keys::{SshHostKeyPolicy, SshHostKeySettings},
+    variables_reader::VariablesReader,
+};
 
 use super::LocalFilePath;
 
 pub enum SshConfigOption {
+    AsSshAgent,
     AsPassword(String),
     AsPrivateKeyFile {
         file_path: LocalFilePath,
@@ -17,6 +25,11 @@ pub struct SshConfigSettings {
     pub password: Option<String>,
     pub private_key_file: Option<String>,
     pub passphrase: Option<String>,
+    pub keep_alive_interval: Option<String>,
+    pub jump_host: Option<String>,
+    pub host_key_policy: Option<String>,
+    pub known_hosts_file: Option<String>,
+    pub host_key_fingerprint: Option<String>,
 }
 
 impl SshConfigSettings {
@@ -26,7 +39,11 @@ impl SshConfigSettings {
         }
 
         if self.private_key_file.is_none() {
-            return Err("Either password or private_key_file must be set".to_string());
+            if self.passphrase.is_some() {
+                return Err("passphrase is set but private_key_file is not".to_string());
+            }
+
+            return Ok(SshConfigOption::AsSshAgent);
         }
 
         let private_key_file = self.private_key_file.as_ref().unwrap();
@@ -44,4 +61,46 @@ impl SshConfigSettings {
             passphrase,
         })
     }
+
+    pub fn get_keep_alive_interval(&self) -> Result<Option<Duration>, String> {
+        match self.keep_alive_interval.as_ref() {
+            Some(keep_alive_interval) => match parse_duration(keep_alive_interval) {
+                Ok(result) => Ok(Some(result)),
+                Err(_) => Err(format!(
+                    "Can not parse ssh keep_alive_interval value: '{}'",
+                    keep_alive_interval
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_host_key_settings(
+        &self,
+        variables_reader: VariablesReader,
+    ) -> Result<SshHostKeySettings, String> {
+        let mut result = SshHostKeySettings::default();
+
+        if let Some(host_key_policy) = self.host_key_policy.as_ref() {
+            result.policy = SshHostKeyPolicy::parse(host_key_policy)?;
+        }
+
+        if let Some(known_hosts_file) = self.known_hosts_file.as_ref() {
+            result.known_hosts_file =
+                populate_variable(known_hosts_file, variables_reader).to_string();
+        }
+
+        if let Some(host_key_fingerprint) = self.host_key_fingerprint.as_ref() {
+            if !host_key_fingerprint.trim().starts_with("SHA256:") {
+                return Err(format!(
+                    "Invalid ssh host_key_fingerprint '{}'. Format must be SHA256:base64",
+                    host_key_fingerprint
+                ));
+            }
+
+            result.fingerprint = Some(host_key_fingerprint.trim().to_string());
+        }
+
+        Ok(result)
+    }
 }
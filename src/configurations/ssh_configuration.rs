@@ -1,10 +1,14 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use my_ssh::SshCredentials;
 
-use crate::variables_reader::VariablesReader;
+use crate::{
+    ssh_host_keys::{SshChainHostKeys, SshHostKeySettings},
+    ssh_jump_hosts::SshChainSession,
+    variables_reader::VariablesReader,
+};
 
-use super::{LocalFilePath, RemoteHost, SshConfigSettings};
+use super::{LocalFilePath, RemoteHost, SshConfigOption, SshConfigSettings};
 
 pub const SSH_PREFIX: &str = "ssh:";
 
@@ -45,7 +49,32 @@ impl SshContent {
 #[derive(Debug)]
 pub struct SshConfiguration {
     pub credentials: Arc<SshCredentials>,
+    pub jump_hosts: Vec<Arc<SshCredentials>>,
+    pub host_keys: Arc<SshChainHostKeys>,
     pub remote_content: SshContent,
+    pub keep_alive_interval: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct SshSessionConfiguration {
+    pub credentials: Arc<SshCredentials>,
+    pub jump_hosts: Vec<Arc<SshCredentials>>,
+    pub host_keys: Arc<SshChainHostKeys>,
+    pub keep_alive_interval: Option<Duration>,
+}
+
+impl SshSessionConfiguration {
+    pub fn to_string(&self) -> String {
+        let mut result = String::new();
+
+        for jump_host in &self.jump_hosts {
+            result.push_str(jump_host.to_string().as_str());
+            result.push_str("->");
+        }
+
+        result.push_str(self.credentials.to_string().as_str());
+        result
+    }
 }
 
 impl SshConfiguration {
@@ -54,94 +83,235 @@ impl SshConfiguration {
         ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
         variables_reader: VariablesReader,
     ) -> Result<Self, String> {
-        let mut parts = src.split("->");
-        let ssh_part = parts.next().unwrap();
+        let mut parts: Vec<&str> = src.split("->").map(|itm| itm.trim()).collect();
 
-        let ssh_part = ssh_part[SSH_PREFIX.len()..].trim();
+        if parts.len() < 2 {
+            return Err(format!(
+                "Invalid ssh configuration '{}'. Remote host or path is missing",
+                src
+            ));
+        }
 
-        let remote_part = parts.next().unwrap();
+        let remote_part = parts.pop().unwrap();
 
         let remote_content = parse_remote_part(remote_part);
 
-        if let Some(ssh_configs) = ssh_configs {
-            if let Some(ssh_config_settings) = ssh_configs.get(ssh_part) {
-                match ssh_config_settings.get_option(variables_reader)? {
-                    super::SshConfigOption::AsPassword(password) => {
-                        let (ssh_user_name, ssh_session_host, ssh_session_port) =
-                            parse_ssh_part(ssh_part);
-                        println!("SSH: {} using Login+Password for authentication", ssh_part);
-                        return Ok(Self {
-                            credentials: SshCredentials::UserNameAndPassword {
-                                ssh_remote_host: ssh_session_host.to_string(),
-                                ssh_remote_port: ssh_session_port,
-                                ssh_user_name: ssh_user_name.to_string(),
-                                password,
-                            }
-                            .into(),
-                            remote_content,
-                        });
-                    }
-                    super::SshConfigOption::AsPrivateKeyFile {
-                        file_path,
-                        passphrase,
-                    } => {
-                        let (ssh_user_name, ssh_session_host, ssh_session_port) =
-                            parse_ssh_part(ssh_part);
-                        println!("SSH: {} using PrivateKey for authentication", ssh_part);
-                        return Ok(Self {
-                            credentials: SshCredentials::PrivateKey {
-                                ssh_remote_host: ssh_session_host.to_string(),
-                                ssh_remote_port: ssh_session_port,
-                                ssh_user_name: ssh_user_name.to_string(),
-                                private_key: load_private_key(&file_path)?,
-                                passphrase,
-                            }
-                            .into(),
-                            remote_content,
-                        });
-                    }
-                }
-            } else {
-                let (ssh_user_name, ssh_session_host, ssh_session_port) = parse_ssh_part(ssh_part);
-                println!("SSH: {} using SshAgent for authentication", ssh_part);
-                let result = Self {
-                    credentials: SshCredentials::SshAgent {
-                        ssh_remote_host: ssh_session_host.to_string(),
-                        ssh_remote_port: ssh_session_port,
-                        ssh_user_name: ssh_user_name.to_string(),
-                    }
-                    .into(),
-                    remote_content,
-                };
-
-                Ok(result)
-            }
-        } else {
-            let (ssh_user_name, ssh_session_host, ssh_session_port) = parse_ssh_part(ssh_part);
-            println!("SSH: {} using SshAgent for authentication", ssh_part);
+        let session = parse_hops(src, parts, ssh_configs, variables_reader)?;
 
-            let result = Self {
-                credentials: SshCredentials::SshAgent {
-                    ssh_remote_host: ssh_session_host.to_string(),
-                    ssh_remote_port: ssh_session_port,
-                    ssh_user_name: ssh_user_name.to_string(),
-                }
-                .into(),
-                remote_content: parse_remote_part(remote_part),
-            };
-
-            Ok(result)
-        }
+        Ok(Self {
+            credentials: session.credentials,
+            jump_hosts: session.jump_hosts,
+            host_keys: session.host_keys,
+            remote_content,
+            keep_alive_interval: session.keep_alive_interval,
+        })
     }
 
-    pub fn to_string(&self) -> String {
-        format!(
-            "{}@{}->{}",
-            self.credentials.get_user_name(),
-            self.credentials.get_host_port_as_string(),
-            self.remote_content.as_str()
+    // Ssh chain without remote part. Remote host is chosen per connection (socks5 egress)
+    pub fn parse_session(
+        src: &str,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+        variables_reader: VariablesReader,
+    ) -> Result<SshSessionConfiguration, String> {
+        let parts: Vec<&str> = src.split("->").map(|itm| itm.trim()).collect();
+        parse_hops(src, parts, ssh_configs, variables_reader)
+    }
+
+    pub async fn connect(&self, timeout: Duration) -> Result<SshChainSession, String> {
+        SshChainSession::connect(
+            &self.jump_hosts,
+            &self.credentials,
+            &self.host_keys,
+            timeout,
         )
+        .await
+        .map_err(|err| format!("{:?}", err))
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut result = String::new();
+
+        for jump_host in &self.jump_hosts {
+            result.push_str(jump_host.to_string().as_str());
+            result.push_str("->");
+        }
+
+        result.push_str(
+            format!(
+                "{}@{}->{}",
+                self.credentials.get_user_name(),
+                self.credentials.get_host_port_as_string(),
+                self.remote_content.as_str()
+            )
+            .as_str(),
+        );
+
+        result
+    }
+}
+
+fn parse_hops(
+    src: &str,
+    parts: Vec<&str>,
+    ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    variables_reader: VariablesReader,
+) -> Result<SshSessionConfiguration, String> {
+    let mut hops = Vec::with_capacity(parts.len());
+
+    for part in parts {
+        if !part.starts_with(SSH_PREFIX) {
+            return Err(format!(
+                "Invalid ssh configuration '{}'. Each ssh hop must start with '{}'",
+                src, SSH_PREFIX
+            ));
+        }
+
+        let ssh_part = part[SSH_PREFIX.len()..].trim();
+
+        add_hop(
+            ssh_part,
+            ssh_configs,
+            variables_reader,
+            &mut hops,
+            &mut Vec::new(),
+        )?;
+    }
+
+    let (credentials, keep_alive_interval, host_key) = hops.pop().unwrap();
+
+    let mut jump_hosts = Vec::with_capacity(hops.len());
+    let mut jump_hosts_keys = Vec::with_capacity(hops.len());
+
+    for (jump_host, _, jump_host_key) in hops {
+        jump_hosts.push(jump_host);
+        jump_hosts_keys.push(jump_host_key);
+    }
+
+    Ok(SshSessionConfiguration {
+        credentials,
+        jump_hosts,
+        host_keys: Arc::new(SshChainHostKeys {
+            jump_hosts: jump_hosts_keys,
+            host: host_key,
+        }),
+        keep_alive_interval,
+    })
+}
+
+fn add_hop(
+    ssh_part: &str,
+    ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    variables_reader: VariablesReader,
+    hops: &mut Vec<(Arc<SshCredentials>, Option<Duration>, SshHostKeySettings)>,
+    path: &mut Vec<String>,
+) -> Result<(), String> {
+    if path.iter().any(|itm| itm == ssh_part) {
+        return Err(format!(
+            "Circular ssh jump_host reference: {} -> {}",
+            path.join(" -> "),
+            ssh_part
+        ));
+    }
+
+    let ssh_config_settings = match ssh_configs {
+        Some(ssh_configs) => ssh_configs.get(ssh_part),
+        None => None,
+    };
+
+    if let Some(jump_host) = ssh_config_settings.and_then(|itm| itm.jump_host.as_ref()) {
+        let jump_host = jump_host.trim();
+        let jump_host = jump_host
+            .strip_prefix(SSH_PREFIX)
+            .unwrap_or(jump_host)
+            .trim();
+
+        path.push(ssh_part.to_string());
+        add_hop(jump_host, ssh_configs, variables_reader, hops, path)?;
+        path.pop();
+    }
+
+    let (credentials, keep_alive_interval, host_key) =
+        create_credentials(ssh_part, ssh_config_settings, variables_reader)?;
+
+    // Jump host can be specified both in the chain and in the jump_host field
+    if let Some((last, _, _)) = hops.last() {
+        if last.are_same(&credentials) {
+            return Ok(());
+        }
+    }
+
+    hops.push((Arc::new(credentials), keep_alive_interval, host_key));
+
+    Ok(())
+}
+
+fn create_credentials(
+    ssh_part: &str,
+    ssh_config_settings: Option<&SshConfigSettings>,
+    variables_reader: VariablesReader,
+) -> Result<(SshCredentials, Option<Duration>, SshHostKeySettings), String> {
+    if !ssh_part.contains('@') {
+        return Err(format!(
+            "Invalid ssh host '{}'. Format must be user@host[:port]",
+            ssh_part
+        ));
     }
+
+    let (ssh_user_name, ssh_session_host, ssh_session_port) = parse_ssh_part(ssh_part);
+
+    let (ssh_config_option, keep_alive_interval) = match ssh_config_settings {
+        Some(ssh_config_settings) => (
+            ssh_config_settings.get_option(variables_reader)?,
+            ssh_config_settings.get_keep_alive_interval()?,
+        ),
+        None => (SshConfigOption::AsSshAgent, None),
+    };
+
+    let host_key_settings = match ssh_config_settings {
+        Some(ssh_config_settings) => ssh_config_settings.get_host_key_settings(variables_reader)?,
+        None => SshHostKeySettings::default(),
+    };
+
+    println!(
+        "SSH: {} host key policy: {}",
+        ssh_part,
+        host_key_settings.policy.as_str()
+    );
+
+    let credentials = match ssh_config_option {
+        SshConfigOption::AsSshAgent => {
+            println!("SSH: {} using SshAgent for authentication", ssh_part);
+            SshCredentials::SshAgent {
+                ssh_remote_host: ssh_session_host.to_string(),
+                ssh_remote_port: ssh_session_port,
+                ssh_user_name: ssh_user_name.to_string(),
+            }
+        }
+        SshConfigOption::AsPassword(password) => {
+            println!("SSH: {} using Login+Password for authentication", ssh_part);
+            SshCredentials::UserNameAndPassword {
+                ssh_remote_host: ssh_session_host.to_string(),
+                ssh_remote_port: ssh_session_port,
+                ssh_user_name: ssh_user_name.to_string(),
+                password,
+            }
+        }
+        SshConfigOption::AsPrivateKeyFile {
+            file_path,
+            passphrase,
+        } => {
+            println!("SSH: {} using PrivateKey for authentication", ssh_part);
+            SshCredentials::PrivateKey {
+                ssh_remote_host: ssh_session_host.to_string(),
+                ssh_remote_port: ssh_session_port,
+                ssh_user_name: ssh_user_name.to_string(),
+                private_key: load_private_key(&file_path)?,
+                passphrase,
+            }
+        }
+    };
+
+    Ok((credentials, keep_alive_interval, host_key_settings))
 }
 
 fn parse_ssh_part(ssh_part: &str) -> (&str, &str, u16) {
@@ -222,4 +392,123 @@ mod test {
             "/home/user/file.txt"
         );
     }
+
+    #[test]
+    fn test_parse_ssh_configuration_with_jump_hosts() {
+        let config = "ssh:jump@10.0.0.1->ssh:root@12.12.13.13:2222->10.0.0.5:5123";
+
+        let result = super::SshConfiguration::parse(config.into(), &None, (&None).into()).unwrap();
+
+        assert_eq!(result.jump_hosts.len(), 1);
+        assert_eq!(result.jump_hosts[0].get_user_name(), "jump");
+        assert_eq!(
+            result.jump_hosts[0].get_host_port_as_string(),
+            "10.0.0.1:22"
+        );
+
+        assert_eq!(result.credentials.get_user_name(), "root");
+        assert_eq!(
+            result.credentials.get_host_port_as_string(),
+            "12.12.13.13:2222"
+        );
+
+        assert_eq!(
+            result.remote_content.unwrap_as_remote_host().as_str(),
+            "10.0.0.5:5123"
+        );
+    }
+
+    #[test]
+    fn test_jump_host_from_settings() {
+        use std::collections::HashMap;
+
+        use crate::configurations::SshConfigSettings;
+
+        let mut ssh_configs = HashMap::new();
+
+        ssh_configs.insert(
+            "root@12.12.13.13".to_string(),
+            SshConfigSettings {
+                password: None,
+                private_key_file: None,
+                passphrase: None,
+                keep_alive_interval: None,
+                jump_host: Some("ssh:jump@10.0.0.1".to_string()),
+                host_key_policy: None,
+                known_hosts_file: None,
+                host_key_fingerprint: None,
+            },
+        );
+
+        let ssh_configs = Some(ssh_configs);
+
+        let config = "ssh:root@12.12.13.13->10.0.0.5:5123";
+
+        let result =
+            super::SshConfiguration::parse(config.into(), &ssh_configs, (&None).into()).unwrap();
+
+        assert_eq!(result.jump_hosts.len(), 1);
+        assert_eq!(result.jump_hosts[0].get_user_name(), "jump");
+        assert_eq!(result.credentials.get_user_name(), "root");
+    }
+
+    #[test]
+    fn test_host_key_settings_are_kept_per_hop() {
+        use std::collections::HashMap;
+
+        use crate::{configurations::SshConfigSettings, ssh_host_keys::SshHostKeyPolicy};
+
+        let mut ssh_configs = HashMap::new();
+
+        ssh_configs.insert(
+            "jump@10.0.0.1".to_string(),
+            SshConfigSettings {
+                password: None,
+                private_key_file: None,
+                passphrase: None,
+                keep_alive_interval: None,
+                jump_host: None,
+                host_key_policy: Some("off".to_string()),
+                known_hosts_file: None,
+                host_key_fingerprint: None,
+            },
+        );
+
+        ssh_configs.insert(
+            "root@10.0.0.1".to_string(),
+            SshConfigSettings {
+                password: None,
+                private_key_file: None,
+                passphrase: None,
+                keep_alive_interval: None,
+                jump_host: None,
+                host_key_policy: Some("accept-new".to_string()),
+                known_hosts_file: None,
+                host_key_fingerprint: None,
+            },
+        );
+
+        let ssh_configs = Some(ssh_configs);
+
+        let result = super::SshConfiguration::parse(
+            "ssh:jump@10.0.0.1->ssh:user@12.12.13.13->10.0.0.5:5123",
+            &ssh_configs,
+            (&None).into(),
+        )
+        .unwrap();
+
+        assert_eq!(result.host_keys.jump_hosts.len(), 1);
+        assert_eq!(result.host_keys.jump_hosts[0].policy, SshHostKeyPolicy::Off);
+        assert_eq!(result.host_keys.host.policy, SshHostKeyPolicy::Off);
+
+        // Other user of the same host keeps its own policy
+        let result = super::SshConfiguration::parse(
+            "ssh:root@10.0.0.1->10.0.0.5:5123",
+            &ssh_configs,
+            (&None).into(),
+        )
+        .unwrap();
+
+        assert_eq!(result.host_keys.host.policy, SshHostKeyPolicy::AcceptNew);
+    }
 }
@@ -0,0 +1,42 @@
+use std::{sync::Arc, time::Duration};
+
+use my_ssh::SshCredentials;
+
+use crate::ssh_host_keys::SshChainHostKeys;
+
+use super::*;
+
+// Remote ssh server listens at host:port and relays incoming connections back to the target
+pub struct SshReverseEndpointConfig {
+    pub host: EndpointHttpHostString,
+    pub ssh_credentials: Arc<SshCredentials>,
+    pub ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+    pub ssh_host_keys: Arc<SshChainHostKeys>,
+    pub ssh_keep_alive_interval: Option<Duration>,
+    pub target: Arc<RemoteHost>,
+    pub debug: bool,
+}
+
+impl SshReverseEndpointConfig {
+    pub fn get_remote_listen_host(&self) -> &str {
+        let host = self.host.as_str();
+        match host.rfind(':') {
+            Some(index) => &host[..index],
+            None => host,
+        }
+    }
+
+    pub fn get_remote_listen_port(&self) -> u16 {
+        self.host.get_port()
+    }
+
+    pub fn to_string(&self) -> String {
+        format!(
+            "ssh:{} listens {}:{} -> {}",
+            self.ssh_credentials.to_string(),
+            self.get_remote_listen_host(),
+            self.get_remote_listen_port(),
+            self.target.as_str()
+        )
+    }
+}
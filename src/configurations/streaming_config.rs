@@ -0,0 +1,8 @@
+use std::time::Duration;
+
+// Timeouts which are not set are not applied, so long-poll and event-stream responses are not cut off
+#[derive(Debug, Clone)]
+pub struct StreamingConfig {
+    pub header_timeout: Option<Duration>,
+    pub idle_body_timeout: Option<Duration>,
+}
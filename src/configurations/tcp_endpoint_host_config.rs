@@ -1,9 +1,12 @@
+use std::sync::Arc;
+
 use super::*;
-use crate::types::WhiteListedIpList;
+use crate::{rate_limit::ConnectionsLimit, types::WhiteListedIpList};
 
 pub struct TcpEndpointHostConfig {
     pub host: EndpointHttpHostString,
     pub remote_addr: std::net::SocketAddr,
     pub debug: bool,
     pub whitelisted_ip: WhiteListedIpList,
+    pub connections_limit: Option<Arc<ConnectionsLimit>>,
 }
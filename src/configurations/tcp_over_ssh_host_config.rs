@@ -1,12 +1,20 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use my_ssh::SshCredentials;
 
+use crate::ssh_host_keys::SshChainHostKeys;
+
+use crate::rate_limit::ConnectionsLimit;
+
 use super::*;
 
 pub struct TcpOverSshEndpointHostConfig {
     pub host: EndpointHttpHostString,
     pub ssh_credentials: Arc<SshCredentials>,
+    pub ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+    pub ssh_host_keys: Arc<SshChainHostKeys>,
+    pub ssh_keep_alive_interval: Option<Duration>,
     pub remote_host: Arc<RemoteHost>,
     pub debug: bool,
+    pub connections_limit: Option<Arc<ConnectionsLimit>>,
 }
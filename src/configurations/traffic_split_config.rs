@@ -0,0 +1,418 @@
+use std::sync::Arc;
+
+use hyper::{header, HeaderMap, Uri};
+
+use super::ProxyPassLocationConfig;
+
+// Value of the sticky cookie which keeps the client on the proxy_pass_to of the location
+pub const PRIMARY_VARIANT_NAME: &str = "default";
+
+// name - request is matched if it has the value, name=value - if the value is equal
+pub struct TrafficSplitMatch {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl TrafficSplitMatch {
+    pub fn parse(src: &str) -> Self {
+        match src.split_once('=') {
+            Some((name, value)) => Self {
+                name: name.trim().to_string(),
+                value: Some(value.trim().to_string()),
+            },
+            None => Self {
+                name: src.trim().to_string(),
+                value: None,
+            },
+        }
+    }
+
+    fn is_matched(&self, value: Option<&str>) -> bool {
+        match (value, self.value.as_ref()) {
+            (Some(value), Some(expected)) => value == expected.as_str(),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+pub struct TrafficSplitVariant {
+    pub name: String,
+    // Percent of requests which are not matched by the rules
+    pub weight: f64,
+    pub header: Option<TrafficSplitMatch>,
+    pub cookie: Option<TrafficSplitMatch>,
+    pub query: Option<TrafficSplitMatch>,
+    pub config: Arc<ProxyPassLocationConfig>,
+}
+
+impl TrafficSplitVariant {
+    fn is_matched(&self, uri: &Uri, headers: &HeaderMap) -> bool {
+        if let Some(header) = self.header.as_ref() {
+            let value = headers
+                .get(header.name.as_str())
+                .and_then(|value| value.to_str().ok());
+
+            if header.is_matched(value) {
+                return true;
+            }
+        }
+
+        if let Some(cookie) = self.cookie.as_ref() {
+            if cookie.is_matched(get_cookie_value(headers, cookie.name.as_str())) {
+                return true;
+            }
+        }
+
+        if let Some(query) = self.query.as_ref() {
+            if query.is_matched(get_query_value(uri, query.name.as_str())) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+pub struct TrafficSplitConfig {
+    pub variants: Vec<TrafficSplitVariant>,
+    pub sticky_cookie: Option<String>,
+    pub cookie_path: String,
+}
+
+pub struct TrafficSplitSelection {
+    // None - request goes to proxy_pass_to of the location
+    pub variant: Option<usize>,
+    // Set-Cookie value which keeps the client on the selected variant
+    pub set_cookie: Option<String>,
+}
+
+impl TrafficSplitConfig {
+    pub fn select(&self, uri: &Uri, headers: &HeaderMap) -> TrafficSplitSelection {
+        let roll = (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 100.0;
+        self.select_with_roll(uri, headers, roll)
+    }
+
+    // Sticky cookie goes first, then the rules, then the weights
+    fn select_with_roll(&self, uri: &Uri, headers: &HeaderMap, roll: f64) -> TrafficSplitSelection {
+        if let Some(sticky_cookie) = self.sticky_cookie.as_ref() {
+            if let Some(value) = get_cookie_value(headers, sticky_cookie.as_str()) {
+                if value == PRIMARY_VARIANT_NAME {
+                    return TrafficSplitSelection {
+                        variant: None,
+                        set_cookie: None,
+                    };
+                }
+
+                if let Some(index) = self.variants.iter().position(|itm| itm.name == value) {
+                    return TrafficSplitSelection {
+                        variant: Some(index),
+                        set_cookie: None,
+                    };
+                }
+            }
+        }
+
+        if let Some(index) = self
+            .variants
+            .iter()
+            .position(|itm| itm.is_matched(uri, headers))
+        {
+            return TrafficSplitSelection {
+                variant: Some(index),
+                set_cookie: None,
+            };
+        }
+
+        let mut weights_sum = 0.0;
+        let mut variant = None;
+
+        for (index, itm) in self.variants.iter().enumerate() {
+            weights_sum += itm.weight;
+
+            if roll < weights_sum {
+                variant = Some(index);
+                break;
+            }
+        }
+
+        // Client which was not split by weight has nothing to stick to
+        if weights_sum == 0.0 {
+            return TrafficSplitSelection {
+                variant,
+                set_cookie: None,
+            };
+        }
+
+        let set_cookie = self.sticky_cookie.as_ref().map(|sticky_cookie| {
+            let name = match variant {
+                Some(index) => self.variants[index].name.as_str(),
+                None => PRIMARY_VARIANT_NAME,
+            };
+
+            format!("{}={}; Path={}", sticky_cookie, name, self.cookie_path)
+        });
+
+        TrafficSplitSelection {
+            variant,
+            set_cookie,
+        }
+    }
+}
+
+fn get_cookie_value<'s>(headers: &'s HeaderMap, name: &str) -> Option<&'s str> {
+    for cookie_header in headers.get_all(header::COOKIE) {
+        let cookie_header = match cookie_header.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        for itm in cookie_header.split(';') {
+            if let Some((cookie_name, value)) = itm.split_once('=') {
+                if cookie_name.trim() == name {
+                    return Some(value.trim());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn get_query_value<'s>(uri: &'s Uri, name: &str) -> Option<&'s str> {
+    for itm in uri.query()?.split('&') {
+        match itm.split_once('=') {
+            Some((param_name, value)) if param_name == name => return Some(value),
+            None if itm == name => return Some(""),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configurations::{HttpType, RemoteHost},
+        settings::ProxyPassTo,
+        types::WhiteListedIpList,
+    };
+
+    fn create_variant(
+        name: &str,
+        weight: f64,
+        header: Option<&str>,
+        cookie: Option<&str>,
+        query: Option<&str>,
+    ) -> TrafficSplitVariant {
+        let config = ProxyPassLocationConfig::new(
+            0,
+            "/api".to_string(),
+            None,
+            WhiteListedIpList::new(),
+            ProxyPassTo::Http(RemoteHost::new(format!("http://{}:5123", name))),
+            None,
+            HttpType::Http1,
+        );
+
+        TrafficSplitVariant {
+            name: name.to_string(),
+            weight,
+            header: header.map(TrafficSplitMatch::parse),
+            cookie: cookie.map(TrafficSplitMatch::parse),
+            query: query.map(TrafficSplitMatch::parse),
+            config: Arc::new(config),
+        }
+    }
+
+    fn create_split(
+        variants: Vec<TrafficSplitVariant>,
+        sticky_cookie: Option<&str>,
+    ) -> TrafficSplitConfig {
+        TrafficSplitConfig {
+            variants,
+            sticky_cookie: sticky_cookie.map(|itm| itm.to_string()),
+            cookie_path: "/api".to_string(),
+        }
+    }
+
+    fn create_headers(headers: &[(&str, &str)]) -> HeaderMap {
+        let mut result = HeaderMap::new();
+
+        for (name, value) in headers {
+            result.append(
+                header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_weight_boundaries() {
+        let split = create_split(
+            vec![
+                create_variant("canary", 10.0, None, None, None),
+                create_variant("beta", 20.0, None, None, None),
+            ],
+            Some("x-variant"),
+        );
+
+        let uri: Uri = "/api".parse().unwrap();
+        let headers = HeaderMap::new();
+
+        // roll, expected variant, expected cookie value
+        let cases = [
+            (0.0, Some(0), "canary"),
+            (9.99, Some(0), "canary"),
+            (10.0, Some(1), "beta"),
+            (29.99, Some(1), "beta"),
+            (30.0, None, PRIMARY_VARIANT_NAME),
+            (99.99, None, PRIMARY_VARIANT_NAME),
+        ];
+
+        for (roll, variant, cookie_value) in cases {
+            let result = split.select_with_roll(&uri, &headers, roll);
+
+            assert_eq!(variant, result.variant, "roll: {}", roll);
+            assert_eq!(
+                Some(format!("x-variant={}; Path=/api", cookie_value)),
+                result.set_cookie,
+                "roll: {}",
+                roll
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_sticky_cookie_without_weights() {
+        let split = create_split(
+            vec![create_variant("canary", 0.0, Some("x-canary"), None, None)],
+            Some("x-variant"),
+        );
+
+        let uri: Uri = "/api".parse().unwrap();
+        let result = split.select_with_roll(&uri, &HeaderMap::new(), 0.0);
+
+        assert_eq!(None, result.variant);
+        assert_eq!(None, result.set_cookie);
+    }
+
+    #[test]
+    fn test_rules_go_before_weights() {
+        let split = create_split(
+            vec![
+                create_variant("canary", 100.0, None, None, None),
+                create_variant(
+                    "beta",
+                    0.0,
+                    Some("x-beta=1"),
+                    Some("beta-user"),
+                    Some("beta=yes"),
+                ),
+            ],
+            Some("x-variant"),
+        );
+
+        // uri, headers, expected variant
+        let cases: [(&str, Vec<(&str, &str)>, Option<usize>); 6] = [
+            ("/api", vec![], Some(0)),
+            ("/api", vec![("x-beta", "1")], Some(1)),
+            ("/api", vec![("x-beta", "2")], Some(0)),
+            ("/api", vec![("cookie", "a=1; beta-user=john")], Some(1)),
+            ("/api?beta=yes", vec![], Some(1)),
+            ("/api?beta=no", vec![], Some(0)),
+        ];
+
+        for (uri, headers, variant) in cases {
+            let uri: Uri = uri.parse().unwrap();
+            let result = split.select_with_roll(&uri, &create_headers(&headers), 0.0);
+
+            assert_eq!(
+                variant, result.variant,
+                "uri: {} headers: {:?}",
+                uri, headers
+            );
+
+            // Client which is matched by the rules is not made sticky
+            if variant == Some(1) {
+                assert_eq!(None, result.set_cookie);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sticky_cookie_is_reused() {
+        let split = create_split(
+            vec![
+                create_variant("canary", 50.0, None, None, None),
+                create_variant("beta", 50.0, Some("x-beta"), None, None),
+            ],
+            Some("x-variant"),
+        );
+
+        let uri: Uri = "/api".parse().unwrap();
+
+        // cookie, headers, roll, expected variant, expected set_cookie
+        let cases: [(&str, Vec<(&str, &str)>, f64, Option<usize>, Option<&str>); 5] = [
+            ("x-variant=beta", vec![], 0.0, Some(1), None),
+            (
+                "x-variant=canary",
+                vec![("x-beta", "1")],
+                99.0,
+                Some(0),
+                None,
+            ),
+            ("x-variant=default", vec![("x-beta", "1")], 0.0, None, None),
+            (
+                "x-variant=removed",
+                vec![],
+                0.0,
+                Some(0),
+                Some("x-variant=canary; Path=/api"),
+            ),
+            (
+                "other=beta",
+                vec![],
+                60.0,
+                Some(1),
+                Some("x-variant=beta; Path=/api"),
+            ),
+        ];
+
+        for (cookie, mut headers, roll, variant, set_cookie) in cases {
+            headers.push(("cookie", cookie));
+
+            let result = split.select_with_roll(&uri, &create_headers(&headers), roll);
+
+            assert_eq!(variant, result.variant, "cookie: {}", cookie);
+            assert_eq!(
+                set_cookie.map(|itm| itm.to_string()),
+                result.set_cookie,
+                "cookie: {}",
+                cookie
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_cookie_and_query_values() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "a=1; x-variant=canary".parse().unwrap());
+
+        assert_eq!(Some("canary"), get_cookie_value(&headers, "x-variant"));
+        assert_eq!(None, get_cookie_value(&headers, "b"));
+
+        let uri: Uri = "/api?canary=1&debug".parse().unwrap();
+        assert_eq!(Some("1"), get_query_value(&uri, "canary"));
+        assert_eq!(Some(""), get_query_value(&uri, "debug"));
+        assert_eq!(None, get_query_value(&uri, "other"));
+
+        let rule = TrafficSplitMatch::parse("canary=1");
+        assert!(rule.is_matched(get_query_value(&uri, "canary")));
+        assert!(!TrafficSplitMatch::parse("canary=2").is_matched(Some("1")));
+    }
+}
@@ -0,0 +1,13 @@
+use std::{sync::Arc, time::Duration};
+
+use super::*;
+use crate::{rate_limit::ConnectionsLimit, types::WhiteListedIpList};
+
+pub struct UdpEndpointHostConfig {
+    pub host: EndpointHttpHostString,
+    pub remote_addr: std::net::SocketAddr,
+    pub idle_timeout: Duration,
+    pub debug: bool,
+    pub whitelisted_ip: WhiteListedIpList,
+    pub connections_limit: Option<Arc<ConnectionsLimit>>,
+}
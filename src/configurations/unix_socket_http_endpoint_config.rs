@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use super::*;
+
+pub struct UnixSocketHttpEndpointConfig {
+    pub unix_socket_path: String,
+    pub permissions: Option<u32>,
+    pub endpoint_info: Arc<HttpEndpointInfo>,
+}
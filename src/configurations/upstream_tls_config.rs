@@ -0,0 +1,182 @@
+use std::{fmt::Debug, sync::Arc};
+
+use my_tls::ROOT_CERT_STORE;
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+pub const ALPN_H2: &[u8] = b"h2";
+pub const ALPN_HTTP1: &[u8] = b"http/1.1";
+
+pub struct UpstreamTlsClientCertificate {
+    pub certificates: Vec<u8>,
+    pub private_key: Vec<u8>,
+    pub private_key_file_name: String,
+}
+
+pub struct UpstreamTlsConfig {
+    pub client_config: Arc<ClientConfig>,
+    pub sni: Option<String>,
+}
+
+impl UpstreamTlsConfig {
+    pub fn new(
+        ca: Option<Vec<u8>>,
+        client_certificate: Option<UpstreamTlsClientCertificate>,
+        sni: Option<String>,
+        insecure_skip_verify: bool,
+        http2: bool,
+    ) -> Result<Self, String> {
+        let builder = ClientConfig::builder();
+
+        let builder = if insecure_skip_verify {
+            let verifier = InsecureServerCertVerifier {
+                provider: builder.crypto_provider().clone(),
+            };
+
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(verifier))
+        } else {
+            match ca {
+                Some(ca) => {
+                    let mut root_cert_store = RootCertStore::empty();
+
+                    let certificates = crate::ssl::certificates::load_certs(ca);
+
+                    if certificates.is_empty() {
+                        return Err("Upstream CA bundle has no certificates".to_string());
+                    }
+
+                    for certificate in certificates {
+                        if let Err(err) = root_cert_store.add(certificate) {
+                            return Err(format!(
+                                "Can not add upstream CA certificate. Err: {}",
+                                err
+                            ));
+                        }
+                    }
+
+                    builder.with_root_certificates(root_cert_store)
+                }
+                None => builder.with_root_certificates(ROOT_CERT_STORE.clone()),
+            }
+        };
+
+        let mut client_config = match client_certificate {
+            Some(client_certificate) => {
+                let private_key = crate::ssl::certificates::load_private_key(
+                    client_certificate.private_key,
+                    &client_certificate.private_key_file_name,
+                );
+
+                let certificates =
+                    crate::ssl::certificates::load_certs(client_certificate.certificates);
+
+                match builder.with_client_auth_cert(certificates, private_key) {
+                    Ok(client_config) => client_config,
+                    Err(err) => {
+                        return Err(format!("Invalid upstream client certificate. Err: {}", err))
+                    }
+                }
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        client_config.alpn_protocols = get_alpn_protocols(http2);
+
+        if let Some(sni) = sni.as_ref() {
+            if ServerName::try_from(sni.to_string()).is_err() {
+                return Err(format!("Invalid upstream sni value: '{}'", sni));
+            }
+        }
+
+        Ok(Self {
+            client_config: Arc::new(client_config),
+            sni,
+        })
+    }
+
+    // Used for https upstream without tls settings
+    pub fn create_default(http2: bool) -> Self {
+        let mut client_config = ClientConfig::builder()
+            .with_root_certificates(ROOT_CERT_STORE.clone())
+            .with_no_client_auth();
+
+        client_config.alpn_protocols = get_alpn_protocols(http2);
+
+        Self {
+            client_config: Arc::new(client_config),
+            sni: None,
+        }
+    }
+}
+
+fn get_alpn_protocols(http2: bool) -> Vec<Vec<u8>> {
+    if http2 {
+        vec![ALPN_H2.to_vec()]
+    } else {
+        vec![ALPN_HTTP1.to_vec()]
+    }
+}
+
+// Accepts any server certificate. Signatures are still verified, so the handshake is not broken
+struct InsecureServerCertVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl Debug for InsecureServerCertVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InsecureServerCertVerifier").finish()
+    }
+}
+
+impl ServerCertVerifier for InsecureServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
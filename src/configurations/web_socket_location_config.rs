@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use hyper_tungstenite::tungstenite::protocol::WebSocketConfig;
+
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketLocationConfig {
+    pub idle_timeout: Option<Duration>,
+    pub ping_interval: Option<Duration>,
+    pub max_message_size: Option<usize>,
+}
+
+impl WebSocketLocationConfig {
+    // Same limits are applied to the client and to the upstream side of the connection
+    pub fn get_protocol_config(&self) -> WebSocketConfig {
+        let mut result = WebSocketConfig::default();
+
+        if let Some(max_message_size) = self.max_message_size {
+            result.max_message_size = Some(max_message_size);
+
+            if let Some(max_frame_size) = result.max_frame_size {
+                result.max_frame_size = Some(max_frame_size.min(max_message_size));
+            }
+        }
+
+        result
+    }
+}
@@ -7,6 +7,9 @@ use crate::{app::AppContext, configurations::*, crl::ListOfCrl, files_cache::Fil
 pub async fn get_and_check_app_config(app: &AppContext) -> Result<AppConfiguration, String> {
     let settings_model = crate::settings::SettingsModel::load(".my-reverse-proxy").await?;
     let listen_ports = settings_model.get_listen_ports(app).await?;
+    let unix_socket_http_endpoints = settings_model.get_unix_socket_http_endpoints(app).await?;
+    let ssh_reverse_endpoints = settings_model.get_ssh_reverse_endpoints()?;
+    let mime_types = settings_model.get_mime_types()?;
 
     let mut ssl_certificates_cache = SslCertificatesCache::new();
 
@@ -18,6 +21,12 @@ pub async fn get_and_check_app_config(app: &AppContext) -> Result<AppConfigurati
 
     let mut tcp_over_ssh_endpoints = BTreeMap::new();
 
+    let mut socks5_endpoints = BTreeMap::new();
+
+    let mut http_forward_proxy_endpoints = BTreeMap::new();
+
+    let mut udp_endpoints = BTreeMap::new();
+
     let crl = settings_model.get_crl()?;
 
     let files_cache = FilesCache::new();
@@ -66,6 +75,15 @@ pub async fn get_and_check_app_config(app: &AppContext) -> Result<AppConfigurati
             crate::configurations::ListenPortConfiguration::TcpOverSsh(port_config) => {
                 tcp_over_ssh_endpoints.insert(listen_port, port_config);
             }
+            crate::configurations::ListenPortConfiguration::Socks5(port_config) => {
+                socks5_endpoints.insert(listen_port, port_config);
+            }
+            crate::configurations::ListenPortConfiguration::HttpForwardProxy(port_config) => {
+                http_forward_proxy_endpoints.insert(listen_port, port_config);
+            }
+            crate::configurations::ListenPortConfiguration::Udp(port_config) => {
+                udp_endpoints.insert(listen_port, port_config);
+            }
         }
     }
 
@@ -75,9 +93,15 @@ pub async fn get_and_check_app_config(app: &AppContext) -> Result<AppConfigurati
         http_endpoints,
         tcp_endpoints,
         tcp_over_ssh_endpoints,
+        socks5_endpoints,
+        http_forward_proxy_endpoints,
+        udp_endpoints,
+        unix_socket_http_endpoints,
+        ssh_reverse_endpoints,
         ssl_certificates_cache,
         client_certificates_cache,
         crl,
         list_of_crl: Mutex::new(list_of_crl),
+        mime_types,
     })
 }
@@ -35,4 +35,38 @@ pub async fn kick_off_endpoints(app: &Arc<AppContext>) {
             port_configuration.clone(),
         );
     }
+
+    for (listen_port, port_configuration) in &app_configuration.socks5_endpoints {
+        let listen_end_point = std::net::SocketAddr::new([0, 0, 0, 0].into(), *listen_port);
+
+        crate::socks5::start_socks5(app.clone(), listen_end_point, port_configuration.clone());
+    }
+
+    for (listen_port, port_configuration) in &app_configuration.http_forward_proxy_endpoints {
+        let listen_end_point = std::net::SocketAddr::new([0, 0, 0, 0].into(), *listen_port);
+
+        crate::http_server::start_http_forward_proxy_server(
+            listen_end_point,
+            app.clone(),
+            port_configuration.clone(),
+        );
+    }
+
+    for (listen_port, port_configuration) in &app_configuration.udp_endpoints {
+        let listen_end_point = std::net::SocketAddr::new([0, 0, 0, 0].into(), *listen_port);
+
+        crate::udp_port_forward::start_udp(
+            app.clone(),
+            listen_end_point,
+            port_configuration.clone(),
+        );
+    }
+
+    for endpoint_config in app_configuration.unix_socket_http_endpoints.values() {
+        crate::http_server::start_unix_socket_http_server(app.clone(), endpoint_config.clone());
+    }
+
+    for endpoint_info in &app_configuration.ssh_reverse_endpoints {
+        crate::ssh_reverse::start_ssh_reverse(app.clone(), endpoint_info.clone());
+    }
 }
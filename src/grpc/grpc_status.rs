@@ -0,0 +1,44 @@
+use hyper::{header, HeaderMap};
+
+pub const GRPC_CONTENT_TYPE: &str = "application/grpc";
+
+// https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+#[derive(Debug, Clone, Copy)]
+pub enum GrpcStatus {
+    Unknown,
+    DeadlineExceeded,
+    PermissionDenied,
+    ResourceExhausted,
+    Unimplemented,
+    Internal,
+    Unavailable,
+    Unauthenticated,
+}
+
+impl GrpcStatus {
+    pub fn as_code(&self) -> u8 {
+        match self {
+            GrpcStatus::Unknown => 2,
+            GrpcStatus::DeadlineExceeded => 4,
+            GrpcStatus::PermissionDenied => 7,
+            GrpcStatus::ResourceExhausted => 8,
+            GrpcStatus::Unimplemented => 12,
+            GrpcStatus::Internal => 13,
+            GrpcStatus::Unavailable => 14,
+            GrpcStatus::Unauthenticated => 16,
+        }
+    }
+}
+
+// Matches application/grpc, application/grpc+proto, application/grpc-web-text and so on
+pub fn is_grpc_request(headers: &HeaderMap) -> bool {
+    match get_grpc_content_type(headers) {
+        Some(content_type) => content_type.starts_with(GRPC_CONTENT_TYPE),
+        None => false,
+    }
+}
+
+pub fn get_grpc_content_type(headers: &HeaderMap) -> Option<&str> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    Some(content_type.split(';').next()?.trim())
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use hyper::HeaderMap;
+
+pub const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+// Format is up to 8 digits followed by the unit: H, M, S, m (millis), u (micros), n (nanos)
+pub fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+
+    let (amount, unit) = value.split_at(value.len() - 1);
+
+    if !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let amount: u64 = amount.parse().ok()?;
+
+    let result = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+pub fn get_grpc_timeout(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(GRPC_TIMEOUT_HEADER)?.to_str().ok()?;
+    parse_grpc_timeout(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_grpc_timeout("2M"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_grpc_timeout("30S"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_grpc_timeout("250m"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_grpc_timeout("100u"), Some(Duration::from_micros(100)));
+        assert_eq!(
+            parse_grpc_timeout("99999999n"),
+            Some(Duration::from_nanos(99999999))
+        );
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("10s"), None);
+        assert_eq!(parse_grpc_timeout("-1S"), None);
+    }
+}
@@ -0,0 +1,218 @@
+use base64::Engine;
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::SinkExt;
+use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
+use hyper::{
+    body::Frame,
+    header::{self, HeaderValue},
+    HeaderMap,
+};
+
+use super::GRPC_CONTENT_TYPE;
+
+pub const GRPC_WEB_CONTENT_TYPE: &str = "application/grpc-web";
+pub const GRPC_WEB_TEXT_CONTENT_TYPE: &str = "application/grpc-web-text";
+
+// Trailers are sent as the last message of the body, marked with the most significant bit of the flags byte
+const GRPC_WEB_TRAILERS_FLAG: u8 = 0x80;
+
+const GRPC_WEB_CHANNEL_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrpcWebMode {
+    Binary,
+    // Body is base64 encoded in both directions
+    Text,
+}
+
+impl GrpcWebMode {
+    pub fn detect(headers: &HeaderMap) -> Option<Self> {
+        let content_type = super::get_grpc_content_type(headers)?;
+
+        if content_type.starts_with(GRPC_WEB_TEXT_CONTENT_TYPE) {
+            return Some(Self::Text);
+        }
+
+        if content_type.starts_with(GRPC_WEB_CONTENT_TYPE) {
+            return Some(Self::Binary);
+        }
+
+        None
+    }
+
+    fn get_content_type_prefix(&self) -> &'static str {
+        match self {
+            GrpcWebMode::Binary => GRPC_WEB_CONTENT_TYPE,
+            GrpcWebMode::Text => GRPC_WEB_TEXT_CONTENT_TYPE,
+        }
+    }
+
+    // application/grpc-web-text+proto -> application/grpc+proto
+    pub fn to_grpc_content_type(&self, content_type: &str) -> Option<String> {
+        let suffix = content_type.strip_prefix(self.get_content_type_prefix())?;
+        Some(format!("{}{}", GRPC_CONTENT_TYPE, suffix))
+    }
+
+    // application/grpc+proto -> application/grpc-web-text+proto
+    pub fn to_grpc_web_content_type(&self, content_type: &str) -> Option<String> {
+        let suffix = content_type.strip_prefix(GRPC_CONTENT_TYPE)?;
+
+        if !suffix.is_empty() && !suffix.starts_with('+') && !suffix.starts_with(';') {
+            return None;
+        }
+
+        Some(format!("{}{}", self.get_content_type_prefix(), suffix))
+    }
+
+    pub fn decode_request_body(&self, body: Bytes) -> Result<Bytes, String> {
+        match self {
+            GrpcWebMode::Binary => Ok(body),
+            GrpcWebMode::Text => {
+                match base64::engine::general_purpose::STANDARD.decode(body.as_ref()) {
+                    Ok(result) => Ok(result.into()),
+                    Err(err) => Err(format!("Invalid grpc-web-text body. Err: {}", err)),
+                }
+            }
+        }
+    }
+
+    fn encode_response_chunk(&self, chunk: Bytes) -> Bytes {
+        match self {
+            GrpcWebMode::Binary => chunk,
+            GrpcWebMode::Text => base64::engine::general_purpose::STANDARD
+                .encode(chunk)
+                .into_bytes()
+                .into(),
+        }
+    }
+}
+
+pub fn translate_grpc_web_request_headers(headers: &mut HeaderMap, mode: GrpcWebMode) {
+    let content_type = super::get_grpc_content_type(headers)
+        .and_then(|content_type| mode.to_grpc_content_type(content_type));
+
+    if let Some(content_type) = content_type {
+        if let Ok(content_type) = content_type.parse() {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+    }
+
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    headers.remove(header::CONTENT_LENGTH);
+}
+
+// Upstream trailers can not reach the browser, so they are moved into the body
+pub fn translate_grpc_web_response(
+    response: hyper::Response<BoxBody<Bytes, String>>,
+    mode: GrpcWebMode,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let (mut parts, body) = response.into_parts();
+
+    let content_type = super::get_grpc_content_type(&parts.headers)
+        .and_then(|content_type| mode.to_grpc_web_content_type(content_type));
+
+    let content_type = match content_type {
+        Some(content_type) => content_type,
+        None => return hyper::Response::from_parts(parts, body),
+    };
+
+    if let Ok(content_type) = content_type.parse() {
+        parts.headers.insert(header::CONTENT_TYPE, content_type);
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    hyper::Response::from_parts(parts, translate_grpc_web_body(body, mode))
+}
+
+fn translate_grpc_web_body(
+    mut body: BoxBody<Bytes, String>,
+    mode: GrpcWebMode,
+) -> BoxBody<Bytes, String> {
+    let (mut sender, receiver) = futures::channel::mpsc::channel(GRPC_WEB_CHANNEL_SIZE);
+
+    tokio::spawn(async move {
+        while let Some(frame) = body.frame().await {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(err) => {
+                    let _ = sender.send(Err(err)).await;
+                    return;
+                }
+            };
+
+            let chunk = match frame.into_data() {
+                Ok(data) => data,
+                Err(frame) => match frame.into_trailers() {
+                    Ok(trailers) => encode_trailers_frame(&trailers),
+                    Err(_) => continue,
+                },
+            };
+
+            let frame = Frame::data(mode.encode_response_chunk(chunk));
+
+            // Client is gone
+            if sender.send(Ok(frame)).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    StreamBody::new(receiver).boxed()
+}
+
+pub fn encode_trailers_frame(trailers: &HeaderMap) -> Bytes {
+    let mut payload = String::new();
+
+    for (name, value) in trailers {
+        if let Ok(value) = value.to_str() {
+            payload.push_str(name.as_str());
+            payload.push(':');
+            payload.push_str(value);
+            payload.push_str("\r\n");
+        }
+    }
+
+    let mut result = BytesMut::with_capacity(5 + payload.len());
+    result.put_u8(GRPC_WEB_TRAILERS_FLAG);
+    result.put_u32(payload.len() as u32);
+    result.put_slice(payload.as_bytes());
+
+    result.freeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grpc_web_translation() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            "application/grpc-web-text+proto".parse().unwrap(),
+        );
+
+        let mode = GrpcWebMode::detect(&headers).unwrap();
+        assert_eq!(mode, GrpcWebMode::Text);
+
+        translate_grpc_web_request_headers(&mut headers, mode);
+        assert_eq!(
+            headers.get(header::CONTENT_TYPE).unwrap(),
+            "application/grpc+proto"
+        );
+        assert_eq!(headers.get(header::TE).unwrap(), "trailers");
+
+        assert_eq!(
+            mode.to_grpc_web_content_type("application/grpc").as_deref(),
+            Some("application/grpc-web-text")
+        );
+        assert_eq!(mode.to_grpc_web_content_type("application/grpcx"), None);
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+
+        let frame = encode_trailers_frame(&trailers);
+        assert_eq!(frame.as_ref(), b"\x80\x00\x00\x00\x0fgrpc-status:0\r\n");
+    }
+}
@@ -0,0 +1,6 @@
+mod grpc_status;
+pub use grpc_status::*;
+mod grpc_timeout;
+pub use grpc_timeout::*;
+mod grpc_web;
+pub use grpc_web::*;
@@ -0,0 +1,134 @@
+use std::time::{Duration, SystemTime};
+
+use hyper::{header, HeaderMap};
+
+#[derive(Debug, Default)]
+pub struct CacheControl {
+    pub no_store: bool,
+    pub no_cache: bool,
+    pub private: bool,
+    pub public: bool,
+    pub must_revalidate: bool,
+    pub max_age: Option<Duration>,
+    pub s_max_age: Option<Duration>,
+    pub stale_if_error: Option<Duration>,
+}
+
+impl CacheControl {
+    pub fn parse(headers: &HeaderMap) -> Self {
+        let mut result = Self::default();
+
+        for value in headers.get_all(header::CACHE_CONTROL) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            for directive in value.split(',') {
+                let mut parts = directive.splitn(2, '=');
+                let name = parts.next().unwrap().trim().to_lowercase();
+                let argument = parts.next().map(|itm| itm.trim().trim_matches('"'));
+
+                match name.as_str() {
+                    "no-store" => result.no_store = true,
+                    // no-cache="header" only limits the headers. Whole response is revalidated to be safe
+                    "no-cache" => result.no_cache = true,
+                    "private" => result.private = true,
+                    "public" => result.public = true,
+                    "must-revalidate" | "proxy-revalidate" => result.must_revalidate = true,
+                    "max-age" => result.max_age = parse_seconds(argument),
+                    "s-maxage" => result.s_max_age = parse_seconds(argument),
+                    "stale-if-error" => result.stale_if_error = parse_seconds(argument),
+                    _ => {}
+                }
+            }
+        }
+
+        result
+    }
+}
+
+fn parse_seconds(src: Option<&str>) -> Option<Duration> {
+    let seconds = src?.parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+// Shared cache freshness lifetime. RFC 9111 4.2.1
+pub fn get_freshness_lifetime(
+    headers: &HeaderMap,
+    cache_control: &CacheControl,
+) -> Option<Duration> {
+    if let Some(s_max_age) = cache_control.s_max_age {
+        return Some(s_max_age);
+    }
+
+    if let Some(max_age) = cache_control.max_age {
+        return Some(max_age);
+    }
+
+    let expires = get_http_date(headers, header::EXPIRES.as_str());
+
+    // Invalid Expires value means already expired
+    if headers.contains_key(header::EXPIRES) && expires.is_none() {
+        return Some(Duration::ZERO);
+    }
+
+    let expires = expires?;
+    let date = get_http_date(headers, header::DATE.as_str()).unwrap_or_else(SystemTime::now);
+
+    Some(expires.duration_since(date).unwrap_or(Duration::ZERO))
+}
+
+pub fn get_http_date(headers: &HeaderMap, name: &str) -> Option<SystemTime> {
+    let value = headers.get(name)?.to_str().ok()?;
+    httpdate::parse_http_date(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hyper::{header, HeaderMap};
+
+    use super::*;
+
+    #[test]
+    fn test_freshness_lifetime() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CACHE_CONTROL,
+            "public, max-age=60, s-maxage=\"120\", stale-if-error=30"
+                .parse()
+                .unwrap(),
+        );
+
+        let cache_control = CacheControl::parse(&headers);
+        assert!(cache_control.public);
+        assert_eq!(Some(Duration::from_secs(30)), cache_control.stale_if_error);
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            get_freshness_lifetime(&headers, &cache_control)
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::DATE,
+            "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap(),
+        );
+        headers.insert(
+            header::EXPIRES,
+            "Sun, 06 Nov 1994 08:59:37 GMT".parse().unwrap(),
+        );
+        let cache_control = CacheControl::parse(&headers);
+        assert_eq!(
+            Some(Duration::from_secs(600)),
+            get_freshness_lifetime(&headers, &cache_control)
+        );
+
+        headers.insert(header::EXPIRES, "0".parse().unwrap());
+        assert_eq!(
+            Some(Duration::ZERO),
+            get_freshness_lifetime(&headers, &cache_control)
+        );
+    }
+}
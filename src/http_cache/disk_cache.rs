@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use super::HttpCacheEntry;
+
+const CACHE_FILE_EXTENSION: &str = "cache";
+
+pub struct DiskCache {
+    path: String,
+    max_size: u64,
+    size: AtomicU64,
+}
+
+impl DiskCache {
+    // Files of the previous run are kept. They have their own freshness and are validated on read
+    pub fn new(path: String, max_size: u64) -> Result<Self, String> {
+        if let Err(err) = std::fs::create_dir_all(path.as_str()) {
+            return Err(format!(
+                "Can not create cache directory {}. Err: {}",
+                path, err
+            ));
+        }
+
+        let mut size = 0;
+
+        if let Ok(read_dir) = std::fs::read_dir(path.as_str()) {
+            for dir_entry in read_dir.flatten() {
+                if let Ok(metadata) = dir_entry.metadata() {
+                    if metadata.is_file() {
+                        size += metadata.len();
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            max_size,
+            size: AtomicU64::new(size),
+        })
+    }
+
+    pub async fn get(&self, key: &str) -> Option<HttpCacheEntry> {
+        let content = tokio::fs::read(self.get_file_name(key)).await.ok()?;
+        let entry = HttpCacheEntry::deserialize(content)?;
+
+        // Hash collision
+        if entry.key != key {
+            return None;
+        }
+
+        Some(entry)
+    }
+
+    pub async fn store(&self, entry: &HttpCacheEntry) {
+        let file_name = self.get_file_name(&entry.key);
+        let content = entry.serialize();
+
+        let prev_size = match tokio::fs::metadata(file_name.as_str()).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        // Readers never see half written file
+        let tmp_file_name = format!("{}.tmp", file_name);
+
+        if let Err(err) = tokio::fs::write(tmp_file_name.as_str(), &content).await {
+            println!("Can not write cache file {}. Err: {}", tmp_file_name, err);
+            return;
+        }
+
+        if let Err(err) = tokio::fs::rename(tmp_file_name.as_str(), file_name.as_str()).await {
+            println!("Can not write cache file {}. Err: {}", file_name, err);
+            let _ = tokio::fs::remove_file(tmp_file_name.as_str()).await;
+            return;
+        }
+
+        self.size.fetch_add(content.len() as u64, Ordering::SeqCst);
+        self.sub_size(prev_size);
+
+        if self.size.load(Ordering::SeqCst) > self.max_size {
+            self.evict().await;
+        }
+    }
+
+    pub async fn remove(&self, key: &str) {
+        self.remove_file(self.get_file_name(key).as_str()).await;
+    }
+
+    // Removes the entries which path starts with the prefix. Returns amount of removed entries
+    pub async fn purge(&self, path_prefix: Option<&str>) -> usize {
+        let mut result = 0;
+
+        for (file_name, _, _) in self.get_files().await {
+            if let Some(path_prefix) = path_prefix {
+                let entry = match tokio::fs::read(file_name.as_str()).await {
+                    Ok(content) => HttpCacheEntry::deserialize(content),
+                    Err(_) => continue,
+                };
+
+                if let Some(entry) = entry {
+                    if !entry.path.starts_with(path_prefix) {
+                        continue;
+                    }
+                }
+            }
+
+            self.remove_file(file_name.as_str()).await;
+            result += 1;
+        }
+
+        result
+    }
+
+    // Least recently written files are removed until the cache fits into 90% of max size
+    async fn evict(&self) {
+        let mut files = self.get_files().await;
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let target_size = self.max_size / 10 * 9;
+
+        for (file_name, _, _) in files {
+            if self.size.load(Ordering::SeqCst) <= target_size {
+                break;
+            }
+
+            self.remove_file(file_name.as_str()).await;
+        }
+    }
+
+    async fn get_files(&self) -> Vec<(String, u64, std::time::SystemTime)> {
+        let mut result = Vec::new();
+
+        let mut read_dir = match tokio::fs::read_dir(self.path.as_str()).await {
+            Ok(read_dir) => read_dir,
+            Err(_) => return result,
+        };
+
+        while let Ok(Some(dir_entry)) = read_dir.next_entry().await {
+            let file_name = dir_entry.path();
+
+            if file_name.extension().and_then(|itm| itm.to_str()) != Some(CACHE_FILE_EXTENSION) {
+                continue;
+            }
+
+            if let Ok(metadata) = dir_entry.metadata().await {
+                let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+                result.push((
+                    file_name.to_string_lossy().to_string(),
+                    metadata.len(),
+                    modified,
+                ));
+            }
+        }
+
+        result
+    }
+
+    async fn remove_file(&self, file_name: &str) {
+        let size = match tokio::fs::metadata(file_name).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return,
+        };
+
+        if tokio::fs::remove_file(file_name).await.is_ok() {
+            self.sub_size(size);
+        }
+    }
+
+    fn sub_size(&self, size: u64) {
+        let _ = self
+            .size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |value| {
+                Some(value.saturating_sub(size))
+            });
+    }
+
+    fn get_file_name(&self, key: &str) -> String {
+        let hash = Sha256::digest(key.as_bytes());
+
+        let mut result = String::with_capacity(self.path.len() + 72);
+        result.push_str(self.path.as_str());
+        if !self.path.ends_with('/') {
+            result.push('/');
+        }
+
+        for b in hash.iter() {
+            result.push_str(format!("{:02x}", b).as_str());
+        }
+
+        result.push('.');
+        result.push_str(CACHE_FILE_EXTENSION);
+        result
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.size.load(Ordering::SeqCst)
+    }
+}
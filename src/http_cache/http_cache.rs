@@ -0,0 +1,299 @@
+use std::{collections::HashMap, sync::Arc, sync::Mutex, time::Duration, time::SystemTime};
+
+use hyper::HeaderMap;
+
+use super::{DiskCache, HttpCacheEntry};
+
+pub struct HttpCacheParams {
+    pub max_memory_size: u64,
+    pub max_entry_size: u64,
+    pub stale_if_error: Duration,
+}
+
+struct MemoryCacheItem {
+    entry: Arc<HttpCacheEntry>,
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct MemoryCache {
+    // Key -> variants of the response which differ by Vary headers
+    entries: HashMap<String, Vec<MemoryCacheItem>>,
+    size: u64,
+    access_counter: u64,
+}
+
+impl MemoryCache {
+    fn get(&mut self, key: &str, req_headers: &HeaderMap) -> Option<Arc<HttpCacheEntry>> {
+        self.access_counter += 1;
+        let access_counter = self.access_counter;
+
+        let item = self
+            .entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|itm| itm.entry.matches_vary(req_headers))?;
+
+        item.last_access = access_counter;
+        Some(item.entry.clone())
+    }
+
+    fn insert(&mut self, entry: Arc<HttpCacheEntry>) {
+        self.access_counter += 1;
+
+        let variants = self.entries.entry(entry.key.clone()).or_default();
+
+        if let Some(index) = variants.iter().position(|itm| itm.entry.vary == entry.vary) {
+            let removed = variants.remove(index);
+            self.size = self.size.saturating_sub(removed.entry.get_size());
+        }
+
+        self.size += entry.get_size();
+
+        variants.push(MemoryCacheItem {
+            entry,
+            last_access: self.access_counter,
+        });
+    }
+
+    fn remove(&mut self, key: &str) -> usize {
+        let variants = match self.entries.remove(key) {
+            Some(variants) => variants,
+            None => return 0,
+        };
+
+        for item in &variants {
+            self.size = self.size.saturating_sub(item.entry.get_size());
+        }
+
+        variants.len()
+    }
+
+    // Least recently used entries are evicted until the cache fits into max_size
+    fn evict(&mut self, max_size: u64) -> Vec<Arc<HttpCacheEntry>> {
+        let mut result = Vec::new();
+
+        if self.size <= max_size {
+            return result;
+        }
+
+        let mut items: Vec<(u64, String, usize)> = Vec::new();
+        for (key, variants) in &self.entries {
+            for (index, item) in variants.iter().enumerate() {
+                items.push((item.last_access, key.clone(), index));
+            }
+        }
+
+        items.sort_by_key(|itm| itm.0);
+
+        let mut to_remove: Vec<(String, usize)> = Vec::new();
+        let mut size = self.size;
+
+        for (_, key, index) in items {
+            if size <= max_size {
+                break;
+            }
+
+            size = size.saturating_sub(self.entries[&key][index].entry.get_size());
+            to_remove.push((key, index));
+        }
+
+        // Indexes are removed from the end, so the lower ones stay valid
+        to_remove.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        for (key, index) in to_remove {
+            if let Some(variants) = self.entries.get_mut(&key) {
+                let removed = variants.remove(index);
+                self.size = self.size.saturating_sub(removed.entry.get_size());
+                result.push(removed.entry);
+
+                if variants.is_empty() {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+pub struct HttpCache {
+    pub params: HttpCacheParams,
+    memory: Mutex<MemoryCache>,
+    disk: Option<DiskCache>,
+}
+
+impl HttpCache {
+    pub fn new(params: HttpCacheParams, disk: Option<DiskCache>) -> Self {
+        Self {
+            params,
+            memory: Mutex::new(MemoryCache::default()),
+            disk,
+        }
+    }
+
+    pub async fn get(&self, key: &str, req_headers: &HeaderMap) -> Option<Arc<HttpCacheEntry>> {
+        let now = SystemTime::now();
+
+        let entry = self.memory.lock().unwrap().get(key, req_headers);
+
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                let entry = self.disk.as_ref()?.get(key).await?;
+
+                if !entry.matches_vary(req_headers) {
+                    return None;
+                }
+
+                let entry = Arc::new(entry);
+                if entry.get_size() <= self.params.max_memory_size {
+                    self.insert_to_memory(entry.clone()).await;
+                }
+                entry
+            }
+        };
+
+        // Response which can be neither revalidated nor served on error is useless
+        if !entry.is_fresh(now) && !entry.has_validators() && !entry.can_serve_stale_on_error(now) {
+            self.remove(key).await;
+            return None;
+        }
+
+        Some(entry)
+    }
+
+    pub async fn store(&self, entry: HttpCacheEntry) {
+        if entry.get_size() > self.params.max_entry_size {
+            return;
+        }
+
+        self.insert_to_memory(Arc::new(entry)).await;
+    }
+
+    pub async fn remove(&self, key: &str) {
+        self.memory.lock().unwrap().remove(key);
+
+        if let Some(disk) = self.disk.as_ref() {
+            disk.remove(key).await;
+        }
+    }
+
+    // Removes the entries which path starts with the prefix. Returns amount of removed entries
+    pub async fn purge(&self, path_prefix: Option<&str>) -> usize {
+        let mut result = 0;
+
+        {
+            let mut memory = self.memory.lock().unwrap();
+
+            let keys: Vec<String> = memory
+                .entries
+                .iter()
+                .filter(|(_, variants)| match path_prefix {
+                    Some(path_prefix) => variants
+                        .iter()
+                        .any(|itm| itm.entry.path.starts_with(path_prefix)),
+                    None => true,
+                })
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            for key in keys {
+                result += memory.remove(key.as_str());
+            }
+        }
+
+        if let Some(disk) = self.disk.as_ref() {
+            result += disk.purge(path_prefix).await;
+        }
+
+        result
+    }
+
+    pub fn get_memory_size(&self) -> u64 {
+        self.memory.lock().unwrap().size
+    }
+
+    pub fn get_disk_size(&self) -> Option<u64> {
+        self.disk.as_ref().map(|disk| disk.get_size())
+    }
+
+    // Entries evicted from memory go to the disk tier
+    async fn insert_to_memory(&self, entry: Arc<HttpCacheEntry>) {
+        let evicted = {
+            let mut memory = self.memory.lock().unwrap();
+            memory.insert(entry);
+            memory.evict(self.params.max_memory_size)
+        };
+
+        if let Some(disk) = self.disk.as_ref() {
+            for entry in evicted {
+                if !entry.is_fresh(SystemTime::now()) && !entry.has_validators() {
+                    continue;
+                }
+
+                disk.store(&entry).await;
+            }
+        }
+    }
+}
+
+pub fn get_cache_key(host: Option<&str>, path_and_query: &str) -> String {
+    let mut result = String::new();
+    if let Some(host) = host {
+        result.push_str(host.to_lowercase().as_str());
+    }
+    result.push_str(path_and_query);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use hyper::HeaderMap;
+
+    use super::*;
+
+    fn create_entry(key: &str, body_size: usize) -> HttpCacheEntry {
+        HttpCacheEntry::new(
+            key.to_string(),
+            key.to_string(),
+            &HeaderMap::new(),
+            200,
+            &HeaderMap::new(),
+            Bytes::from(vec![0u8; body_size]),
+            Duration::from_secs(60),
+            Duration::ZERO,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_memory_eviction_and_purge() {
+        let cache = HttpCache::new(
+            HttpCacheParams {
+                max_memory_size: 250,
+                max_entry_size: 200,
+                stale_if_error: Duration::ZERO,
+            },
+            None,
+        );
+
+        cache.store(create_entry("/a", 100)).await;
+        cache.store(create_entry("/b", 100)).await;
+        assert!(cache.get("/a", &HeaderMap::new()).await.is_some());
+
+        // /b is least recently used
+        cache.store(create_entry("/c", 100)).await;
+        assert!(cache.get("/b", &HeaderMap::new()).await.is_none());
+        assert!(cache.get("/a", &HeaderMap::new()).await.is_some());
+
+        cache.store(create_entry("/d", 300)).await;
+        assert!(cache.get("/d", &HeaderMap::new()).await.is_none());
+
+        assert_eq!(1, cache.purge(Some("/c")).await);
+        assert!(cache.get("/c", &HeaderMap::new()).await.is_none());
+        assert!(cache.get("/a", &HeaderMap::new()).await.is_some());
+    }
+}
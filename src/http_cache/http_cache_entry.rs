@@ -0,0 +1,353 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
+use hyper::{header, HeaderMap};
+use serde::*;
+
+use super::CacheControl;
+
+// Headers which describe the connection, not the content. They are not stored
+const NOT_STORED_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+    "age",
+];
+
+pub const CACHE_STATUS_HEADER: &str = "x-cache";
+
+pub struct HttpCacheEntry {
+    pub key: String,
+    pub path: String,
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    // Request headers which are listed in Vary response header with their values
+    pub vary: Vec<(String, Option<String>)>,
+    pub stored_at: SystemTime,
+    pub fresh_until: SystemTime,
+    pub stale_if_error_until: SystemTime,
+}
+
+impl HttpCacheEntry {
+    pub fn new(
+        key: String,
+        path: String,
+        req_headers: &HeaderMap,
+        status: u16,
+        headers: &HeaderMap,
+        body: Bytes,
+        freshness_lifetime: Duration,
+        stale_if_error: Duration,
+    ) -> Self {
+        let now = SystemTime::now();
+
+        let mut stored_headers = HeaderMap::new();
+        for (name, value) in headers {
+            if !NOT_STORED_HEADERS.contains(&name.as_str()) {
+                stored_headers.append(name, value.clone());
+            }
+        }
+
+        Self {
+            vary: get_vary_values(headers, req_headers),
+            key,
+            path,
+            status,
+            headers: stored_headers,
+            body,
+            stored_at: now,
+            fresh_until: now + freshness_lifetime,
+            stale_if_error_until: get_stale_if_error_until(
+                headers,
+                now + freshness_lifetime,
+                stale_if_error,
+            ),
+        }
+    }
+
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        now < self.fresh_until
+    }
+
+    pub fn can_serve_stale_on_error(&self, now: SystemTime) -> bool {
+        now < self.stale_if_error_until
+    }
+
+    pub fn matches_vary(&self, req_headers: &HeaderMap) -> bool {
+        self.vary.iter().all(|(name, value)| {
+            let req_value = req_headers.get(name.as_str()).and_then(|v| v.to_str().ok());
+            req_value == value.as_deref()
+        })
+    }
+
+    pub fn get_etag(&self) -> Option<&hyper::header::HeaderValue> {
+        self.headers.get(header::ETAG)
+    }
+
+    pub fn get_last_modified(&self) -> Option<&hyper::header::HeaderValue> {
+        self.headers.get(header::LAST_MODIFIED)
+    }
+
+    pub fn has_validators(&self) -> bool {
+        self.get_etag().is_some() || self.get_last_modified().is_some()
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let headers_size: usize = self
+            .headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum();
+
+        (self.key.len() + headers_size + self.body.len()) as u64
+    }
+
+    // 304 response of revalidation updates the headers and the freshness of the stored response
+    pub fn revalidate(
+        &self,
+        not_modified_headers: &HeaderMap,
+        freshness_lifetime: Duration,
+        stale_if_error: Duration,
+    ) -> Self {
+        let mut headers = self.headers.clone();
+
+        for name in not_modified_headers.keys() {
+            if NOT_STORED_HEADERS.contains(&name.as_str()) || name == header::CONTENT_LENGTH {
+                continue;
+            }
+
+            headers.remove(name);
+            for value in not_modified_headers.get_all(name) {
+                headers.append(name, value.clone());
+            }
+        }
+
+        let now = SystemTime::now();
+
+        Self {
+            key: self.key.clone(),
+            path: self.path.clone(),
+            status: self.status,
+            stale_if_error_until: get_stale_if_error_until(
+                &headers,
+                now + freshness_lifetime,
+                stale_if_error,
+            ),
+            headers,
+            body: self.body.clone(),
+            vary: self.vary.clone(),
+            stored_at: now,
+            fresh_until: now + freshness_lifetime,
+        }
+    }
+
+    pub fn to_response(
+        &self,
+        is_head: bool,
+        cache_status: &'static str,
+    ) -> hyper::Response<BoxBody<Bytes, String>> {
+        let mut builder = hyper::Response::builder().status(self.status);
+
+        if let Some(headers) = builder.headers_mut() {
+            *headers = self.headers.clone();
+
+            let age = SystemTime::now()
+                .duration_since(self.stored_at)
+                .unwrap_or(Duration::ZERO)
+                .as_secs();
+            headers.insert(header::AGE, age.into());
+            headers.insert(CACHE_STATUS_HEADER, cache_status.parse().unwrap());
+            headers.insert(header::CONTENT_LENGTH, self.body.len().into());
+        }
+
+        let body = if is_head {
+            Empty::new().map_err(|e| crate::to_hyper_error(e)).boxed()
+        } else {
+            Full::new(self.body.clone())
+                .map_err(|e| crate::to_hyper_error(e))
+                .boxed()
+        };
+
+        builder.body(body).unwrap()
+    }
+
+    // Disk format: 4 bytes of metadata length, json metadata, body
+    pub fn serialize(&self) -> Vec<u8> {
+        let metadata = HttpCacheEntryMetadata {
+            key: self.key.clone(),
+            path: self.path.clone(),
+            status: self.status,
+            headers: self
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            vary: self.vary.clone(),
+            stored_at: to_unix_seconds(self.stored_at),
+            fresh_until: to_unix_seconds(self.fresh_until),
+            stale_if_error_until: to_unix_seconds(self.stale_if_error_until),
+        };
+
+        let metadata = serde_json::to_vec(&metadata).unwrap();
+
+        let mut result = Vec::with_capacity(4 + metadata.len() + self.body.len());
+        result.extend_from_slice(&(metadata.len() as u32).to_be_bytes());
+        result.extend_from_slice(&metadata);
+        result.extend_from_slice(&self.body);
+        result
+    }
+
+    pub fn deserialize(src: Vec<u8>) -> Option<Self> {
+        if src.len() < 4 {
+            return None;
+        }
+
+        let metadata_len = u32::from_be_bytes(src[..4].try_into().ok()?) as usize;
+
+        if src.len() < 4 + metadata_len {
+            return None;
+        }
+
+        let metadata: HttpCacheEntryMetadata =
+            serde_json::from_slice(&src[4..4 + metadata_len]).ok()?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in metadata.headers {
+            let name = hyper::header::HeaderName::from_bytes(name.as_bytes()).ok()?;
+            headers.append(name, value.parse().ok()?);
+        }
+
+        let body = Bytes::from(src).slice(4 + metadata_len..);
+
+        Some(Self {
+            key: metadata.key,
+            path: metadata.path,
+            status: metadata.status,
+            headers,
+            body,
+            vary: metadata.vary,
+            stored_at: from_unix_seconds(metadata.stored_at),
+            fresh_until: from_unix_seconds(metadata.fresh_until),
+            stale_if_error_until: from_unix_seconds(metadata.stale_if_error_until),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HttpCacheEntryMetadata {
+    key: String,
+    path: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    vary: Vec<(String, Option<String>)>,
+    stored_at: u64,
+    fresh_until: u64,
+    stale_if_error_until: u64,
+}
+
+pub fn get_vary_names(headers: &HeaderMap) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for value in headers.get_all(header::VARY) {
+        if let Ok(value) = value.to_str() {
+            for name in value.split(',') {
+                let name = name.trim().to_lowercase();
+                if !name.is_empty() {
+                    result.push(name);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+fn get_vary_values(headers: &HeaderMap, req_headers: &HeaderMap) -> Vec<(String, Option<String>)> {
+    get_vary_names(headers)
+        .into_iter()
+        .map(|name| {
+            let value = req_headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string());
+            (name, value)
+        })
+        .collect()
+}
+
+// must-revalidate forbids to serve stale response even if the upstream is down
+fn get_stale_if_error_until(
+    headers: &HeaderMap,
+    fresh_until: SystemTime,
+    stale_if_error: Duration,
+) -> SystemTime {
+    let cache_control = CacheControl::parse(headers);
+
+    if cache_control.must_revalidate {
+        return fresh_until;
+    }
+
+    fresh_until + cache_control.stale_if_error.unwrap_or(stale_if_error)
+}
+
+fn to_unix_seconds(src: SystemTime) -> u64 {
+    src.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+fn from_unix_seconds(src: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(src)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use hyper::{header, HeaderMap};
+
+    use super::HttpCacheEntry;
+
+    #[test]
+    fn test_vary_and_serialization() {
+        let mut req_headers = HeaderMap::new();
+        req_headers.insert(header::ACCEPT_ENCODING, "gzip".parse().unwrap());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, "Accept-Encoding".parse().unwrap());
+        headers.insert(header::ETAG, "\"v1\"".parse().unwrap());
+        headers.insert(header::TRANSFER_ENCODING, "chunked".parse().unwrap());
+
+        let entry = HttpCacheEntry::new(
+            "localhost/app.js".to_string(),
+            "/app.js".to_string(),
+            &req_headers,
+            200,
+            &headers,
+            Bytes::from_static(b"content"),
+            Duration::from_secs(60),
+            Duration::ZERO,
+        );
+
+        assert!(entry.matches_vary(&req_headers));
+        assert!(!entry.matches_vary(&HeaderMap::new()));
+        assert!(entry.headers.get(header::TRANSFER_ENCODING).is_none());
+
+        let entry = HttpCacheEntry::deserialize(entry.serialize()).unwrap();
+
+        assert_eq!("localhost/app.js", entry.key);
+        assert_eq!(200, entry.status);
+        assert_eq!("\"v1\"", entry.get_etag().unwrap().to_str().unwrap());
+        assert_eq!(b"content", entry.body.as_ref());
+        assert!(entry.matches_vary(&req_headers));
+    }
+}
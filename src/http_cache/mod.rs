@@ -0,0 +1,10 @@
+mod cache_control;
+pub use cache_control::*;
+mod http_cache_entry;
+pub use http_cache_entry::*;
+mod http_cache;
+pub use http_cache::*;
+mod disk_cache;
+pub use disk_cache::*;
+mod store_response;
+pub use store_response::*;
@@ -0,0 +1,241 @@
+use std::{sync::Arc, time::Duration};
+
+use bytes::{Bytes, BytesMut};
+use futures::SinkExt;
+use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
+use hyper::{header, http::response::Parts, HeaderMap, Method, StatusCode};
+
+use super::*;
+
+const STORABLE_STATUS_CODES: [u16; 8] = [200, 203, 204, 300, 301, 308, 404, 410];
+
+pub const CACHE_STATUS_HIT: &str = "HIT";
+pub const CACHE_STATUS_MISS: &str = "MISS";
+pub const CACHE_STATUS_REVALIDATED: &str = "REVALIDATED";
+pub const CACHE_STATUS_STALE: &str = "STALE";
+
+// Request which goes to the upstream with the cache behind it
+pub struct HttpCacheRequest {
+    pub cache: Arc<HttpCache>,
+    pub key: String,
+    pub path: String,
+    pub req_headers: HeaderMap,
+    pub is_head: bool,
+    pub stale_entry: Option<Arc<HttpCacheEntry>>,
+    // Validators of the stale entry are added to the upstream request
+    pub revalidating: bool,
+}
+
+pub fn is_cacheable_request(method: &Method, headers: &HeaderMap) -> bool {
+    if method != Method::GET && method != Method::HEAD {
+        return false;
+    }
+
+    // Responses to authorized and partial requests are not shared between clients
+    if headers.contains_key(header::AUTHORIZATION) || headers.contains_key(header::RANGE) {
+        return false;
+    }
+
+    !CacheControl::parse(headers).no_store
+}
+
+// Client which sent no-cache or max-age=0 wants the response to be validated by the upstream
+pub fn is_revalidation_requested(headers: &HeaderMap) -> bool {
+    let cache_control = CacheControl::parse(headers);
+    cache_control.no_cache || cache_control.max_age == Some(Duration::ZERO)
+}
+
+// Conditional request of the client itself is passed to the upstream as is
+pub fn has_conditional_headers(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::IF_NONE_MATCH) || headers.contains_key(header::IF_MODIFIED_SINCE)
+}
+
+impl HttpCacheRequest {
+    pub fn add_validators(entry: &HttpCacheEntry, headers: &mut HeaderMap) {
+        if let Some(etag) = entry.get_etag() {
+            headers.insert(header::IF_NONE_MATCH, etag.clone());
+        }
+
+        if let Some(last_modified) = entry.get_last_modified() {
+            headers.insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    pub fn handle_response(
+        self,
+        parts: Parts,
+        body: BoxBody<Bytes, String>,
+    ) -> hyper::Response<BoxBody<Bytes, String>> {
+        if parts.status == StatusCode::NOT_MODIFIED && self.revalidating {
+            if let Some(stale_entry) = self.stale_entry.as_ref() {
+                let cache_control = CacheControl::parse(&parts.headers);
+                let freshness_lifetime = get_freshness_lifetime(&parts.headers, &cache_control)
+                    .or_else(|| get_stored_freshness_lifetime(stale_entry))
+                    .unwrap_or(Duration::ZERO);
+
+                let entry = stale_entry.revalidate(
+                    &parts.headers,
+                    freshness_lifetime,
+                    self.cache.params.stale_if_error,
+                );
+
+                let response = entry.to_response(self.is_head, CACHE_STATUS_REVALIDATED);
+
+                let cache = self.cache.clone();
+                tokio::spawn(async move {
+                    cache.store(entry).await;
+                });
+
+                return response;
+            }
+        }
+
+        let freshness_lifetime = match self.get_storable_freshness_lifetime(&parts) {
+            Some(freshness_lifetime) => freshness_lifetime,
+            None => {
+                return hyper::Response::from_parts(
+                    with_cache_status(parts, CACHE_STATUS_MISS),
+                    body,
+                )
+            }
+        };
+
+        let status = parts.status.as_u16();
+        let headers = parts.headers.clone();
+
+        let body = store_while_streaming(body, self, status, headers, freshness_lifetime);
+
+        let parts = with_cache_status(parts, CACHE_STATUS_MISS);
+
+        hyper::Response::from_parts(parts, body)
+    }
+
+    fn get_storable_freshness_lifetime(&self, parts: &Parts) -> Option<Duration> {
+        // Response to HEAD has no body to serve GET requests
+        if self.is_head || !STORABLE_STATUS_CODES.contains(&parts.status.as_u16()) {
+            return None;
+        }
+
+        let cache_control = CacheControl::parse(&parts.headers);
+
+        if cache_control.no_store || cache_control.private {
+            return None;
+        }
+
+        if parts.headers.contains_key(header::SET_COOKIE) {
+            return None;
+        }
+
+        if get_vary_names(&parts.headers)
+            .iter()
+            .any(|name| name.as_str() == "*")
+        {
+            return None;
+        }
+
+        if let Some(content_length) = parts
+            .headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            if content_length > self.cache.params.max_entry_size {
+                return None;
+            }
+        }
+
+        let has_validators = parts.headers.contains_key(header::ETAG)
+            || parts.headers.contains_key(header::LAST_MODIFIED);
+
+        let freshness_lifetime = if cache_control.no_cache {
+            Some(Duration::ZERO)
+        } else {
+            get_freshness_lifetime(&parts.headers, &cache_control)
+        };
+
+        match freshness_lifetime {
+            Some(freshness_lifetime) if !freshness_lifetime.is_zero() => Some(freshness_lifetime),
+            // Stored only to be revalidated with the validators
+            _ if has_validators => Some(Duration::ZERO),
+            _ => None,
+        }
+    }
+}
+
+fn with_cache_status(mut parts: Parts, cache_status: &'static str) -> Parts {
+    parts
+        .headers
+        .insert(CACHE_STATUS_HEADER, cache_status.parse().unwrap());
+    parts
+}
+
+fn get_stored_freshness_lifetime(entry: &HttpCacheEntry) -> Option<Duration> {
+    entry.fresh_until.duration_since(entry.stored_at).ok()
+}
+
+// Body is sent to the client as it comes and is stored to the cache when it is complete
+fn store_while_streaming(
+    mut body: BoxBody<Bytes, String>,
+    cache_request: HttpCacheRequest,
+    status: u16,
+    headers: HeaderMap,
+    freshness_lifetime: Duration,
+) -> BoxBody<Bytes, String> {
+    let (mut sender, receiver) = futures::channel::mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let max_entry_size = cache_request.cache.params.max_entry_size as usize;
+        let mut content = Some(BytesMut::new());
+
+        loop {
+            let frame = match body.frame().await {
+                Some(frame) => frame,
+                None => break,
+            };
+
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(err) => {
+                    let _ = sender.send(Err(err)).await;
+                    return;
+                }
+            };
+
+            match frame.data_ref() {
+                Some(data) => {
+                    if let Some(buffer) = content.as_mut() {
+                        if buffer.len() + data.len() > max_entry_size {
+                            content = None;
+                        } else {
+                            buffer.extend_from_slice(data);
+                        }
+                    }
+                }
+                // Trailers are not stored
+                None => content = None,
+            }
+
+            // Client is gone
+            if sender.send(Ok(frame)).await.is_err() {
+                return;
+            }
+        }
+
+        if let Some(content) = content {
+            let entry = HttpCacheEntry::new(
+                cache_request.key,
+                cache_request.path,
+                &cache_request.req_headers,
+                status,
+                &headers,
+                content.freeze(),
+                freshness_lifetime,
+                cache_request.cache.params.stale_if_error,
+            );
+
+            cache_request.cache.store(entry).await;
+        }
+    });
+
+    StreamBody::new(receiver).boxed()
+}
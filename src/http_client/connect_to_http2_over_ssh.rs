@@ -3,54 +3,35 @@ use std::sync::Arc;
 use bytes::Bytes;
 use http_body_util::Full;
 use hyper::client::conn::http2::SendRequest;
+use hyper_util::rt::{TokioExecutor, TokioIo};
 use my_ssh::SshCredentials;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
 
-use crate::{app::AppContext, http_proxy_pass::ProxyPassError};
+use crate::{app::AppContext, http_proxy_pass::ProxyPassError, ssh_host_keys::SshChainHostKeys};
 
 use crate::configurations::*;
 
 pub async fn connect_to_http2_over_ssh(
     app: &AppContext,
+    ssh_jump_hosts: &[Arc<SshCredentials>],
     ssh_credentials: &Arc<SshCredentials>,
+    ssh_host_keys: &SshChainHostKeys,
     ssh_remote_host: &RemoteHost,
 ) -> Result<SendRequest<Full<Bytes>>, ProxyPassError> {
-    let tunnel_info = app
-        .ssh_to_http_port_forward_pool
-        .get_or_create_port_forward(
-            ssh_credentials,
-            ssh_remote_host.get_host(),
-            ssh_remote_host.get_port(),
-            || app.local_port_allocator.next(),
-        )
-        .await;
-
-    let remote_host = tunnel_info.get_unix_socket_path();
-
-    let result =
-        super::connect_to_http2_unix_socket_endpoint::connect_to_http2_unix_socket_endpoint(
-            &remote_host,
-        )
-        .await?;
-
-    return Ok(result);
-
-    /*
-    let ssh_session = Arc::new(SshSession::new(ssh_credentials.clone()));
-
-    let ssh_channel = ssh_session
+    let ssh_channel = app
+        .ssh_sessions_pool
         .connect_to_remote_host(
+            ssh_jump_hosts,
+            ssh_credentials,
+            ssh_host_keys,
+            None,
             ssh_remote_host.get_host(),
             ssh_remote_host.get_port(),
             app.connection_settings.remote_connect_timeout,
         )
         .await?;
 
-    let buf_writer = tokio::io::BufWriter::with_capacity(
-        app.connection_settings.buffer_size,
-        tokio::io::BufReader::with_capacity(app.connection_settings.buffer_size, ssh_channel),
-    );
-
-    let io = TokioIo::new(buf_writer);
+    let io = TokioIo::new(ssh_channel.compat());
 
     let (mut sender, conn) =
         hyper::client::conn::http2::handshake(TokioExecutor::new(), io).await?;
@@ -64,12 +45,9 @@ pub async fn connect_to_http2_over_ssh(
                 proxy_pass_uri, err
             );
         }
-
-        //Here
     });
 
     sender.ready().await?;
 
-    Ok((ssh_session, sender))
-     */
+    Ok(sender)
 }
@@ -0,0 +1,46 @@
+use http_body_util::Full;
+use hyper::{body::Bytes, client::conn::http2::SendRequest};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+
+use crate::configurations::*;
+
+use super::HttpClientError;
+
+pub async fn connect_to_http2_tls_endpoint(
+    remote_host: &RemoteHost,
+    domain_name: &Option<String>,
+    tls_config: &UpstreamTlsConfig,
+) -> Result<SendRequest<Full<Bytes>>, HttpClientError> {
+    let tls_stream = super::connect_to_tls_stream(remote_host, domain_name, tls_config).await?;
+
+    // Server which does not speak h2 would answer with http/1.1 to the h2 preface
+    let (_, connection) = tls_stream.get_ref();
+    if connection.alpn_protocol() != Some(ALPN_H2) {
+        return Err(HttpClientError::CanNotEstablishConnection(format!(
+            "Remote host {} did not negotiate h2 via ALPN",
+            remote_host.as_str()
+        )));
+    }
+
+    let io = TokioIo::new(tls_stream);
+
+    let handshake_result = hyper::client::conn::http2::handshake(TokioExecutor::new(), io).await;
+
+    match handshake_result {
+        Ok((mut sender, conn)) => {
+            let host_port = remote_host.to_string();
+            tokio::task::spawn(async move {
+                if let Err(err) = conn.await {
+                    println!(
+                        "Https2 Connection to https://{} is failed: {:?}",
+                        host_port, err
+                    );
+                }
+            });
+
+            sender.ready().await?;
+            Ok(sender)
+        }
+        Err(err) => Err(HttpClientError::InvalidHttp1HandShake(format!("{}", err))),
+    }
+}
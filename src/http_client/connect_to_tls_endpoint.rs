@@ -1,11 +1,9 @@
-use std::sync::Arc;
-
 use bytes::Bytes;
 use http_body_util::Full;
 use hyper::client::conn::http1::SendRequest;
 use hyper_util::rt::TokioIo;
-use my_tls::ROOT_CERT_STORE;
 use tokio::net::TcpStream;
+use tokio_rustls::{client::TlsStream, rustls::pki_types::ServerName};
 
 use crate::configurations::*;
 
@@ -14,78 +12,84 @@ use super::HttpClientError;
 pub async fn connect_to_tls_endpoint(
     remote_host: &RemoteHost,
     domain_name: &Option<String>,
+    tls_config: &UpstreamTlsConfig,
 ) -> Result<SendRequest<Full<Bytes>>, HttpClientError> {
-    use tokio_rustls::rustls::pki_types::ServerName;
+    let tls_stream = connect_to_tls_stream(remote_host, domain_name, tls_config).await?;
+
+    let io = TokioIo::new(tls_stream);
+
+    let handshake_result = hyper::client::conn::http1::handshake(io).await;
+
+    match handshake_result {
+        Ok((mut sender, conn)) => {
+            let host_port = remote_host.to_string();
+            tokio::task::spawn(async move {
+                if let Err(err) = conn.with_upgrades().await {
+                    println!(
+                        "Https Connection to https://{} is failed: {:?}",
+                        host_port, err
+                    );
+                }
+            });
+
+            sender.ready().await?;
+
+            Ok(sender)
+        }
+        Err(err) => {
+            println!(
+                "Can not connect to TLS remote host: {}. Err: {}",
+                remote_host.get_host_port(),
+                err
+            );
+            Err(HttpClientError::InvalidHttp1HandShake(format!("{}", err)))
+        }
+    }
+}
 
+// Sni is taken from tls settings first, then from domain_name of the location, then from the remote host
+pub async fn connect_to_tls_stream(
+    remote_host: &RemoteHost,
+    domain_name: &Option<String>,
+    tls_config: &UpstreamTlsConfig,
+) -> Result<TlsStream<TcpStream>, HttpClientError> {
     let host_port = remote_host.get_host_port();
 
     let connect_result = if host_port.find(":").is_none() {
-        TcpStream::connect(format!("{}:443", remote_host.get_host_port())).await
+        TcpStream::connect(format!("{}:443", host_port)).await
     } else {
         TcpStream::connect(host_port).await
     };
 
-    println!(
-        "Connecting to TLS remote host: {}",
-        remote_host.get_host_port(),
-    );
-    match connect_result {
-        Ok(tcp_stream) => {
-            let config = tokio_rustls::rustls::ClientConfig::builder()
-                .with_root_certificates(ROOT_CERT_STORE.clone())
-                .with_no_client_auth();
-
-            let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
-            let domain = if let Some(domain_name) = domain_name {
-                ServerName::try_from(domain_name.to_string()).unwrap()
-            } else {
-                ServerName::try_from(remote_host.get_host().to_string()).unwrap()
-            };
-
-            println!("TLS Domain Name: {:?}", domain);
-
-            let tls_stream = connector
-                .connect_with(domain, tcp_stream, |itm| {
-                    println!("Debugging: {:?}", itm.alpn_protocol());
-                })
-                .await?;
-
-            let io = TokioIo::new(tls_stream);
-
-            let handshake_result = hyper::client::conn::http1::handshake(io).await;
-
-            match handshake_result {
-                Ok((mut sender, conn)) => {
-                    let host_port = remote_host.to_string();
-                    tokio::task::spawn(async move {
-                        println!("Connected to TLS remote host: {}", host_port,);
-                        if let Err(err) = conn.await {
-                            println!(
-                                "Https Connection to https://{} is failed: {:?}",
-                                host_port, err
-                            );
-                        }
-                    });
-
-                    sender.ready().await?;
-
-                    return Ok(sender);
-                }
-                Err(err) => {
-                    println!(
-                        "Can not connect to TLS remote host: {}. Err: {}",
-                        remote_host.get_host_port(),
-                        err
-                    );
-                    return Err(HttpClientError::InvalidHttp1HandShake(format!("{}", err)));
-                }
-            }
-        }
+    let tcp_stream = match connect_result {
+        Ok(tcp_stream) => tcp_stream,
         Err(err) => {
             return Err(HttpClientError::CanNotEstablishConnection(format!(
                 "{}",
                 err
             )));
         }
-    }
+    };
+
+    let server_name = match (tls_config.sni.as_ref(), domain_name.as_ref()) {
+        (Some(sni), _) => sni.to_string(),
+        (None, Some(domain_name)) => domain_name.to_string(),
+        (None, None) => remote_host.get_host().to_string(),
+    };
+
+    let server_name = match ServerName::try_from(server_name) {
+        Ok(server_name) => server_name,
+        Err(err) => {
+            return Err(HttpClientError::CanNotEstablishConnection(format!(
+                "Invalid TLS server name. Err: {}",
+                err
+            )));
+        }
+    };
+
+    let connector = tokio_rustls::TlsConnector::from(tls_config.client_config.clone());
+
+    let tls_stream = connector.connect(server_name, tcp_stream).await?;
+
+    Ok(tls_stream)
 }
@@ -1,15 +1,15 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use http_body_util::Full;
 use hyper::{body::Bytes, client::conn::http1::SendRequest};
 use my_ssh::SshCredentials;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
-use crate::{app::AppContext, http_proxy_pass::ProxyPassError};
+use crate::{app::AppContext, http_proxy_pass::ProxyPassError, ssh_host_keys::SshChainHostKeys};
 
 use crate::configurations::*;
 
-use super::{HttpClientError, HTTP_CLIENT_TIMEOUT};
+use super::HttpClientError;
 
 pub struct Http1Client {
     pub connected: DateTimeAsMicroseconds,
@@ -20,8 +20,11 @@ impl Http1Client {
     pub async fn connect(
         remote_host: &RemoteHost,
         domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
     ) -> Result<Self, HttpClientError> {
-        let send_request = Self::connect_to_http(remote_host, domain_name).await?;
+        let send_request =
+            Self::connect_to_http(remote_host, domain_name, tls_config, connect_timeout).await?;
 
         let result = Self {
             send_request,
@@ -33,11 +36,19 @@ impl Http1Client {
 
     pub async fn connect_over_ssh(
         app: &AppContext,
+        ssh_jump_hosts: &[Arc<SshCredentials>],
         ssh_credentials: &Arc<SshCredentials>,
+        ssh_host_keys: &SshChainHostKeys,
         remote_host: &RemoteHost,
     ) -> Result<Self, ProxyPassError> {
-        let send_request =
-            super::connect_to_http_over_ssh(app, ssh_credentials, remote_host).await?;
+        let send_request = super::connect_to_http_over_ssh(
+            app,
+            ssh_jump_hosts,
+            ssh_credentials,
+            ssh_host_keys,
+            remote_host,
+        )
+        .await?;
 
         let result = Self {
             send_request,
@@ -47,14 +58,45 @@ impl Http1Client {
         Ok(result)
     }
 
+    pub async fn connect_to_unix_socket(
+        unix_socket_path: &str,
+        connect_timeout: Duration,
+    ) -> Result<Self, HttpClientError> {
+        let future = super::connect_to_http_unix_socket_endpoint(unix_socket_path);
+
+        let result = tokio::time::timeout(connect_timeout, future).await;
+
+        if result.is_err() {
+            return Err(HttpClientError::TimeOut);
+        }
+
+        let result = Self {
+            send_request: result.unwrap()?,
+            connected: DateTimeAsMicroseconds::now(),
+        };
+
+        Ok(result)
+    }
+
     async fn connect_to_http(
         remote_host: &RemoteHost,
         domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
     ) -> Result<SendRequest<Full<Bytes>>, HttpClientError> {
         if remote_host.is_https() {
-            let future = super::connect_to_tls_endpoint(remote_host, domain_name);
+            let default_tls_config;
+            let tls_config = match tls_config {
+                Some(tls_config) => tls_config.as_ref(),
+                None => {
+                    default_tls_config = UpstreamTlsConfig::create_default(false);
+                    &default_tls_config
+                }
+            };
+
+            let future = super::connect_to_tls_endpoint(remote_host, domain_name, tls_config);
 
-            let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, future).await;
+            let result = tokio::time::timeout(connect_timeout, future).await;
 
             if result.is_err() {
                 return Err(HttpClientError::TimeOut);
@@ -64,7 +106,7 @@ impl Http1Client {
         } else {
             let future = super::connect_to_http_endpoint(remote_host);
 
-            let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, future).await;
+            let result = tokio::time::timeout(connect_timeout, future).await;
 
             if result.is_err() {
                 return Err(HttpClientError::TimeOut);
@@ -1,15 +1,15 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use http_body_util::Full;
 use hyper::{body::Bytes, client::conn::http2::SendRequest};
 use my_ssh::SshCredentials;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
-use crate::{app::AppContext, http_proxy_pass::ProxyPassError};
+use crate::{app::AppContext, http_proxy_pass::ProxyPassError, ssh_host_keys::SshChainHostKeys};
 
 use crate::configurations::*;
 
-use super::{HttpClientError, HTTP_CLIENT_TIMEOUT};
+use super::HttpClientError;
 
 pub struct Http2Client {
     pub connected: DateTimeAsMicroseconds,
@@ -19,25 +19,33 @@ pub struct Http2Client {
 impl Http2Client {
     pub async fn connect_to_http2_int(
         remote_host: &RemoteHost,
+        domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
     ) -> Result<SendRequest<Full<Bytes>>, HttpClientError> {
         let is_https = remote_host.is_https();
         if is_https {
-            panic!("TLS not supported yet");
-            /*
-            let future = super::connect_to_tls_endpoint(proxy_pass);
+            let default_tls_config;
+            let tls_config = match tls_config {
+                Some(tls_config) => tls_config.as_ref(),
+                None => {
+                    default_tls_config = UpstreamTlsConfig::create_default(true);
+                    &default_tls_config
+                }
+            };
 
-            let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, future).await;
+            let future = super::connect_to_http2_tls_endpoint(remote_host, domain_name, tls_config);
+
+            let result = tokio::time::timeout(connect_timeout, future).await;
 
             if result.is_err() {
                 return Err(HttpClientError::TimeOut);
             }
 
             result.unwrap()
-
-             */
         } else {
             let future = super::connect_to_http2_endpoint(remote_host);
-            let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, future).await;
+            let result = tokio::time::timeout(connect_timeout, future).await;
             if result.is_err() {
                 return Err(HttpClientError::TimeOut);
             }
@@ -45,8 +53,15 @@ impl Http2Client {
         }
     }
 
-    pub async fn connect(proxy_pass: &RemoteHost) -> Result<Self, HttpClientError> {
-        let send_request = Self::connect_to_http2_int(proxy_pass).await?;
+    pub async fn connect(
+        proxy_pass: &RemoteHost,
+        domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
+    ) -> Result<Self, HttpClientError> {
+        let send_request =
+            Self::connect_to_http2_int(proxy_pass, domain_name, tls_config, connect_timeout)
+                .await?;
 
         let result = Self {
             send_request,
@@ -56,13 +71,41 @@ impl Http2Client {
         Ok(result)
     }
 
+    pub async fn connect_to_unix_socket(
+        unix_socket_path: &str,
+        connect_timeout: Duration,
+    ) -> Result<Self, HttpClientError> {
+        let future = super::connect_to_http2_unix_socket_endpoint(unix_socket_path);
+
+        let result = tokio::time::timeout(connect_timeout, future).await;
+
+        if result.is_err() {
+            return Err(HttpClientError::TimeOut);
+        }
+
+        let result = Self {
+            send_request: result.unwrap()?,
+            connected: DateTimeAsMicroseconds::now(),
+        };
+
+        Ok(result)
+    }
+
     pub async fn connect_over_ssh(
         app: &AppContext,
+        ssh_jump_hosts: &[Arc<SshCredentials>],
         ssh_credentials: &Arc<SshCredentials>,
+        ssh_host_keys: &SshChainHostKeys,
         remote_host: &RemoteHost,
     ) -> Result<Self, ProxyPassError> {
-        let send_request =
-            super::connect_to_http2_over_ssh(app, ssh_credentials, remote_host).await?;
+        let send_request = super::connect_to_http2_over_ssh(
+            app,
+            ssh_jump_hosts,
+            ssh_credentials,
+            ssh_host_keys,
+            remote_host,
+        )
+        .await?;
 
         let result = Self {
             send_request,
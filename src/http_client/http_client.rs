@@ -1,10 +1,10 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use my_ssh::SshCredentials;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
 use crate::configurations::*;
-use crate::{app::AppContext, http_proxy_pass::ProxyPassError};
+use crate::{app::AppContext, http_proxy_pass::ProxyPassError, ssh_host_keys::SshChainHostKeys};
 
 use super::{Http1Client, Http2Client, HttpClientError};
 
@@ -62,8 +62,11 @@ impl HttpClient {
         &mut self,
         remote_host: &RemoteHost,
         domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
     ) -> Result<(), HttpClientError> {
-        let connect_result = Http1Client::connect(remote_host, domain_name).await;
+        let connect_result =
+            Http1Client::connect(remote_host, domain_name, tls_config, connect_timeout).await;
 
         match connect_result {
             Ok(client) => {
@@ -84,16 +87,51 @@ impl HttpClient {
     pub async fn connect_to_http1_over_ssh(
         &mut self,
         app: &AppContext,
+        ssh_jump_hosts: &[Arc<SshCredentials>],
         ssh_credentials: &Arc<SshCredentials>,
+        ssh_host_keys: &SshChainHostKeys,
         remote_host: &RemoteHost,
     ) -> Result<(), ProxyPassError> {
-        let client = Http1Client::connect_over_ssh(app, ssh_credentials, remote_host).await?;
+        let client = Http1Client::connect_over_ssh(
+            app,
+            ssh_jump_hosts,
+            ssh_credentials,
+            ssh_host_keys,
+            remote_host,
+        )
+        .await?;
         *self = Self::Http(client);
         Ok(())
     }
 
-    pub async fn connect_to_http2(&mut self, uri: &RemoteHost) -> Result<(), HttpClientError> {
-        let client = Http2Client::connect(uri).await?;
+    pub async fn connect_to_http1_unix_socket(
+        &mut self,
+        unix_socket_path: &str,
+        connect_timeout: Duration,
+    ) -> Result<(), HttpClientError> {
+        let client = Http1Client::connect_to_unix_socket(unix_socket_path, connect_timeout).await?;
+        *self = Self::Http(client);
+        Ok(())
+    }
+
+    pub async fn connect_to_http2_unix_socket(
+        &mut self,
+        unix_socket_path: &str,
+        connect_timeout: Duration,
+    ) -> Result<(), HttpClientError> {
+        let client = Http2Client::connect_to_unix_socket(unix_socket_path, connect_timeout).await?;
+        *self = Self::Http2(client);
+        Ok(())
+    }
+
+    pub async fn connect_to_http2(
+        &mut self,
+        uri: &RemoteHost,
+        domain_name: &Option<String>,
+        tls_config: Option<&Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
+    ) -> Result<(), HttpClientError> {
+        let client = Http2Client::connect(uri, domain_name, tls_config, connect_timeout).await?;
         *self = Self::Http2(client);
         Ok(())
     }
@@ -101,10 +139,19 @@ impl HttpClient {
     pub async fn connect_to_http2_over_ssh(
         &mut self,
         app: &AppContext,
+        ssh_jump_hosts: &[Arc<SshCredentials>],
         ssh_credentials: &Arc<SshCredentials>,
+        ssh_host_keys: &SshChainHostKeys,
         remote_host: &RemoteHost,
     ) -> Result<(), ProxyPassError> {
-        let client = Http2Client::connect_over_ssh(app, ssh_credentials, remote_host).await?;
+        let client = Http2Client::connect_over_ssh(
+            app,
+            ssh_jump_hosts,
+            ssh_credentials,
+            ssh_host_keys,
+            remote_host,
+        )
+        .await?;
         *self = Self::Http2(client);
         Ok(())
     }
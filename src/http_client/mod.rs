@@ -6,6 +6,8 @@ mod error;
 pub use error::*;
 mod connect_to_tls_endpoint;
 pub use connect_to_tls_endpoint::*;
+mod connect_to_http2_tls_endpoint;
+pub use connect_to_http2_tls_endpoint::*;
 
 mod connect_to_http_over_ssh;
 pub use connect_to_http_over_ssh::*;
@@ -18,7 +20,9 @@ pub use http_client::*;
 mod connect_to_http2_over_ssh;
 pub use connect_to_http2_over_ssh::*;
 mod connect_to_http_unix_socket_endpoint;
+pub use connect_to_http_unix_socket_endpoint::*;
 
 pub const HTTP_CLIENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 mod connect_to_http2_unix_socket_endpoint;
+pub use connect_to_http2_unix_socket_endpoint::*;
 mod unix_socket_utils;
@@ -0,0 +1,100 @@
+use super::ContentEncoding;
+
+// Picks the encoding with the highest q-value among supported ones.
+// Encodings with q=0 are refused explicitly, '*' covers the ones which are not listed
+pub fn negotiate_content_encoding(
+    accept_encoding: &str,
+    supported: &[ContentEncoding],
+) -> Option<ContentEncoding> {
+    let mut listed: Vec<(ContentEncoding, f32)> = Vec::new();
+    let mut wildcard_q = None;
+
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+
+        let name = parts.next().unwrap_or("").trim();
+
+        if name.is_empty() {
+            continue;
+        }
+
+        let q = parse_q_value(parts);
+
+        if name == "*" {
+            wildcard_q = Some(q);
+            continue;
+        }
+
+        if let Some(encoding) = ContentEncoding::parse(name) {
+            listed.push((encoding, q));
+        }
+    }
+
+    let mut result: Option<(ContentEncoding, f32)> = None;
+
+    for encoding in supported {
+        let q = match listed.iter().find(|itm| itm.0 == *encoding) {
+            Some((_, q)) => *q,
+            None => match wildcard_q {
+                Some(q) => q,
+                None => continue,
+            },
+        };
+
+        if q <= 0.0 {
+            continue;
+        }
+
+        match result {
+            Some((_, best_q)) if best_q >= q => {}
+            _ => result = Some((*encoding, q)),
+        }
+    }
+
+    result.map(|itm| itm.0)
+}
+
+fn parse_q_value<'s>(params: impl Iterator<Item = &'s str>) -> f32 {
+    for param in params {
+        if let Some((name, value)) = param.split_once('=') {
+            if name.trim().eq_ignore_ascii_case("q") {
+                return value.trim().parse().unwrap_or(0.0);
+            }
+        }
+    }
+
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_content_encoding() {
+        let all = ContentEncoding::ALL;
+
+        assert_eq!(
+            negotiate_content_encoding("gzip, deflate, br, zstd", &all),
+            Some(ContentEncoding::Brotli)
+        );
+        assert_eq!(
+            negotiate_content_encoding("gzip, deflate", &all),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            negotiate_content_encoding("br;q=0.5, gzip;q=0.8", &all),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            negotiate_content_encoding("br;q=0, *", &all),
+            Some(ContentEncoding::Zstd)
+        );
+        assert_eq!(
+            negotiate_content_encoding("br", &[ContentEncoding::Gzip]),
+            None
+        );
+        assert_eq!(negotiate_content_encoding("identity", &all), None);
+        assert_eq!(negotiate_content_encoding("*;q=0", &all), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::pin::Pin;
+
+use async_compression::{
+    tokio::bufread::{BrotliEncoder, GzipEncoder, ZstdEncoder},
+    Level,
+};
+use bytes::Bytes;
+use futures::{SinkExt, TryStreamExt};
+use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
+use hyper::{
+    body::Frame,
+    header::{self, HeaderValue},
+    HeaderMap,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{CompressionConfig, ContentEncoding};
+
+const COMPRESS_CHUNK_SIZE: usize = 16 * 1024;
+const COMPRESS_CHANNEL_SIZE: usize = 4;
+
+// Default brotli quality (11) is made for the static content, it is too slow for on the fly compression
+const BROTLI_QUALITY: i32 = 4;
+
+pub fn compress_response(
+    response: hyper::Response<BoxBody<Bytes, String>>,
+    accept_encoding: Option<&str>,
+    config: &CompressionConfig,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let status_code = response.status().as_u16();
+
+    // Partial content can not be compressed, since ranges are pointing to the original bytes
+    if status_code < 200 || status_code == 204 || status_code == 206 || status_code == 304 {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    if parts.headers.contains_key(header::CONTENT_ENCODING) {
+        return hyper::Response::from_parts(parts, body);
+    }
+
+    let is_compressible = match parts.headers.get(header::CONTENT_TYPE) {
+        Some(content_type) => match content_type.to_str() {
+            Ok(content_type) => config.is_compressible_content_type(content_type),
+            Err(_) => false,
+        },
+        None => false,
+    };
+
+    if !is_compressible || has_no_transform(&parts.headers) {
+        return hyper::Response::from_parts(parts, body);
+    }
+
+    add_vary_accept_encoding(&mut parts.headers);
+
+    if let Some(content_length) = get_content_length(&parts.headers) {
+        if content_length < config.min_size {
+            return hyper::Response::from_parts(parts, body);
+        }
+    }
+
+    let encoding = match accept_encoding {
+        Some(accept_encoding) => {
+            super::negotiate_content_encoding(accept_encoding, &config.encodings)
+        }
+        None => None,
+    };
+
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => return hyper::Response::from_parts(parts, body),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::ACCEPT_RANGES);
+    parts.headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+
+    // Compressed representation is not byte to byte equal to the original one
+    if let Some(etag) = parts.headers.get(header::ETAG) {
+        if let Ok(etag) = etag.to_str() {
+            if !etag.starts_with("W/") {
+                if let Ok(weak_etag) = format!("W/{}", etag).parse() {
+                    parts.headers.insert(header::ETAG, weak_etag);
+                }
+            }
+        }
+    }
+
+    hyper::Response::from_parts(parts, compress_body(body, encoding))
+}
+
+pub fn add_vary_accept_encoding(headers: &mut HeaderMap) {
+    let has_vary = headers.get_all(header::VARY).iter().any(|value| {
+        value.to_str().map_or(false, |value| {
+            value.split(',').any(|itm| {
+                let itm = itm.trim();
+                itm == "*" || itm.eq_ignore_ascii_case("accept-encoding")
+            })
+        })
+    });
+
+    if !has_vary {
+        headers.append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    }
+}
+
+fn has_no_transform(headers: &HeaderMap) -> bool {
+    match headers.get(header::CACHE_CONTROL) {
+        Some(value) => value.to_str().map_or(false, |value| {
+            value
+                .split(',')
+                .any(|itm| itm.trim().eq_ignore_ascii_case("no-transform"))
+        }),
+        None => false,
+    }
+}
+
+fn get_content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn compress_body(
+    body: BoxBody<Bytes, String>,
+    encoding: ContentEncoding,
+) -> BoxBody<Bytes, String> {
+    let stream = body
+        .into_data_stream()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+
+    let reader = tokio_util::io::StreamReader::new(stream);
+
+    let mut encoder: Pin<Box<dyn AsyncRead + Send>> = match encoding {
+        ContentEncoding::Brotli => Box::pin(BrotliEncoder::with_quality(
+            reader,
+            Level::Precise(BROTLI_QUALITY),
+        )),
+        ContentEncoding::Zstd => Box::pin(ZstdEncoder::new(reader)),
+        ContentEncoding::Gzip => Box::pin(GzipEncoder::new(reader)),
+    };
+
+    let (mut sender, receiver) = futures::channel::mpsc::channel(COMPRESS_CHANNEL_SIZE);
+
+    tokio::spawn(async move {
+        let mut buffer = vec![0u8; COMPRESS_CHUNK_SIZE];
+
+        loop {
+            let read = match encoder.read(&mut buffer).await {
+                Ok(0) => return,
+                Ok(read) => read,
+                Err(err) => {
+                    let _ = sender.send(Err(format!("{:?}", err))).await;
+                    return;
+                }
+            };
+
+            let frame = Frame::data(Bytes::copy_from_slice(&buffer[..read]));
+
+            // Client is gone
+            if sender.send(Ok(frame)).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    StreamBody::new(receiver).boxed()
+}
@@ -0,0 +1,56 @@
+use super::ContentEncoding;
+
+pub const DEFAULT_COMPRESSION_MIN_SIZE: u64 = 1024;
+
+pub const DEFAULT_COMPRESSIBLE_CONTENT_TYPES: [&str; 12] = [
+    "text/html",
+    "text/css",
+    "text/plain",
+    "text/xml",
+    "text/javascript",
+    "application/javascript",
+    "application/json",
+    "application/xml",
+    "application/manifest+json",
+    "application/wasm",
+    "image/svg+xml",
+    "font/ttf",
+];
+
+pub struct CompressionConfig {
+    pub encodings: Vec<ContentEncoding>,
+    pub content_types: Vec<String>,
+    pub min_size: u64,
+}
+
+impl CompressionConfig {
+    // Content type is matched without parameters. 'text/*' matches all the text types
+    pub fn is_compressible_content_type(&self, content_type: &str) -> bool {
+        let content_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_lowercase();
+
+        // Events are sent one by one, buffering of the encoder would delay them
+        if content_type == "text/event-stream" {
+            return false;
+        }
+
+        // Grpc messages have own compression flag and framing
+        if content_type.starts_with("application/grpc") {
+            return false;
+        }
+
+        self.content_types
+            .iter()
+            .any(|itm| match itm.strip_suffix("/*") {
+                Some(prefix) => {
+                    content_type.starts_with(prefix)
+                        && content_type.as_bytes().get(prefix.len()) == Some(&b'/')
+                }
+                None => itm == &content_type,
+            })
+    }
+}
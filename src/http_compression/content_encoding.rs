@@ -0,0 +1,40 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Brotli,
+    Zstd,
+    Gzip,
+}
+
+impl ContentEncoding {
+    // Order is the server preference, when client accepts several encodings with the same q-value
+    pub const ALL: [ContentEncoding; 3] = [
+        ContentEncoding::Brotli,
+        ContentEncoding::Zstd,
+        ContentEncoding::Gzip,
+    ];
+
+    pub fn parse(src: &str) -> Option<Self> {
+        match src.trim().to_lowercase().as_str() {
+            "br" | "brotli" => Some(ContentEncoding::Brotli),
+            "zstd" => Some(ContentEncoding::Zstd),
+            "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Zstd => "zstd",
+            ContentEncoding::Gzip => "gzip",
+        }
+    }
+
+    pub fn get_file_extension(&self) -> &'static str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Zstd => "zst",
+            ContentEncoding::Gzip => "gz",
+        }
+    }
+}
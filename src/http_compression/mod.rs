@@ -0,0 +1,8 @@
+mod content_encoding;
+pub use content_encoding::*;
+mod accept_encoding;
+pub use accept_encoding::*;
+mod compression_config;
+pub use compression_config::*;
+mod compress_response;
+pub use compress_response::*;
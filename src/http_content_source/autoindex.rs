@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use hyper::{header, HeaderMap};
+use serde::Serialize;
+
+use super::{RequestExecutorBody, RequestExecutorResult, WebContentType};
+
+#[derive(Serialize)]
+struct AutoIndexItem {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<String>,
+}
+
+pub fn is_json_listing_requested(headers: &HeaderMap) -> bool {
+    match headers.get(header::ACCEPT) {
+        Some(accept) => match accept.to_str() {
+            Ok(accept) => accept.contains("application/json"),
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+pub async fn render_local_autoindex(
+    uri_path: &str,
+    dir_path: &Path,
+    json: bool,
+) -> RequestExecutorResult {
+    let mut items = Vec::new();
+
+    if let Ok(mut read_dir) = tokio::fs::read_dir(dir_path).await {
+        while let Ok(Some(entry)) = read_dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // Hidden files are not listed
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let metadata = match entry.metadata().await {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            items.push(AutoIndexItem {
+                name,
+                is_dir: metadata.is_dir(),
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified().ok().map(httpdate::fmt_http_date),
+            });
+        }
+    }
+
+    items.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    if json {
+        return RequestExecutorResult {
+            status_code: 200,
+            content_type: Some(WebContentType::Json),
+            headers: vec![],
+            body: RequestExecutorBody::Full(serde_json::to_vec(&items).unwrap()),
+        };
+    }
+
+    RequestExecutorResult {
+        status_code: 200,
+        content_type: Some(WebContentType::Html),
+        headers: vec![],
+        body: RequestExecutorBody::Full(render_html(uri_path, &items).into_bytes()),
+    }
+}
+
+fn render_html(uri_path: &str, items: &[AutoIndexItem]) -> String {
+    let title = html_escape(uri_path);
+
+    let mut table_lines = String::new();
+
+    if uri_path != "/" {
+        table_lines.push_str(r##"<tr><td><a href="../">../</a></td><td></td><td></td></tr>"##);
+    }
+
+    for item in items {
+        let href = super::percent_encode_segment(&item.name);
+        let name = html_escape(&item.name);
+
+        let (href, name, size) = if item.is_dir {
+            (format!("{href}/"), format!("{name}/"), "-".to_string())
+        } else {
+            (href, name, item.size.to_string())
+        };
+
+        let modified = item.modified.as_deref().unwrap_or("");
+
+        table_lines.push_str(
+            format!(
+                r##"<tr><td><a href="{href}">{name}</a></td><td>{modified}</td><td>{size}</td></tr>"##
+            )
+            .as_str(),
+        );
+    }
+
+    format!(
+        r##"<!DOCTYPE html>
+<html>
+    <head>
+        <title>Index of {title}</title>
+        <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    </head>
+    <body>
+        <h1>Index of {title}</h1>
+        <table>
+        <tr><th>Name</th><th>Last modified</th><th>Size</th></tr>
+        {table_lines}
+        </table>
+    </body>
+</html>"##
+    )
+}
+
+fn html_escape(src: &str) -> String {
+    src.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,3 +1,12 @@
+use std::{collections::HashMap, sync::Mutex};
+
+lazy_static::lazy_static! {
+    // Overrides are coming from global_settings.mime_types and are replaced on each config load
+    static ref MIME_TYPES_OVERRIDE: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+const CHARSET_SUFFIX: &str = "; charset=utf-8";
+
 #[derive(Debug, Clone)]
 pub enum WebContentType {
     Html,
@@ -13,9 +22,9 @@ pub enum WebContentType {
 impl WebContentType {
     pub fn as_str(&self) -> &str {
         match self {
-            WebContentType::Html => "text/html",
-            WebContentType::Css => "text/css",
-            WebContentType::JavaScript => "text/javascript",
+            WebContentType::Html => "text/html; charset=utf-8",
+            WebContentType::Css => "text/css; charset=utf-8",
+            WebContentType::JavaScript => "text/javascript; charset=utf-8",
             WebContentType::Json => "application/json",
             WebContentType::Text => "text/plain; charset=utf-8",
             WebContentType::Png => "image/png",
@@ -25,20 +34,29 @@ impl WebContentType {
     }
 
     pub fn detect_by_extension(path: &str) -> Option<Self> {
-        let res = path.split(".");
+        let extension = get_extension(path)?;
 
-        let el = res.last()?;
+        if let Some(content_type) = MIME_TYPES_OVERRIDE.lock().unwrap().get(extension.as_str()) {
+            return WebContentType::Raw(content_type.to_string()).into();
+        }
 
-        match el.to_lowercase().as_str() {
+        match extension.as_str() {
             "png" => WebContentType::Png.into(),
             "svg" => WebContentType::Svg.into(),
             "css" => WebContentType::Css.into(),
-            "js" => WebContentType::JavaScript.into(),
-            "html" => WebContentType::Html.into(),
-            "htm" => WebContentType::Html.into(),
-            "text" => WebContentType::Text.into(),
+            "js" | "mjs" | "cjs" => WebContentType::JavaScript.into(),
+            "html" | "htm" => WebContentType::Html.into(),
+            "text" | "txt" => WebContentType::Text.into(),
             "json" => WebContentType::Json.into(),
-            _ => None,
+            _ => {
+                let mime_type = get_builtin_mime_type(extension.as_str())?;
+
+                if is_text_mime_type(mime_type) {
+                    return WebContentType::Raw(format!("{}{}", mime_type, CHARSET_SUFFIX)).into();
+                }
+
+                WebContentType::Raw(mime_type.to_string()).into()
+            }
         }
     }
 }
@@ -48,3 +66,190 @@ impl Into<WebContentType> for String {
         WebContentType::Raw(self)
     }
 }
+
+pub fn register_mime_types(mime_types: HashMap<String, String>) {
+    let mut result = HashMap::new();
+
+    for (extension, content_type) in mime_types {
+        result.insert(normalize_extension(&extension), content_type);
+    }
+
+    *MIME_TYPES_OVERRIDE.lock().unwrap() = result;
+}
+
+pub fn normalize_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+fn get_extension(path: &str) -> Option<String> {
+    let file_name = path.rsplit(['/', '\\']).next()?;
+
+    let (_, extension) = file_name.rsplit_once('.')?;
+
+    if extension.is_empty() {
+        return None;
+    }
+
+    Some(extension.to_lowercase())
+}
+
+// Xml and json based types are text as well, but image/svg+xml is served without charset
+fn is_text_mime_type(mime_type: &str) -> bool {
+    if mime_type.starts_with("text/") {
+        return true;
+    }
+
+    match mime_type.strip_prefix("application/") {
+        Some(sub_type) => {
+            sub_type == "javascript"
+                || sub_type == "json"
+                || sub_type == "xml"
+                || sub_type.ends_with("+json")
+                || sub_type.ends_with("+xml")
+        }
+        None => false,
+    }
+}
+
+fn get_builtin_mime_type(extension: &str) -> Option<&'static str> {
+    let result = match extension {
+        // Text
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "md" | "markdown" => "text/markdown",
+        "xml" => "application/xml",
+        "xhtml" => "application/xhtml+xml",
+        "xsl" | "xslt" => "application/xslt+xml",
+        "ics" => "text/calendar",
+        "vtt" => "text/vtt",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "map" => "application/json",
+        "jsonld" => "application/ld+json",
+        "webmanifest" => "application/manifest+json",
+        "geojson" => "application/geo+json",
+        "rss" => "application/rss+xml",
+        "atom" => "application/atom+xml",
+
+        // Images
+        "jpg" | "jpeg" | "jpe" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "cur" => "image/x-icon",
+        "tif" | "tiff" => "image/tiff",
+        "apng" => "image/apng",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "jxl" => "image/jxl",
+
+        // Fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+
+        // Audio
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "oga" | "ogg" => "audio/ogg",
+        "opus" => "audio/opus",
+        "flac" => "audio/flac",
+        "aac" => "audio/aac",
+        "m4a" => "audio/mp4",
+        "weba" => "audio/webm",
+        "mid" | "midi" => "audio/midi",
+
+        // Video
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "avi" => "video/x-msvideo",
+        "mkv" => "video/x-matroska",
+        "mpeg" | "mpg" => "video/mpeg",
+        "ts" => "video/mp2t",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        "mpd" => "application/dash+xml",
+
+        // Applications
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "bz2" => "application/x-bzip2",
+        "xz" => "application/x-xz",
+        "7z" => "application/x-7z-compressed",
+        "rar" => "application/vnd.rar",
+        "zst" => "application/zstd",
+        "br" => "application/x-brotli",
+        "rtf" => "application/rtf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "odp" => "application/vnd.oasis.opendocument.presentation",
+        "epub" => "application/epub+zip",
+        "jar" => "application/java-archive",
+        "apk" => "application/vnd.android.package-archive",
+        "exe" | "dll" | "bin" => "application/octet-stream",
+        "iso" => "application/octet-stream",
+        "dmg" => "application/x-apple-diskimage",
+        "deb" => "application/vnd.debian.binary-package",
+        "rpm" => "application/x-rpm",
+        "swf" => "application/x-shockwave-flash",
+        "pem" | "crt" | "cer" => "application/x-x509-ca-cert",
+        "der" => "application/x-x509-ca-cert",
+        "p12" | "pfx" => "application/x-pkcs12",
+        "sh" => "application/x-sh",
+        "proto" => "text/plain",
+        "log" => "text/plain",
+        "conf" | "ini" | "cfg" => "text/plain",
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_extension() {
+        let detect = |path: &str| {
+            WebContentType::detect_by_extension(path).map(|itm| itm.as_str().to_string())
+        };
+
+        assert_eq!(
+            detect("/app/module.wasm").as_deref(),
+            Some("application/wasm")
+        );
+        assert_eq!(
+            detect("/app/Main.MJS").as_deref(),
+            Some("text/javascript; charset=utf-8")
+        );
+        assert_eq!(detect("/fonts/font.woff2").as_deref(), Some("font/woff2"));
+        assert_eq!(
+            detect("/data/items.csv").as_deref(),
+            Some("text/csv; charset=utf-8")
+        );
+        assert_eq!(
+            detect("/feed.rss").as_deref(),
+            Some("application/rss+xml; charset=utf-8")
+        );
+        assert_eq!(detect("/img/photo.jpeg").as_deref(), Some("image/jpeg"));
+        assert_eq!(detect("/dir.v2/Makefile"), None);
+        assert_eq!(detect("/file."), None);
+        assert_eq!(detect("/file.unknown"), None);
+    }
+}
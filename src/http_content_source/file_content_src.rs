@@ -1,20 +1,48 @@
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use hyper::Uri;
+use bytes::Bytes;
+use futures::SinkExt;
+use http_body_util::{BodyExt, StreamBody};
+use hyper::{body::Frame, HeaderMap, Uri};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
+use crate::http_compression::ContentEncoding;
 use crate::http_proxy_pass::ProxyPassError;
 
 use crate::configurations::*;
 
-use super::{RequestExecutor, RequestExecutorResult, WebContentType};
+use super::{
+    FileInfo, FileRequestHeaders, RequestExecutor, RequestExecutorBody, RequestExecutorResult,
+    UriPathError, WebContentType,
+};
+
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+const STREAM_CHANNEL_SIZE: usize = 4;
+
+const TRY_FILES_URI_PLACEHOLDER: &str = "$uri";
+
+const PRECOMPRESSED_ENCODINGS: [ContentEncoding; 2] =
+    [ContentEncoding::Brotli, ContentEncoding::Gzip];
 
 pub struct LocalPathContentSrc {
     pub file_path: String,
     default_file: Option<String>,
+    autoindex: bool,
+    try_files: Option<Arc<Vec<String>>>,
+    precompressed: bool,
 }
 
 impl LocalPathContentSrc {
-    pub fn new(file_path: &LocalFilePath, default_file: Option<String>) -> Self {
+    pub fn new(
+        file_path: &LocalFilePath,
+        default_file: Option<String>,
+        autoindex: bool,
+        try_files: Option<Vec<String>>,
+        precompressed: bool,
+    ) -> Self {
         let mut file_path = file_path.get_value().to_string();
 
         let last_char = *file_path.as_bytes().last().unwrap() as char;
@@ -25,48 +53,320 @@ impl LocalPathContentSrc {
         Self {
             file_path,
             default_file,
+            autoindex,
+            try_files: try_files.map(Arc::new),
+            precompressed,
         }
     }
 
     pub fn get_request_executor(
         &self,
         uri: &Uri,
+        headers: &HeaderMap,
     ) -> Result<Arc<dyn RequestExecutor + Send + Sync + 'static>, ProxyPassError> {
-        let file_path = if uri.path() == "/" {
-            if let Some(default_file) = self.default_file.as_ref() {
-                format!("{}/{}", self.file_path, default_file)
-            } else {
-                format!("{}{}", self.file_path, uri.path())
-            }
-        } else {
-            format!("{}{}", self.file_path, uri.path())
+        let result = FileRequestExecutor {
+            root_path: self.file_path.clone(),
+            uri_path: uri.path().to_string(),
+            query: uri.query().map(|itm| itm.to_string()),
+            default_file: self.default_file.clone(),
+            autoindex: self.autoindex,
+            try_files: self.try_files.clone(),
+            precompressed: self.precompressed,
+            json_listing: super::is_json_listing_requested(headers),
+            request_headers: FileRequestHeaders::new(headers),
         };
-
-        let result = FileRequestExecutor { file_path };
         Ok(Arc::new(result))
     }
 }
 
 pub struct FileRequestExecutor {
-    file_path: String,
+    root_path: String,
+    uri_path: String,
+    query: Option<String>,
+    default_file: Option<String>,
+    autoindex: bool,
+    try_files: Option<Arc<Vec<String>>>,
+    precompressed: bool,
+    json_listing: bool,
+    request_headers: FileRequestHeaders,
+}
+
+impl FileRequestExecutor {
+    async fn resolve_path(&self, segments: &[String]) -> Option<PathBuf> {
+        let mut file_path = PathBuf::from(&self.root_path);
+        for segment in segments {
+            file_path.push(segment);
+        }
+
+        self.canonicalize_inside_root(&file_path).await
+    }
+
+    // Symlinks are resolved as well, so the link pointing outside of the root is not served
+    async fn canonicalize_inside_root(&self, file_path: &Path) -> Option<PathBuf> {
+        let root_path = tokio::fs::canonicalize(&self.root_path).await.ok()?;
+
+        let file_path = tokio::fs::canonicalize(file_path).await.ok()?;
+
+        if !file_path.starts_with(&root_path) {
+            return None;
+        }
+
+        Some(file_path)
+    }
+
+    async fn serve_file(
+        &self,
+        file_path: &Path,
+        metadata: &std::fs::Metadata,
+    ) -> RequestExecutorResult {
+        let content_type = WebContentType::detect_by_extension(&file_path.to_string_lossy());
+
+        if !self.precompressed {
+            return self.serve_file_content(file_path, metadata, content_type);
+        }
+
+        let mut result = match self
+            .serve_precompressed_file(file_path, &content_type)
+            .await
+        {
+            Some(result) => result,
+            None => self.serve_file_content(file_path, metadata, content_type),
+        };
+
+        result
+            .headers
+            .push((hyper::header::VARY, "Accept-Encoding".to_string()));
+
+        result
+    }
+
+    fn serve_file_content(
+        &self,
+        file_path: &Path,
+        metadata: &std::fs::Metadata,
+        content_type: Option<WebContentType>,
+    ) -> RequestExecutorResult {
+        let file_info = FileInfo {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        };
+
+        let file_response = self.request_headers.get_file_response(&file_info);
+
+        file_response.into_executor_result(&file_info, content_type, |start, len| {
+            stream_file(file_path.to_path_buf(), start, len)
+        })
+    }
+
+    // Serves '.br' or '.gz' sibling of the file, if client accepts the encoding and the sibling exists
+    async fn serve_precompressed_file(
+        &self,
+        file_path: &Path,
+        content_type: &Option<WebContentType>,
+    ) -> Option<RequestExecutorResult> {
+        let accept_encoding = self.request_headers.get_accept_encoding()?;
+
+        let mut encodings = PRECOMPRESSED_ENCODINGS.to_vec();
+
+        while let Some(encoding) =
+            crate::http_compression::negotiate_content_encoding(accept_encoding, &encodings)
+        {
+            encodings.retain(|itm| *itm != encoding);
+
+            let mut compressed_path = file_path.as_os_str().to_os_string();
+            compressed_path.push(".");
+            compressed_path.push(encoding.get_file_extension());
+
+            let compressed_path = match self
+                .canonicalize_inside_root(Path::new(&compressed_path))
+                .await
+            {
+                Some(compressed_path) => compressed_path,
+                None => continue,
+            };
+
+            let metadata = match tokio::fs::metadata(&compressed_path).await {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+
+            let mut result =
+                self.serve_file_content(&compressed_path, &metadata, content_type.clone());
+
+            if result.status_code == 200 || result.status_code == 206 {
+                result.headers.push((
+                    hyper::header::CONTENT_ENCODING,
+                    encoding.as_str().to_string(),
+                ));
+            }
+
+            return Some(result);
+        }
+
+        None
+    }
+
+    async fn serve_directory(
+        &self,
+        uri_path: &str,
+        dir_path: &Path,
+    ) -> Option<RequestExecutorResult> {
+        // Relative links of the default file and the listing work only with trailing slash
+        if !uri_path.ends_with('/') {
+            let location = match self.query.as_ref() {
+                Some(query) => format!("{}/?{}", uri_path, query),
+                None => format!("{}/", uri_path),
+            };
+
+            return Some(RequestExecutorResult {
+                status_code: 301,
+                content_type: None,
+                headers: vec![(hyper::header::LOCATION, location)],
+                body: RequestExecutorBody::Full(vec![]),
+            });
+        }
+
+        if let Some(default_file) = self.default_file.as_ref() {
+            if let Some(default_file_path) = self
+                .canonicalize_inside_root(&dir_path.join(default_file))
+                .await
+            {
+                if let Ok(metadata) = tokio::fs::metadata(&default_file_path).await {
+                    if metadata.is_file() {
+                        return Some(self.serve_file(&default_file_path, &metadata).await);
+                    }
+                }
+            }
+        }
+
+        if self.autoindex {
+            let result = super::render_local_autoindex(uri_path, dir_path, self.json_listing).await;
+            return Some(result);
+        }
+
+        None
+    }
+
+    // Returns None if there is nothing to serve by the path
+    async fn serve_uri_path(
+        &self,
+        uri_path: &str,
+        serve_directory: bool,
+    ) -> Option<RequestExecutorResult> {
+        let segments = match super::get_uri_path_segments(uri_path) {
+            Ok(segments) => segments,
+            Err(UriPathError::InvalidEncoding) => {
+                return Some(RequestExecutorResult {
+                    status_code: 400,
+                    content_type: None,
+                    headers: vec![],
+                    body: RequestExecutorBody::Full("Bad request".as_bytes().to_vec()),
+                });
+            }
+            Err(UriPathError::OutsideOfRoot) => return None,
+        };
+
+        let file_path = self.resolve_path(&segments).await?;
+
+        let metadata = tokio::fs::metadata(&file_path).await.ok()?;
+
+        if metadata.is_dir() {
+            if !serve_directory {
+                return None;
+            }
+
+            return self.serve_directory(uri_path, &file_path).await;
+        }
+
+        Some(self.serve_file(&file_path, &metadata).await)
+    }
 }
 
 #[async_trait::async_trait]
 impl RequestExecutor for FileRequestExecutor {
     async fn execute_request(&self) -> Result<RequestExecutorResult, ProxyPassError> {
-        let result = match tokio::fs::read(&self.file_path).await {
-            Ok(content) => RequestExecutorResult {
-                status_code: 200,
-                content_type: WebContentType::detect_by_extension(&self.file_path),
-                body: content,
-            },
-            Err(_) => RequestExecutorResult {
-                status_code: 404,
-                content_type: None,
-                body: "Not found".as_bytes().to_vec(),
-            },
+        let try_files = match self.try_files.as_ref() {
+            Some(try_files) => try_files,
+            None => {
+                let result = self.serve_uri_path(&self.uri_path, true).await;
+                return Ok(result.unwrap_or_else(create_not_found));
+            }
         };
 
-        Ok(result)
+        // Same as nginx: '$uri' matches files only, '$uri/' matches directories
+        for try_file in try_files.iter() {
+            let uri_path = try_file.replace(TRY_FILES_URI_PLACEHOLDER, &self.uri_path);
+
+            let serve_directory = uri_path.ends_with('/');
+
+            if let Some(result) = self.serve_uri_path(&uri_path, serve_directory).await {
+                return Ok(result);
+            }
+        }
+
+        Ok(create_not_found())
+    }
+}
+
+fn create_not_found() -> RequestExecutorResult {
+    RequestExecutorResult {
+        status_code: 404,
+        content_type: None,
+        headers: vec![],
+        body: RequestExecutorBody::Full("Not found".as_bytes().to_vec()),
+    }
+}
+
+fn stream_file(file_path: PathBuf, start: u64, len: u64) -> RequestExecutorBody {
+    let (mut sender, receiver) = futures::channel::mpsc::channel(STREAM_CHANNEL_SIZE);
+
+    tokio::spawn(async move {
+        let mut file = match tokio::fs::File::open(&file_path).await {
+            Ok(file) => file,
+            Err(err) => {
+                let _ = sender.send(Err(format!("{:?}", err))).await;
+                return;
+            }
+        };
+
+        if start > 0 {
+            if let Err(err) = file.seek(std::io::SeekFrom::Start(start)).await {
+                let _ = sender.send(Err(format!("{:?}", err))).await;
+                return;
+            }
+        }
+
+        let mut remains = len;
+        let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+
+        while remains > 0 {
+            let to_read = remains.min(buffer.len() as u64) as usize;
+
+            let read = match file.read(&mut buffer[..to_read]).await {
+                Ok(0) => {
+                    let _ = sender.send(Err("File is truncated".to_string())).await;
+                    return;
+                }
+                Ok(read) => read,
+                Err(err) => {
+                    let _ = sender.send(Err(format!("{:?}", err))).await;
+                    return;
+                }
+            };
+
+            remains -= read as u64;
+
+            let frame = Frame::data(Bytes::copy_from_slice(&buffer[..read]));
+
+            // Client is gone
+            if sender.send(Ok(frame)).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    RequestExecutorBody::Stream {
+        content_length: len,
+        body: StreamBody::new(receiver).boxed(),
     }
 }
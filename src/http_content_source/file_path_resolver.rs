@@ -0,0 +1,94 @@
+pub enum UriPathError {
+    InvalidEncoding,
+    OutsideOfRoot,
+}
+
+// Percent-decodes the uri path and resolves '.' and '..' segments.
+// Returns segments relative to the root of the location
+pub fn get_uri_path_segments(uri_path: &str) -> Result<Vec<String>, UriPathError> {
+    let decoded = percent_decode(uri_path).ok_or(UriPathError::InvalidEncoding)?;
+
+    let mut result: Vec<String> = Vec::new();
+
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if result.pop().is_none() {
+                    return Err(UriPathError::OutsideOfRoot);
+                }
+            }
+            _ => {
+                if segment.contains('\0') || segment.contains('\\') {
+                    return Err(UriPathError::InvalidEncoding);
+                }
+
+                result.push(segment.to_string());
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn percent_decode(src: &str) -> Option<String> {
+    let src = src.as_bytes();
+    let mut result = Vec::with_capacity(src.len());
+
+    let mut i = 0;
+    while i < src.len() {
+        if src[i] == b'%' {
+            let hex = src.get(i + 1..i + 3)?;
+            let hex = std::str::from_utf8(hex).ok()?;
+            result.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            result.push(src[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(result).ok()
+}
+
+pub fn percent_encode_segment(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+
+    for b in src.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                result.push(*b as char)
+            }
+            _ => result.push_str(format!("%{:02X}", b).as_str()),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_path_segments() {
+        let result = get_uri_path_segments("/dir/my%20file.txt").ok().unwrap();
+        assert_eq!(result, vec!["dir", "my file.txt"]);
+
+        let result = get_uri_path_segments("/dir/./sub/../file.txt")
+            .ok()
+            .unwrap();
+        assert_eq!(result, vec!["dir", "file.txt"]);
+
+        let result = get_uri_path_segments("/%D1%84%D0%B0%D0%B9%D0%BB")
+            .ok()
+            .unwrap();
+        assert_eq!(result, vec!["файл"]);
+
+        assert!(get_uri_path_segments("/../etc/passwd").is_err());
+        assert!(get_uri_path_segments("/dir/%2e%2e/%2e%2e/etc/passwd").is_err());
+        assert!(get_uri_path_segments("/dir/..%2f..%2fetc").is_err());
+        assert!(get_uri_path_segments("/file%00.txt").is_err());
+        assert!(get_uri_path_segments("/file%zz").is_err());
+    }
+}
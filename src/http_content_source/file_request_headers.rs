@@ -0,0 +1,311 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyper::{header, HeaderMap};
+
+use super::{RequestExecutorBody, RequestExecutorResult, WebContentType};
+
+pub struct FileInfo {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileInfo {
+    pub fn get_etag(&self) -> Option<String> {
+        let modified = self.modified?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!("\"{:x}-{:x}\"", self.size, modified.as_secs()))
+    }
+
+    pub fn get_last_modified(&self) -> Option<String> {
+        let modified = self.modified?;
+        Some(httpdate::fmt_http_date(modified))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FileResponse {
+    NotModified,
+    Full,
+    Partial { start: u64, end: u64 },
+    NotSatisfiable,
+}
+
+impl FileResponse {
+    pub fn into_executor_result(
+        self,
+        file_info: &FileInfo,
+        content_type: Option<WebContentType>,
+        get_body: impl FnOnce(u64, u64) -> RequestExecutorBody,
+    ) -> RequestExecutorResult {
+        let mut headers = Vec::new();
+
+        if let Some(etag) = file_info.get_etag() {
+            headers.push((header::ETAG, etag));
+        }
+
+        if let Some(last_modified) = file_info.get_last_modified() {
+            headers.push((header::LAST_MODIFIED, last_modified));
+        }
+
+        match self {
+            FileResponse::NotModified => RequestExecutorResult {
+                status_code: 304,
+                content_type: None,
+                headers,
+                body: RequestExecutorBody::Full(vec![]),
+            },
+            FileResponse::Full => {
+                headers.push((header::ACCEPT_RANGES, "bytes".to_string()));
+
+                RequestExecutorResult {
+                    status_code: 200,
+                    content_type,
+                    headers,
+                    body: get_body(0, file_info.size),
+                }
+            }
+            FileResponse::Partial { start, end } => {
+                headers.push((header::ACCEPT_RANGES, "bytes".to_string()));
+                headers.push((
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, file_info.size),
+                ));
+
+                RequestExecutorResult {
+                    status_code: 206,
+                    content_type,
+                    headers,
+                    body: get_body(start, end - start + 1),
+                }
+            }
+            FileResponse::NotSatisfiable => {
+                headers.push((header::CONTENT_RANGE, format!("bytes */{}", file_info.size)));
+
+                RequestExecutorResult {
+                    status_code: 416,
+                    content_type: None,
+                    headers,
+                    body: RequestExecutorBody::Full(vec![]),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct FileRequestHeaders {
+    range: Option<String>,
+    if_range: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
+    accept_encoding: Option<String>,
+}
+
+impl FileRequestHeaders {
+    pub fn new(headers: &HeaderMap) -> Self {
+        let get_header = |name: header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        Self {
+            range: get_header(header::RANGE),
+            if_range: get_header(header::IF_RANGE),
+            if_none_match: get_header(header::IF_NONE_MATCH),
+            if_modified_since: get_header(header::IF_MODIFIED_SINCE),
+            accept_encoding: get_header(header::ACCEPT_ENCODING),
+        }
+    }
+
+    pub fn get_accept_encoding(&self) -> Option<&str> {
+        self.accept_encoding.as_deref()
+    }
+
+    pub fn get_file_response(&self, file_info: &FileInfo) -> FileResponse {
+        if self.is_not_modified(file_info) {
+            return FileResponse::NotModified;
+        }
+
+        let range = match self.range.as_ref() {
+            Some(range) => range,
+            None => return FileResponse::Full,
+        };
+
+        if let Some(if_range) = self.if_range.as_ref() {
+            let if_range = if_range.trim();
+            let matches = file_info.get_etag().as_deref() == Some(if_range)
+                || file_info.get_last_modified().as_deref() == Some(if_range);
+
+            if !matches {
+                return FileResponse::Full;
+            }
+        }
+
+        parse_range(range, file_info.size)
+    }
+
+    fn is_not_modified(&self, file_info: &FileInfo) -> bool {
+        // If-None-Match has priority over If-Modified-Since by RFC 9110
+        if let Some(if_none_match) = self.if_none_match.as_ref() {
+            let etag = match file_info.get_etag() {
+                Some(etag) => etag,
+                None => return false,
+            };
+
+            return if_none_match.split(',').any(|itm| {
+                let itm = itm.trim();
+                itm == "*" || itm.trim_start_matches("W/") == etag
+            });
+        }
+
+        let if_modified_since = match self.if_modified_since.as_ref() {
+            Some(if_modified_since) => if_modified_since,
+            None => return false,
+        };
+
+        let since = match httpdate::parse_http_date(if_modified_since) {
+            Ok(since) => since,
+            Err(_) => return false,
+        };
+
+        match file_info.modified {
+            // Http date has seconds precision
+            Some(modified) => match modified.duration_since(since) {
+                Ok(diff) => diff.as_secs() == 0,
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
+}
+
+fn parse_range(range: &str, size: u64) -> FileResponse {
+    let range = match range.trim().strip_prefix("bytes=") {
+        Some(range) => range.trim(),
+        None => return FileResponse::Full,
+    };
+
+    // Multipart ranges are not supported. Serving the whole file is allowed by RFC
+    if range.contains(',') {
+        return FileResponse::Full;
+    }
+
+    let (start, end) = match range.split_once('-') {
+        Some(value) => value,
+        None => return FileResponse::Full,
+    };
+
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        let suffix_len: u64 = match end.parse() {
+            Ok(value) => value,
+            Err(_) => return FileResponse::Full,
+        };
+
+        if suffix_len == 0 || size == 0 {
+            return FileResponse::NotSatisfiable;
+        }
+
+        return FileResponse::Partial {
+            start: size - suffix_len.min(size),
+            end: size - 1,
+        };
+    }
+
+    let start: u64 = match start.parse() {
+        Ok(value) => value,
+        Err(_) => return FileResponse::Full,
+    };
+
+    if start >= size {
+        return FileResponse::NotSatisfiable;
+    }
+
+    let end = if end.is_empty() {
+        size - 1
+    } else {
+        match end.parse::<u64>() {
+            Ok(value) if value >= start => value.min(size - 1),
+            _ => return FileResponse::Full,
+        }
+    };
+
+    FileResponse::Partial { start, end }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            FileResponse::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            FileResponse::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            FileResponse::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            FileResponse::Partial {
+                start: 500,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=1000-", 1000),
+            FileResponse::NotSatisfiable
+        );
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), FileResponse::Full);
+        assert_eq!(parse_range("items=0-1", 1000), FileResponse::Full);
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let file_info = FileInfo {
+            size: 10,
+            modified: Some(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            file_info.get_etag().unwrap().parse().unwrap(),
+        );
+        assert_eq!(
+            FileRequestHeaders::new(&headers).get_file_response(&file_info),
+            FileResponse::NotModified
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            file_info.get_last_modified().unwrap().parse().unwrap(),
+        );
+        assert_eq!(
+            FileRequestHeaders::new(&headers).get_file_response(&file_info),
+            FileResponse::NotModified
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, "\"other\"".parse().unwrap());
+        assert_eq!(
+            FileRequestHeaders::new(&headers).get_file_response(&file_info),
+            FileResponse::Full
+        );
+    }
+}
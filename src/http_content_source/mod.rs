@@ -6,6 +6,12 @@ mod ssh_file_content_src;
 pub use ssh_file_content_src::*;
 mod request_executor;
 pub use request_executor::*;
+mod file_request_headers;
+pub use file_request_headers::*;
+mod file_path_resolver;
+pub use file_path_resolver::*;
+mod autoindex;
+pub use autoindex::*;
 mod content_type;
 pub use content_type::*;
 mod static_content_src;
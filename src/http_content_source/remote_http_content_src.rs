@@ -1,4 +1,8 @@
-use std::{future::Future, sync::atomic::AtomicI64};
+use std::{
+    future::Future,
+    sync::{atomic::AtomicI64, Arc},
+    time::Duration,
+};
 
 use bytes::Bytes;
 use http_body_util::Full;
@@ -7,6 +11,7 @@ use rust_extensions::{date_time::DateTimeAsMicroseconds, StopWatch};
 
 use crate::{
     app::AppContext,
+    configurations::UpstreamTlsConfig,
     http_client::HttpClient,
     http_proxy_pass::{HttpProxyPassRemoteEndpoint, ProxyPassError},
 };
@@ -16,16 +21,26 @@ static CONNECTIONS: AtomicI64 = AtomicI64::new(0);
 pub struct RemoteHttpContentSource {
     http_client: HttpClient,
     pub remote_endpoint: HttpProxyPassRemoteEndpoint,
+    upstream_tls: Option<Arc<UpstreamTlsConfig>>,
+    connect_timeout: Duration,
     id: i64,
     debug: bool,
 }
 
 impl RemoteHttpContentSource {
-    pub fn new(id: i64, remote_endpoint: HttpProxyPassRemoteEndpoint, debug: bool) -> Self {
+    pub fn new(
+        id: i64,
+        remote_endpoint: HttpProxyPassRemoteEndpoint,
+        upstream_tls: Option<Arc<UpstreamTlsConfig>>,
+        connect_timeout: Duration,
+        debug: bool,
+    ) -> Self {
         CONNECTIONS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         Self {
             http_client: HttpClient::new(),
             remote_endpoint,
+            upstream_tls,
+            connect_timeout,
             id,
             debug,
         }
@@ -46,18 +61,58 @@ impl RemoteHttpContentSource {
                 if debug {
                     println!("Connecting to Http remote endpoint: {:?}", uri);
                 }
-                self.http_client.connect_to_http1(uri, domain_name).await?;
+                self.http_client
+                    .connect_to_http1(
+                        uri,
+                        domain_name,
+                        self.upstream_tls.as_ref(),
+                        self.connect_timeout,
+                    )
+                    .await?;
             }
 
             HttpProxyPassRemoteEndpoint::Http2(uri) => {
                 if debug {
                     println!("Connecting to Http2 remote endpoint: {:?}", uri);
                 }
-                self.http_client.connect_to_http2(uri).await?;
+                self.http_client
+                    .connect_to_http2(
+                        uri,
+                        domain_name,
+                        self.upstream_tls.as_ref(),
+                        self.connect_timeout,
+                    )
+                    .await?;
+            }
+
+            HttpProxyPassRemoteEndpoint::Http1UnixSocket(unix_socket_path) => {
+                if debug {
+                    println!(
+                        "Connecting to Http unix socket remote endpoint: {}",
+                        unix_socket_path
+                    );
+                }
+                self.http_client
+                    .connect_to_http1_unix_socket(unix_socket_path, self.connect_timeout)
+                    .await?;
+            }
+
+            HttpProxyPassRemoteEndpoint::Http2UnixSocket(unix_socket_path) => {
+                if debug {
+                    println!(
+                        "Connecting to Http2 unix socket remote endpoint: {}",
+                        unix_socket_path
+                    );
+                }
+                self.http_client
+                    .connect_to_http2_unix_socket(unix_socket_path, self.connect_timeout)
+                    .await?;
             }
 
             HttpProxyPassRemoteEndpoint::Http1OverSsh {
                 ssh_credentials,
+                ssh_jump_hosts,
+                ssh_host_keys,
                 remote_host,
             } => {
                 let mut sw = StopWatch::new();
@@ -72,7 +127,13 @@ impl RemoteHttpContentSource {
                     );
                 }
                 self.http_client
-                    .connect_to_http1_over_ssh(app, ssh_credentials, remote_host)
+                    .connect_to_http1_over_ssh(
+                        app,
+                        ssh_jump_hosts,
+                        ssh_credentials,
+                        ssh_host_keys,
+                        remote_host,
+                    )
                     .await?;
                 sw.pause();
 
@@ -89,6 +150,8 @@ impl RemoteHttpContentSource {
 
             HttpProxyPassRemoteEndpoint::Http2OverSsh {
                 ssh_credentials,
+                ssh_jump_hosts,
+                ssh_host_keys,
                 remote_host,
             } => {
                 let mut sw = StopWatch::new();
@@ -104,7 +167,13 @@ impl RemoteHttpContentSource {
                     );
                 }
                 self.http_client
-                    .connect_to_http2_over_ssh(app, ssh_credentials, remote_host)
+                    .connect_to_http2_over_ssh(
+                        app,
+                        ssh_jump_hosts,
+                        ssh_credentials,
+                        ssh_host_keys,
+                        remote_host,
+                    )
                     .await?;
                 sw.pause();
 
@@ -137,6 +206,17 @@ impl RemoteHttpContentSource {
         Ok(result)
     }
 
+    // Http1 connection can not take the next request until the response of the current one is read
+    pub fn is_http1_ready(&mut self) -> Result<bool, ProxyPassError> {
+        let result = self
+            .http_client
+            .unwrap_as_http1_mut(self.id)?
+            .send_request
+            .is_ready();
+
+        Ok(result)
+    }
+
     pub fn send_http2_request(
         &mut self,
         req: hyper::Request<Full<Bytes>>,
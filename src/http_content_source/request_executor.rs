@@ -1,11 +1,24 @@
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::header::HeaderName;
+
 use crate::http_proxy_pass::ProxyPassError;
 
 use super::WebContentType;
 
+pub enum RequestExecutorBody {
+    Full(Vec<u8>),
+    Stream {
+        content_length: u64,
+        body: BoxBody<Bytes, String>,
+    },
+}
+
 pub struct RequestExecutorResult {
     pub status_code: u16,
     pub content_type: Option<WebContentType>,
-    pub body: Vec<u8>,
+    pub headers: Vec<(HeaderName, String)>,
+    pub body: RequestExecutorBody,
 }
 
 #[async_trait::async_trait]
@@ -1,16 +1,24 @@
 use std::{sync::Arc, time::Duration};
 
-use hyper::Uri;
-use my_ssh::{SshCredentials, SshSession};
+use hyper::{HeaderMap, Uri};
+use my_ssh::SshCredentials;
 use tokio::sync::Mutex;
 
-use crate::{app::AppContext, http_proxy_pass::ProxyPassError};
+use crate::{
+    app::AppContext, http_proxy_pass::ProxyPassError, ssh_host_keys::SshChainHostKeys,
+    ssh_jump_hosts::SshChainSession,
+};
 
-use super::{RequestExecutor, RequestExecutorResult, WebContentType};
+use super::{
+    FileInfo, FileRequestHeaders, FileResponse, RequestExecutor, RequestExecutorBody,
+    RequestExecutorResult, WebContentType,
+};
 
 pub struct PathOverSshContentSource {
-    ssh_session: Option<Arc<SshSession>>,
+    ssh_session: Option<Arc<SshChainSession>>,
     ssh_credentials: Arc<SshCredentials>,
+    ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+    ssh_host_keys: Arc<SshChainHostKeys>,
     home_value: Arc<Mutex<Option<String>>>,
     default_file: Option<String>,
     pub file_path: String,
@@ -20,6 +28,8 @@ pub struct PathOverSshContentSource {
 impl PathOverSshContentSource {
     pub fn new(
         ssh_credentials: Arc<SshCredentials>,
+        ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+        ssh_host_keys: Arc<SshChainHostKeys>,
         file_path: String,
         default_file: Option<String>,
         execute_timeout: Duration,
@@ -28,6 +38,8 @@ impl PathOverSshContentSource {
             ssh_session: None,
             file_path,
             ssh_credentials,
+            ssh_jump_hosts,
+            ssh_host_keys,
             home_value: Arc::new(Mutex::new(None)),
             default_file,
             execute_timeout,
@@ -38,56 +50,92 @@ impl PathOverSshContentSource {
             return Ok(());
         }
 
-        let ssh_session = Arc::new(SshSession::new(self.ssh_credentials.clone()));
+        let ssh_session = SshChainSession::connect(
+            &self.ssh_jump_hosts,
+            &self.ssh_credentials,
+            &self.ssh_host_keys,
+            app.connection_settings.remote_connect_timeout,
+        )
+        .await?;
 
-        let (host, port) = self.ssh_credentials.get_host_port();
-        ssh_session
-            .connect_to_remote_host(host, port, app.connection_settings.remote_connect_timeout)
-            .await?;
-
-        self.ssh_session = Some(ssh_session);
+        self.ssh_session = Some(Arc::new(ssh_session));
         Ok(())
     }
 
     pub fn get_request_executor(
         &self,
         uri: &Uri,
+        headers: &HeaderMap,
     ) -> Result<Arc<dyn RequestExecutor + Send + Sync + 'static>, ProxyPassError> {
         if self.ssh_session.is_none() {
             return Err(ProxyPassError::ConnectionIsDisposed);
         }
 
-        let file_path = if uri.path() == "/" {
+        // Remote path can not be canonicalized without extra round trip, so '..' is resolved within the uri only
+        let segments = match super::get_uri_path_segments(uri.path()) {
+            Ok(segments) => segments,
+            Err(_) => return Err(ProxyPassError::NoLocationFound),
+        };
+
+        let mut file_path = self.file_path.trim_end_matches('/').to_string();
+        for segment in &segments {
+            file_path.push('/');
+            file_path.push_str(segment);
+        }
+
+        if uri.path().ends_with('/') {
             if let Some(default_file) = self.default_file.as_ref() {
-                format!("{}/{}", self.file_path, default_file)
-            } else {
-                format!("{}{}", self.file_path, uri.path())
+                file_path.push('/');
+                file_path.push_str(default_file);
             }
-        } else {
-            format!("{}{}", self.file_path, uri.path())
-        };
+        }
 
         let result = FileOverSshRequestExecutor {
             file_path,
             session: self.ssh_session.as_ref().unwrap().clone(),
             home_value: self.home_value.clone(),
             execute_timeout: self.execute_timeout,
+            request_headers: FileRequestHeaders::new(headers),
         };
         Ok(Arc::new(result))
     }
 }
 
 pub struct FileOverSshRequestExecutor {
-    session: Arc<SshSession>,
+    session: Arc<SshChainSession>,
     file_path: String,
     home_value: Arc<Mutex<Option<String>>>,
     execute_timeout: Duration,
+    request_headers: FileRequestHeaders,
+}
+
+impl FileOverSshRequestExecutor {
+    // Remote file is downloaded as a whole, so stat is the only way to answer 304 and 416 without downloading it.
+    // Works with GNU stat. If stat is not available - size is taken from the downloaded content
+    async fn get_file_info(&self, file_path: &str) -> Option<FileInfo> {
+        let command = format!("stat -c '%s %Y' '{}'", file_path.replace("'", "'\\''"));
+
+        let (output, _) = self
+            .session
+            .execute_command(&command, self.execute_timeout)
+            .await
+            .ok()?;
+
+        let mut parts = output.trim().split(' ');
+        let size: u64 = parts.next()?.parse().ok()?;
+        let modified: u64 = parts.next()?.parse().ok()?;
+
+        Some(FileInfo {
+            size,
+            modified: Some(std::time::UNIX_EPOCH + Duration::from_secs(modified)),
+        })
+    }
 }
 
 #[async_trait::async_trait]
 impl RequestExecutor for FileOverSshRequestExecutor {
     async fn execute_request(&self) -> Result<RequestExecutorResult, ProxyPassError> {
-        let file_path = if self.file_path.contains("~") {
+        let file_path = if self.file_path.starts_with("~") {
             let mut home_value = self.home_value.lock().await;
 
             if home_value.is_none() {
@@ -98,23 +146,62 @@ impl RequestExecutor for FileOverSshRequestExecutor {
                 home_value.replace(home.trim().to_string());
             }
 
-            let result = self.file_path.replace("~", home_value.as_ref().unwrap());
+            let result = self
+                .file_path
+                .replacen("~", home_value.as_ref().unwrap(), 1);
             result
         } else {
             self.file_path.clone()
         };
 
+        let file_info = self.get_file_info(&file_path).await;
+
+        let file_response = match file_info.as_ref() {
+            Some(file_info) => self.request_headers.get_file_response(file_info),
+            None => FileResponse::Full,
+        };
+
+        match file_response {
+            FileResponse::NotModified | FileResponse::NotSatisfiable => {
+                return Ok(file_response.into_executor_result(
+                    file_info.as_ref().unwrap(),
+                    None,
+                    |_, _| RequestExecutorBody::Full(vec![]),
+                ));
+            }
+            _ => {}
+        }
+
         let result = self
             .session
             .download_remote_file(&file_path, self.execute_timeout)
             .await;
 
         match result {
-            Ok(content) => Ok(RequestExecutorResult {
-                status_code: 200,
-                content_type: WebContentType::detect_by_extension(&file_path),
-                body: content,
-            }),
+            Ok(content) => {
+                let file_info = match file_info {
+                    Some(file_info) if file_info.size == content.len() as u64 => file_info,
+                    // File is changed between stat and download. Serving what is downloaded
+                    _ => {
+                        return Ok(RequestExecutorResult {
+                            status_code: 200,
+                            content_type: WebContentType::detect_by_extension(&file_path),
+                            headers: vec![],
+                            body: RequestExecutorBody::Full(content),
+                        });
+                    }
+                };
+
+                Ok(file_response.into_executor_result(
+                    &file_info,
+                    WebContentType::detect_by_extension(&file_path),
+                    |start, len| {
+                        let start = start as usize;
+                        let end = start + len as usize;
+                        RequestExecutorBody::Full(content[start..end].to_vec())
+                    },
+                ))
+            }
             Err(err) => {
                 println!("{} -> Error: {:?}", file_path, err);
                 match &err {
@@ -125,7 +212,10 @@ impl RequestExecutor for FileOverSshRequestExecutor {
                                     return Ok(RequestExecutorResult {
                                         status_code: 404,
                                         content_type: None,
-                                        body: "Not found".as_bytes().to_vec(),
+                                        headers: vec![],
+                                        body: RequestExecutorBody::Full(
+                                            "Not found".as_bytes().to_vec(),
+                                        ),
                                     });
                                 }
                             }
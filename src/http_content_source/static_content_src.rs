@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use crate::http_proxy_pass::ProxyPassError;
 
-use super::{RequestExecutor, RequestExecutorResult, WebContentType};
+use super::{RequestExecutor, RequestExecutorBody, RequestExecutorResult, WebContentType};
 
 pub struct StaticContentSrc {
     pub inner: Arc<StaticContentExecutor>,
@@ -42,7 +42,8 @@ impl RequestExecutor for StaticContentExecutor {
             } else {
                 None
             },
-            body: self.body.clone(),
+            headers: vec![],
+            body: RequestExecutorBody::Full(self.body.clone()),
         })
     }
 }
@@ -0,0 +1,9 @@
+use my_http_server::macros::MyHttpInput;
+
+#[derive(MyHttpInput)]
+pub struct PurgeCacheInputData {
+    #[http_query(description = "Endpoint host. All endpoints if empty")]
+    pub endpoint: Option<String>,
+    #[http_query(description = "Path prefix of purged responses. All responses if empty")]
+    pub path: Option<String>,
+}
@@ -0,0 +1,3 @@
+mod purge_cache_action;
+pub use purge_cache_action::*;
+mod contracts;
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use my_http_server::{macros::http_route, HttpContext, HttpFailResult, HttpOkResult, HttpOutput};
+
+use super::contracts::*;
+use crate::app::AppContext;
+
+#[http_route(
+    method: "DELETE",
+    route: "/api/cache",
+    summary: "Purge response cache",
+    description: "Purge cached responses of the locations. Returns amount of purged responses",
+    controller: "Cache",
+    input_data: "PurgeCacheInputData",
+    result:[
+        {status_code: 200, description: "Ok response", model:"String"},
+    ]
+)]
+pub struct PurgeCacheAction {
+    app: Arc<AppContext>,
+}
+
+impl PurgeCacheAction {
+    pub fn new(app: Arc<AppContext>) -> Self {
+        Self { app }
+    }
+}
+async fn handle_request(
+    action: &PurgeCacheAction,
+    input_data: PurgeCacheInputData,
+    _ctx: &HttpContext,
+) -> Result<HttpOkResult, HttpFailResult> {
+    let config = action.app.get_current_app_configuration().await;
+
+    let mut purged = 0;
+
+    for listen_port_config in config.http_endpoints.values() {
+        for endpoint_info in listen_port_config.endpoint_info.iter() {
+            if let Some(endpoint) = input_data.endpoint.as_ref() {
+                if endpoint_info.host_endpoint.as_str() != endpoint.as_str() {
+                    continue;
+                }
+            }
+
+            for location in endpoint_info.locations.iter() {
+                if let Some(cache) = location.cache.as_ref() {
+                    purged += cache.purge(input_data.path.as_deref()).await;
+                }
+            }
+        }
+    }
+
+    HttpOutput::as_text(format!("Purged responses: {}", purged))
+        .into_ok_result(true)
+        .into()
+}
@@ -1,17 +1,25 @@
-use std::sync::Arc;
+use std::sync::{atomic::Ordering, Arc};
 
 use my_http_server::macros::MyHttpObjectStructure;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
 use serde::*;
 
-use crate::configurations::*;
+use crate::{
+    circuit_breaker::CircuitBreaker,
+    configurations::*,
+    http_proxy_pass::{WebSocketConnection, WebSocketConnections},
+    traffic_mirror::TrafficMirror,
+};
 
 #[derive(MyHttpObjectStructure, Serialize)]
 pub struct CurrentConfigurationHttpModel {
     pub http: Vec<HttpConfigurationHttpModel>,
+    pub udp: Vec<UdpConfigurationHttpModel>,
+    pub web_sockets: Vec<WebSocketConnectionHttpModel>,
 }
 
 impl CurrentConfigurationHttpModel {
-    pub fn new(config: &AppConfiguration) -> Self {
+    pub fn new(config: &AppConfiguration, web_socket_connections: &WebSocketConnections) -> Self {
         let mut http = Vec::new();
 
         for (port, listen_port_config) in &config.http_endpoints {
@@ -21,7 +29,49 @@ impl CurrentConfigurationHttpModel {
             ))
         }
 
-        Self { http }
+        let mut udp = Vec::new();
+
+        for (port, udp_endpoint_config) in &config.udp_endpoints {
+            udp.push(UdpConfigurationHttpModel::new(
+                *port,
+                udp_endpoint_config.as_ref(),
+            ));
+        }
+
+        let now = DateTimeAsMicroseconds::now();
+
+        let web_sockets = web_socket_connections
+            .get_snapshot()
+            .iter()
+            .map(|itm| WebSocketConnectionHttpModel::new(itm, now))
+            .collect();
+
+        Self {
+            http,
+            udp,
+            web_sockets,
+        }
+    }
+}
+
+#[derive(MyHttpObjectStructure, Serialize)]
+pub struct UdpConfigurationHttpModel {
+    pub port: u16,
+    pub host: String,
+    pub to: String,
+    pub idle_timeout_sec: u64,
+    pub debug: bool,
+}
+
+impl UdpConfigurationHttpModel {
+    pub fn new(port: u16, endpoint: &UdpEndpointHostConfig) -> Self {
+        Self {
+            port,
+            host: endpoint.host.as_str().to_string(),
+            to: endpoint.remote_addr.to_string(),
+            idle_timeout_sec: endpoint.idle_timeout.as_secs(),
+            debug: endpoint.debug,
+        }
     }
 }
 
@@ -73,14 +123,188 @@ pub struct HttpProxyPassLocationModel {
     pub to: String,
     #[serde(rename = "type")]
     pub r#type: String,
+    pub circuit_breakers: Vec<CircuitBreakerHttpModel>,
+    pub mirror: Option<TrafficMirrorHttpModel>,
+    pub split_variants: Vec<TrafficSplitVariantHttpModel>,
 }
 
 impl HttpProxyPassLocationModel {
     pub fn new(src: &Arc<ProxyPassLocationConfig>) -> Self {
+        let circuit_breakers = match src.circuit_breaker.as_ref() {
+            Some(circuit_breaker) => circuit_breaker
+                .circuit_breakers
+                .iter()
+                .map(|itm| CircuitBreakerHttpModel::new(itm))
+                .collect(),
+            None => vec![],
+        };
+
+        let split_variants = match src.split.as_ref() {
+            Some(split) => split
+                .variants
+                .iter()
+                .map(|itm| TrafficSplitVariantHttpModel::new(itm))
+                .collect(),
+            None => vec![],
+        };
+
         Self {
             path: src.path.to_string(),
             to: src.get_proxy_pass_to_as_string(),
             r#type: src.remote_type.to_str().to_string(),
+            circuit_breakers,
+            mirror: src
+                .mirror
+                .as_ref()
+                .map(|itm| TrafficMirrorHttpModel::new(itm)),
+            split_variants,
         }
     }
 }
+
+#[derive(MyHttpObjectStructure, Serialize)]
+pub struct CircuitBreakerHttpModel {
+    pub upstream: String,
+    pub state: String,
+    pub consecutive_failures: usize,
+    pub error_rate: f64,
+    pub requests_in_window: usize,
+    pub opened: usize,
+    pub open_remaining_ms: Option<u64>,
+}
+
+impl CircuitBreakerHttpModel {
+    pub fn new(src: &CircuitBreaker) -> Self {
+        let snapshot = src.get_snapshot();
+        Self {
+            upstream: snapshot.upstream,
+            state: snapshot.state.as_str().to_string(),
+            consecutive_failures: snapshot.consecutive_failures,
+            error_rate: snapshot.error_rate,
+            requests_in_window: snapshot.requests_in_window,
+            opened: snapshot.opened,
+            open_remaining_ms: snapshot
+                .open_remaining
+                .map(|open_remaining| open_remaining.as_millis() as u64),
+        }
+    }
+}
+
+#[derive(MyHttpObjectStructure, Serialize)]
+pub struct TrafficMirrorHttpModel {
+    pub upstream: String,
+    pub percent: f64,
+    pub mirrored: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    pub in_flight: usize,
+}
+
+impl TrafficMirrorHttpModel {
+    pub fn new(src: &TrafficMirror) -> Self {
+        let snapshot = src.get_snapshot();
+        Self {
+            upstream: snapshot.upstream,
+            percent: snapshot.percent,
+            mirrored: snapshot.mirrored,
+            failed: snapshot.failed,
+            dropped: snapshot.dropped,
+            in_flight: snapshot.in_flight,
+        }
+    }
+}
+
+#[derive(MyHttpObjectStructure, Serialize)]
+pub struct TrafficSplitVariantHttpModel {
+    pub name: String,
+    pub to: String,
+    pub weight: f64,
+}
+
+impl TrafficSplitVariantHttpModel {
+    pub fn new(src: &TrafficSplitVariant) -> Self {
+        Self {
+            name: src.name.clone(),
+            to: src.config.get_proxy_pass_to_as_string(),
+            weight: src.weight,
+        }
+    }
+}
+
+#[derive(MyHttpObjectStructure, Serialize)]
+pub struct WebSocketConnectionHttpModel {
+    pub endpoint: String,
+    pub path: String,
+    pub client_addr: String,
+    pub connected_at: String,
+    pub uptime_sec: u64,
+    pub messages_from_client: u64,
+    pub bytes_from_client: u64,
+    pub messages_from_remote: u64,
+    pub bytes_from_remote: u64,
+}
+
+impl WebSocketConnectionHttpModel {
+    pub fn new(src: &WebSocketConnection, now: DateTimeAsMicroseconds) -> Self {
+        Self {
+            endpoint: src.endpoint.clone(),
+            path: src.path.clone(),
+            client_addr: src.client_addr.clone(),
+            connected_at: src.connected_at.to_rfc3339(),
+            uptime_sec: now
+                .duration_since(src.connected_at)
+                .as_positive_or_zero()
+                .as_secs(),
+            messages_from_client: src.messages_from_client.load(Ordering::Relaxed),
+            bytes_from_client: src.bytes_from_client.load(Ordering::Relaxed),
+            messages_from_remote: src.messages_from_remote.load(Ordering::Relaxed),
+            bytes_from_remote: src.bytes_from_remote.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::http_proxy_pass::WebSocketConnections;
+
+    use super::WebSocketConnectionHttpModel;
+
+    #[test]
+    fn test_web_socket_connection_model() {
+        let connections = Arc::new(WebSocketConnections::new());
+
+        let guard = connections.add(
+            "localhost:8000".to_string(),
+            "/ws".to_string(),
+            "127.0.0.1:5000".to_string(),
+        );
+
+        guard.connection.add_from_client(10);
+        guard.connection.add_from_client(5);
+        guard.connection.add_from_remote(100);
+
+        let now = guard.connection.connected_at.add(Duration::from_secs(3));
+
+        let models: Vec<_> = connections
+            .get_snapshot()
+            .iter()
+            .map(|itm| WebSocketConnectionHttpModel::new(itm, now))
+            .collect();
+
+        assert_eq!(1, models.len());
+        assert_eq!("localhost:8000", models[0].endpoint);
+        assert_eq!("/ws", models[0].path);
+        assert_eq!("127.0.0.1:5000", models[0].client_addr);
+        assert_eq!(3, models[0].uptime_sec);
+        assert_eq!(2, models[0].messages_from_client);
+        assert_eq!(15, models[0].bytes_from_client);
+        assert_eq!(1, models[0].messages_from_remote);
+        assert_eq!(100, models[0].bytes_from_remote);
+
+        drop(guard);
+
+        assert!(connections.get_snapshot().is_empty());
+    }
+}
@@ -30,7 +30,10 @@ async fn handle_request(
 ) -> Result<HttpOkResult, HttpFailResult> {
     let config = action.app.get_current_app_configuration().await;
 
-    let result = CurrentConfigurationHttpModel::new(config.as_ref());
+    let result = CurrentConfigurationHttpModel::new(
+        config.as_ref(),
+        action.app.web_socket_connections.as_ref(),
+    );
 
     HttpOutput::as_json(result).into_ok_result(true).into()
 }
@@ -1,10 +1,15 @@
-use std::sync::Arc;
+use std::sync::{atomic::Ordering, Arc};
 
 use my_http_server::{
     macros::http_route, HttpContext, HttpFailResult, HttpOkResult, HttpOutput, WebContentType,
 };
 
-use crate::{app::AppContext, configurations::*};
+use rust_extensions::{date_time::DateTimeAsMicroseconds, duration_utils::DurationExtensions};
+
+use crate::{
+    app::AppContext, circuit_breaker::CircuitState, configurations::*,
+    http_proxy_pass::WebSocketConnections, ssh_reverse::*,
+};
 
 const RIGHT_BADGE_STYLE: &str = "border-radius: 0 5px 5px 0;";
 
@@ -30,12 +35,31 @@ async fn handle_request(
     HttpOutput::Content {
         headers: None,
         content_type: WebContentType::Html.into(),
-        content: create_html_content(config.as_ref()).into_bytes(),
+        content: create_html_content(
+            config.as_ref(),
+            &action.app.ssh_reverse_statuses,
+            &action.app.web_socket_connections,
+        )
+        .into_bytes(),
     }
     .into_ok_result(false)
 }
 
-fn create_html_content(config: &AppConfiguration) -> String {
+fn create_html_content(
+    config: &AppConfiguration,
+    ssh_reverse_statuses: &SshReverseStatuses,
+    web_socket_connections: &WebSocketConnections,
+) -> String {
+    let ssh_reverse_html = render_ssh_reverse(config, ssh_reverse_statuses);
+
+    let web_sockets_html = render_web_sockets(web_socket_connections);
+
+    let udp_html = render_udp(config);
+
+    let circuit_breakers_html = render_circuit_breakers(config);
+
+    let traffic_mirrors_html = render_traffic_mirrors(config);
+
     let mut table_lines = String::new();
     for (port, config) in &config.http_endpoints {
         let mut draw_port = port.to_string();
@@ -135,6 +159,11 @@ fn create_html_content(config: &AppConfiguration) -> String {
         </tr>
         {table_lines}
         </table>
+        {circuit_breakers_html}
+        {traffic_mirrors_html}
+        {udp_html}
+        {ssh_reverse_html}
+        {web_sockets_html}
       
     </body>          
     "##
@@ -157,3 +186,313 @@ fn render_http_badge(src: HttpType) -> &'static str {
         }
     }
 }
+
+fn render_circuit_breakers(config: &AppConfiguration) -> String {
+    let mut table_lines = String::new();
+
+    for config in config.http_endpoints.values() {
+        for http_endpoint in &config.endpoint_info {
+            let host = http_endpoint.host_endpoint.as_str();
+
+            for location in &http_endpoint.locations {
+                let circuit_breaker = match location.circuit_breaker.as_ref() {
+                    Some(circuit_breaker) => circuit_breaker,
+                    None => continue,
+                };
+
+                let path = location.path.as_str();
+
+                for circuit_breaker in &circuit_breaker.circuit_breakers {
+                    let snapshot = circuit_breaker.get_snapshot();
+
+                    let upstream = snapshot.upstream.as_str();
+
+                    let state = match snapshot.state {
+                        CircuitState::Closed => "text-bg-success",
+                        CircuitState::Open => "text-bg-danger",
+                        CircuitState::HalfOpen => "text-bg-warning",
+                    };
+                    let state = format!(
+                        r##"<span class="badge {state}">{}</span>"##,
+                        snapshot.state.as_str()
+                    );
+
+                    let open_remaining = match snapshot.open_remaining {
+                        Some(open_remaining) => open_remaining.format_to_string(),
+                        None => "-".to_string(),
+                    };
+
+                    let consecutive_failures = snapshot.consecutive_failures;
+                    let error_rate = snapshot.error_rate;
+                    let requests_in_window = snapshot.requests_in_window;
+                    let opened = snapshot.opened;
+
+                    table_lines.push_str(
+                        format!(
+                            r##"<tr><td><span class="badge text-bg-secondary">{host}</span></td><td>{path}</td><td>{upstream}</td><td>{state}</td><td>{consecutive_failures}</td><td>{error_rate:.1}% of {requests_in_window}</td><td>{opened}</td><td>{open_remaining}</td></tr>"##,
+                        )
+                        .as_str(),
+                    );
+                }
+            }
+        }
+    }
+
+    if table_lines.is_empty() {
+        return "".to_string();
+    }
+
+    format!(
+        r##"
+        <h1>Circuit breakers</h1>
+        <table class="table table-striped" style="width:100%;">
+        <tr>
+            <th>Endpoint</th>
+            <th>Path</th>
+            <th>Upstream</th>
+            <th>State</th>
+            <th>Consecutive failures</th>
+            <th>Error rate</th>
+            <th>Opened times</th>
+            <th>Open remaining</th>
+        </tr>
+        {table_lines}
+        </table>
+        "##
+    )
+}
+
+fn render_traffic_mirrors(config: &AppConfiguration) -> String {
+    let mut table_lines = String::new();
+
+    for config in config.http_endpoints.values() {
+        for http_endpoint in &config.endpoint_info {
+            let host = http_endpoint.host_endpoint.as_str();
+
+            for location in &http_endpoint.locations {
+                let mirror = match location.mirror.as_ref() {
+                    Some(mirror) => mirror,
+                    None => continue,
+                };
+
+                let path = location.path.as_str();
+
+                let snapshot = mirror.get_snapshot();
+
+                let upstream = snapshot.upstream.as_str();
+                let percent = snapshot.percent;
+                let mirrored = snapshot.mirrored;
+                let failed = snapshot.failed;
+                let dropped = snapshot.dropped;
+                let in_flight = snapshot.in_flight;
+
+                table_lines.push_str(
+                    format!(
+                        r##"<tr><td><span class="badge text-bg-secondary">{host}</span></td><td>{path}</td><td>{upstream}</td><td>{percent}%</td><td>{mirrored}</td><td>{failed}</td><td>{dropped}</td><td>{in_flight}</td></tr>"##,
+                    )
+                    .as_str(),
+                );
+            }
+        }
+    }
+
+    if table_lines.is_empty() {
+        return "".to_string();
+    }
+
+    format!(
+        r##"
+        <h1>Traffic mirrors</h1>
+        <table class="table table-striped" style="width:100%;">
+        <tr>
+            <th>Endpoint</th>
+            <th>Path</th>
+            <th>Mirror</th>
+            <th>Percent</th>
+            <th>Mirrored</th>
+            <th>Failed</th>
+            <th>Dropped</th>
+            <th>In flight</th>
+        </tr>
+        {table_lines}
+        </table>
+        "##
+    )
+}
+
+fn render_udp(config: &AppConfiguration) -> String {
+    if config.udp_endpoints.is_empty() {
+        return "".to_string();
+    }
+
+    let mut table_lines = String::new();
+
+    for (port, endpoint_info) in &config.udp_endpoints {
+        let host = endpoint_info.host.as_str();
+        let remote_addr = endpoint_info.remote_addr;
+        let idle_timeout = endpoint_info.idle_timeout.format_to_string();
+
+        let debug = if endpoint_info.debug {
+            r##"<span class="badge text-bg-warning">debug</span>"##
+        } else {
+            ""
+        };
+
+        table_lines.push_str(
+            format!(
+                r##"<tr><td>{port}</td><td><span class="badge text-bg-secondary">{host}</span> {debug}</td><td>{remote_addr}</td><td>{idle_timeout}</td></tr>"##,
+            )
+            .as_str(),
+        );
+    }
+
+    format!(
+        r##"
+        <h1>Udp forwards</h1>
+        <table class="table table-striped" style="width:100%;">
+        <tr>
+            <th>Port</th>
+            <th>Host</th>
+            <th>Remote</th>
+            <th>Idle timeout</th>
+        </tr>
+        {table_lines}
+        </table>
+        "##
+    )
+}
+
+fn render_ssh_reverse(
+    config: &AppConfiguration,
+    ssh_reverse_statuses: &SshReverseStatuses,
+) -> String {
+    if config.ssh_reverse_endpoints.is_empty() {
+        return "".to_string();
+    }
+
+    let statuses = ssh_reverse_statuses.get_snapshot();
+
+    let now = DateTimeAsMicroseconds::now();
+
+    let mut table_lines = String::new();
+
+    for endpoint_info in &config.ssh_reverse_endpoints {
+        let host = endpoint_info.host.as_str();
+        let ssh = endpoint_info.ssh_credentials.to_string();
+        let target = endpoint_info.target.as_str();
+
+        let (state, uptime, reconnects, active_connections, last_error) = match statuses.get(host) {
+            Some(status) => {
+                let state = match status.state {
+                    SshReverseState::Listening => {
+                        r##"<span class="badge text-bg-success">listening</span>"##.to_string()
+                    }
+                    _ => format!(
+                        r##"<span class="badge text-bg-danger">{}</span>"##,
+                        status.state.as_str()
+                    ),
+                };
+
+                let uptime = match status.connected_at {
+                    Some(connected_at) => now
+                        .duration_since(connected_at)
+                        .as_positive_or_zero()
+                        .format_to_string(),
+                    None => "-".to_string(),
+                };
+
+                (
+                    state,
+                    uptime,
+                    status.reconnects,
+                    status.active_connections,
+                    status.last_error.clone().unwrap_or_default(),
+                )
+            }
+            None => (
+                r##"<span class="badge text-bg-secondary">not started</span>"##.to_string(),
+                "-".to_string(),
+                0,
+                0,
+                "".to_string(),
+            ),
+        };
+
+        table_lines.push_str(
+            format!(
+                r##"<tr><td><span class="badge text-bg-secondary">{host}</span></td><td>{ssh}</td><td>{target}</td><td>{state}</td><td>{uptime}</td><td>{reconnects}</td><td>{active_connections}</td><td>{last_error}</td></tr>"##,
+            )
+            .as_str(),
+        );
+    }
+
+    format!(
+        r##"
+        <h1>Ssh reverse forwards</h1>
+        <table class="table table-striped" style="width:100%;">
+        <tr>
+            <th>Remote listen</th>
+            <th>Ssh</th>
+            <th>Target</th>
+            <th>State</th>
+            <th>Uptime</th>
+            <th>Reconnects</th>
+            <th>Connections</th>
+            <th>Last error</th>
+        </tr>
+        {table_lines}
+        </table>
+        "##
+    )
+}
+
+fn render_web_sockets(web_socket_connections: &WebSocketConnections) -> String {
+    let connections = web_socket_connections.get_snapshot();
+
+    if connections.is_empty() {
+        return "".to_string();
+    }
+
+    let now = DateTimeAsMicroseconds::now();
+
+    let mut table_lines = String::new();
+
+    for connection in connections {
+        let endpoint = connection.endpoint.as_str();
+        let path = connection.path.as_str();
+        let client_addr = connection.client_addr.as_str();
+        let uptime = now
+            .duration_since(connection.connected_at)
+            .as_positive_or_zero()
+            .format_to_string();
+
+        let messages_from_client = connection.messages_from_client.load(Ordering::Relaxed);
+        let bytes_from_client = connection.bytes_from_client.load(Ordering::Relaxed);
+        let messages_from_remote = connection.messages_from_remote.load(Ordering::Relaxed);
+        let bytes_from_remote = connection.bytes_from_remote.load(Ordering::Relaxed);
+
+        table_lines.push_str(
+            format!(
+                r##"<tr><td><span class="badge text-bg-secondary">{endpoint}</span></td><td>{path}</td><td>{client_addr}</td><td>{uptime}</td><td>{messages_from_client} / {bytes_from_client}</td><td>{messages_from_remote} / {bytes_from_remote}</td></tr>"##,
+            )
+            .as_str(),
+        );
+    }
+
+    format!(
+        r##"
+        <h1>Web socket connections</h1>
+        <table class="table table-striped" style="width:100%;">
+        <tr>
+            <th>Endpoint</th>
+            <th>Path</th>
+            <th>Client</th>
+            <th>Uptime</th>
+            <th>From client (messages / bytes)</th>
+            <th>From remote (messages / bytes)</th>
+        </tr>
+        {table_lines}
+        </table>
+        "##
+    )
+}
@@ -1,17 +1,204 @@
+pub const ALLOWED_USER_GROUP_PREFIX: char = '@';
+pub const ALLOWED_USER_NEGATIVE_PREFIX: char = '!';
+
+pub enum AllowedUserRule {
+    Exact(String),
+    Wildcard(String),
+    Regex(regex::Regex),
+}
+
+impl AllowedUserRule {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        if src.len() > 1 && src.starts_with('/') && src.ends_with('/') {
+            let pattern = &src[1..src.len() - 1];
+            // Emails are compared in lower case, so the pattern is case insensitive as well
+            return match regex::RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+            {
+                Ok(regex) => Ok(Self::Regex(regex)),
+                Err(err) => Err(format!(
+                    "Invalid allowed user regex pattern '{}'. Err: {}",
+                    src, err
+                )),
+            };
+        }
+
+        if src.contains('*') || src.contains('?') {
+            return Ok(Self::Wildcard(src.to_lowercase()));
+        }
+
+        Ok(Self::Exact(src.to_lowercase()))
+    }
+
+    // User is expected to be in lower case already
+    pub fn is_match(&self, user: &str) -> bool {
+        match self {
+            AllowedUserRule::Exact(value) => value == user,
+            AllowedUserRule::Wildcard(pattern) => {
+                wildcard_match(pattern.as_bytes(), user.as_bytes())
+            }
+            AllowedUserRule::Regex(regex) => regex.is_match(user),
+        }
+    }
+}
+
 pub struct AllowedUserList {
     users: Vec<String>,
+    allowed: Vec<AllowedUserRule>,
+    denied: Vec<AllowedUserRule>,
 }
 
 impl AllowedUserList {
-    pub fn new<'s>(users: Vec<String>) -> Self {
-        Self { users }
+    pub fn new(users: Vec<String>) -> Result<Self, String> {
+        let mut allowed = Vec::new();
+        let mut denied = Vec::new();
+
+        for user in &users {
+            let user = user.trim();
+
+            if user.is_empty() {
+                continue;
+            }
+
+            if user.starts_with(ALLOWED_USER_NEGATIVE_PREFIX) {
+                denied.push(AllowedUserRule::parse(user[1..].trim())?);
+            } else {
+                allowed.push(AllowedUserRule::parse(user)?);
+            }
+        }
+
+        Ok(Self {
+            users,
+            allowed,
+            denied,
+        })
     }
 
     pub fn is_allowed(&self, user: &str) -> bool {
-        self.users.contains(&user.to_string())
+        // Email addresses are case insensitive, so all the rules match them in lower case
+        let user = user.to_lowercase();
+        let user = user.as_str();
+
+        for rule in &self.denied {
+            if rule.is_match(user) {
+                return false;
+            }
+        }
+
+        for rule in &self.allowed {
+            if rule.is_match(user) {
+                return true;
+            }
+        }
+
+        false
     }
 
     pub fn get_list(&self) -> &[String] {
         &self.users
     }
 }
+
+pub fn wildcard_match(pattern: &[u8], value: &[u8]) -> bool {
+    let mut p = 0;
+    let mut v = 0;
+
+    let mut star_p = None;
+    let mut star_v = 0;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+            continue;
+        }
+
+        if p < pattern.len() && pattern[p] == b'*' {
+            star_p = Some(p);
+            star_v = v;
+            p += 1;
+            continue;
+        }
+
+        match star_p {
+            Some(star) => {
+                p = star + 1;
+                star_v += 1;
+                v = star_v;
+            }
+            None => return false,
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == b'*' {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AllowedUserList;
+
+    #[test]
+    fn test_exact_match() {
+        let list = AllowedUserList::new(vec!["user@domain.com".to_string()]).unwrap();
+
+        assert!(list.is_allowed("user@domain.com"));
+        assert!(!list.is_allowed("other@domain.com"));
+    }
+
+    #[test]
+    fn test_case_insensitive_match() {
+        let list = AllowedUserList::new(vec![
+            "User@Domain.com".to_string(),
+            "*@Team.example.com".to_string(),
+            r"/^admin@example\.com$/".to_string(),
+            "!Intern@Team.Example.com".to_string(),
+        ])
+        .unwrap();
+
+        assert!(list.is_allowed("user@domain.com"));
+        assert!(list.is_allowed("USER@DOMAIN.COM"));
+        assert!(list.is_allowed("Someone@TEAM.example.com"));
+        assert!(list.is_allowed("Admin@Example.com"));
+        assert!(!list.is_allowed("intern@team.example.com"));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        let list = AllowedUserList::new(vec!["*@team.example.com".to_string()]).unwrap();
+
+        assert!(list.is_allowed("user@team.example.com"));
+        assert!(list.is_allowed("User@Team.Example.com"));
+        assert!(!list.is_allowed("user@example.com"));
+        assert!(!list.is_allowed("user@team.example.com.evil.com"));
+    }
+
+    #[test]
+    fn test_regex_match() {
+        let list = AllowedUserList::new(vec![r"/^admin-\d+@example\.com$/".to_string()]).unwrap();
+
+        assert!(list.is_allowed("admin-15@example.com"));
+        assert!(!list.is_allowed("admin-x@example.com"));
+    }
+
+    #[test]
+    fn test_negative_entry_wins() {
+        let list = AllowedUserList::new(vec![
+            "*@team.example.com".to_string(),
+            "!intern@team.example.com".to_string(),
+        ])
+        .unwrap();
+
+        assert!(list.is_allowed("user@team.example.com"));
+        assert!(!list.is_allowed("intern@team.example.com"));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        assert!(AllowedUserList::new(vec!["/[a-/".to_string()]).is_err());
+    }
+}
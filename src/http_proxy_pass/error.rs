@@ -8,6 +8,7 @@ pub enum ProxyPassError {
     HyperError(hyper::Error),
     IoError(tokio::io::Error),
     SshSessionError(my_ssh::SshSessionError),
+    SshConnectError(String),
     WebSocketProtocolError(hyper_tungstenite::tungstenite::error::ProtocolError),
     NoLocationFound,
     ConnectionIsDisposed,
@@ -15,6 +16,9 @@ pub enum ProxyPassError {
     UserIsForbidden,
     IpRestricted(String),
     Timeout,
+    TooManyRequests(std::time::Duration),
+    CircuitOpen(std::time::Duration),
+    InvalidGrpcWebRequest(String),
 }
 
 impl ProxyPassError {
@@ -1,4 +1,7 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use bytes::Bytes;
 use http_body_util::{combinators::BoxBody, BodyExt};
@@ -6,8 +9,12 @@ use rust_extensions::date_time::DateTimeAsMicroseconds;
 use tokio::sync::Mutex;
 
 use crate::{
-    app::AppContext, configurations::*, http_client::HTTP_CLIENT_TIMEOUT,
+    app::AppContext,
+    circuit_breaker::CircuitBreaker,
+    configurations::*,
+    http_cache::*,
     http_server::ClientCertificateData,
+    rate_limit::{ConnectionPermit, RateLimitKey, RateLimiter},
 };
 
 const OLD_CONNECTION_DELAY: Duration = Duration::from_secs(10);
@@ -15,7 +22,7 @@ const OLD_CONNECTION_DELAY: Duration = Duration::from_secs(10);
 const NEW_CONNECTION_NOT_READY_RETRY_DELAY: Duration = Duration::from_millis(50);
 
 use super::{
-    BuildResult, GoogleAuthResult, HttpProxyPassContentSource, HttpProxyPassIdentity,
+    BuildResult, GoogleAuthResult, HostPort, HttpProxyPassContentSource, HttpProxyPassIdentity,
     HttpProxyPassInner, HttpRequestBuilder, LocationIndex, ProxyPassError, ProxyPassLocations,
     RetryType,
 };
@@ -24,6 +31,7 @@ pub struct HttpProxyPass {
     pub inner: Mutex<HttpProxyPassInner>,
     pub listening_port_info: HttpListenPortInfo,
     pub endpoint_info: Arc<HttpEndpointInfo>,
+    _connection_permit: Option<ConnectionPermit>,
 }
 
 impl HttpProxyPass {
@@ -31,9 +39,10 @@ impl HttpProxyPass {
         endpoint_info: Arc<HttpEndpointInfo>,
         listening_port_info: HttpListenPortInfo,
         client_cert: Option<ClientCertificateData>,
-        request_timeout: Duration,
+        connection_permit: Option<ConnectionPermit>,
     ) -> Self {
-        let locations = ProxyPassLocations::new(&endpoint_info, request_timeout);
+        let locations = ProxyPassLocations::new(&endpoint_info);
+
         Self {
             inner: Mutex::new(HttpProxyPassInner::new(
                 HttpProxyPassIdentity::new(client_cert),
@@ -43,6 +52,7 @@ impl HttpProxyPass {
 
             listening_port_info,
             endpoint_info,
+            _connection_permit: connection_permit,
         }
     }
 
@@ -60,10 +70,29 @@ impl HttpProxyPass {
             );
         }
 
+        let grpc_web_mode = crate::grpc::GrpcWebMode::detect(req.headers());
+        let grpc_timeout = crate::grpc::get_grpc_timeout(req.headers());
+
         let mut req = HttpRequestBuilder::new(self.endpoint_info.http_type.clone(), req);
 
+        let mut rate_limit_checked = false;
+
+        let mut grpc_web = None;
+        let mut streaming;
+        let mut response_timeout;
+        let mut retry_policy;
+        let mut location_index;
+        let mut circuit_breaker;
+
+        // Mirror and cache are handled once per request, not once per attempt
+        let mut request_handled = false;
+        let mut cache_request = None;
+
+        let started = Instant::now();
+        let mut attempts_done = 1;
+
         loop {
-            let (future1, future2, build_result, request_executor, dest_http1) = {
+            let (future1, future2, build_result, request_executor, dest_http1, connect_error) = {
                 let mut inner = self.inner.lock().await;
 
                 match self.handle_auth_with_g_auth(app, &req).await {
@@ -82,7 +111,52 @@ impl HttpProxyPass {
                     }
                 }
 
-                let build_result = req.populate_and_build(self, &inner).await?;
+                if !rate_limit_checked {
+                    self.check_rate_limits(&inner, &req)?;
+                    rate_limit_checked = true;
+                }
+
+                let mut build_result = req.populate_and_build(self, &inner).await?;
+
+                let location_config = inner
+                    .locations
+                    .find(build_result.get_location_index())
+                    .config
+                    .clone();
+
+                streaming = location_config.streaming.clone();
+                retry_policy = location_config.retry.clone();
+                response_timeout = location_config.timeouts.get_read_timeout();
+
+                if let Some(streaming) = streaming.as_ref() {
+                    // Long-poll response headers are sent only when there is something to send.
+                    // Tokio treats too big duration as the far future
+                    response_timeout = streaming.header_timeout.unwrap_or(Duration::MAX);
+                }
+
+                if let Some(grpc) = location_config.grpc.as_ref() {
+                    // Client deadline is the time it is ready to wait, so it overrides the default one
+                    if let Some(grpc_timeout) = grpc_timeout {
+                        response_timeout = grpc_timeout;
+                    }
+
+                    if grpc.grpc_web {
+                        if let Some(grpc_web_mode) = grpc_web_mode {
+                            req.translate_grpc_web_request(grpc_web_mode).await?;
+                            grpc_web = Some(grpc_web_mode);
+                        }
+                    }
+                }
+
+                if let Some(total_timeout) = location_config.timeouts.total {
+                    let remaining = total_timeout.saturating_sub(started.elapsed());
+
+                    if remaining.is_zero() {
+                        return Err(ProxyPassError::Timeout);
+                    }
+
+                    response_timeout = response_timeout.min(remaining);
+                }
 
                 let proxy_pass_location =
                     inner.locations.find_mut(build_result.get_location_index());
@@ -97,74 +171,188 @@ impl HttpProxyPass {
                     ));
                 }
 
-                proxy_pass_location
-                    .connect_if_require(app, self.endpoint_info.debug)
-                    .await?;
+                location_index = build_result.get_location_index().clone();
 
-                let (future1, future2, request_executor, is_http_1) = {
-                    match &mut proxy_pass_location.content_source {
-                        super::HttpProxyPassContentSource::Http(http_content_source) => {
-                            if http_content_source.remote_endpoint.is_http1() {
-                                let result = http_content_source.send_http1_request(req.get());
+                if !request_handled {
+                    request_handled = true;
 
-                                (Some(result), None, None, Some(true))
-                            } else {
-                                let future = http_content_source.send_http2_request(req.get());
-                                (None, Some(future), None, Some(false))
+                    if let BuildResult::HttpRequest(_) = &build_result {
+                        self.mirror_if_required(app, &location_config, &req);
+                    }
+
+                    if let (BuildResult::HttpRequest(_), Some(cache)) =
+                        (&build_result, location_config.cache.as_ref())
+                    {
+                        let key = get_cache_key(
+                            req.get_host(),
+                            req.uri()
+                                .path_and_query()
+                                .map(|itm| itm.as_str())
+                                .unwrap_or("/"),
+                        );
+
+                        if is_cacheable_request(req.method(), req.get_headers()) {
+                            let is_head = req.method() == hyper::Method::HEAD;
+                            let entry = cache.get(&key, req.get_headers()).await;
+
+                            if let Some(entry) = entry.as_ref() {
+                                if entry.is_fresh(SystemTime::now())
+                                    && !is_revalidation_requested(req.get_headers())
+                                {
+                                    let response =
+                                        super::http_response_builder::build_cached_response(
+                                            self,
+                                            &inner,
+                                            &req,
+                                            entry,
+                                            &location_index,
+                                            is_head,
+                                            CACHE_STATUS_HIT,
+                                        );
+
+                                    let response = self.add_sticky_cookie(&req, response);
+                                    return Ok(Ok(self.compress_if_required(&req, response)));
+                                }
                             }
-                        }
-                        super::HttpProxyPassContentSource::LocalPath(file) => {
-                            let executor = file.get_request_executor(req.uri())?;
 
-                            (None, None, Some(executor), None)
-                        }
+                            let mut revalidating = false;
 
-                        super::HttpProxyPassContentSource::PathOverSsh(ssh) => {
-                            let executor = ssh.get_request_executor(req.uri())?;
+                            if let Some(entry) = entry.as_ref() {
+                                if entry.has_validators()
+                                    && !has_conditional_headers(req.get_headers())
+                                {
+                                    if let Some(headers) = req.get_prepared_headers_mut() {
+                                        HttpCacheRequest::add_validators(entry, headers);
+                                        revalidating = true;
+                                    }
+                                }
+                            }
 
-                            (None, None, Some(executor), None)
+                            cache_request = Some(HttpCacheRequest {
+                                cache: cache.clone(),
+                                key,
+                                path: req.uri().path().to_string(),
+                                req_headers: req.get_headers().clone(),
+                                is_head,
+                                stale_entry: entry,
+                                revalidating,
+                            });
+                        } else if !req.method().is_safe() {
+                            // Stored response is outdated by the change of the resource
+                            cache.remove(&key).await;
                         }
+                    }
+                }
 
-                        super::HttpProxyPassContentSource::Static(static_content_src) => {
-                            let static_content_src = static_content_src.get_request_executor()?;
-                            (None, None, Some(static_content_src), None)
+                circuit_breaker = match proxy_pass_location.acquire_upstream() {
+                    Ok(circuit_breaker) => circuit_breaker,
+                    Err(retry_after) => {
+                        // Web socket upgrade is not served by the fallback location
+                        let fallback = match &build_result {
+                            BuildResult::HttpRequest(_) => location_config
+                                .circuit_breaker
+                                .as_ref()
+                                .and_then(|itm| itm.fallback.as_deref())
+                                .and_then(|fallback| inner.locations.find_by_path(fallback)),
+                            BuildResult::WebSocketUpgrade { .. } => None,
+                        };
+
+                        match fallback {
+                            Some(fallback_index) => {
+                                if self.endpoint_info.debug {
+                                    println!(
+                                        "Circuits of location {} are open. Request {} goes to fallback location",
+                                        location_config.path,
+                                        req.uri()
+                                    );
+                                }
+
+                                build_result = req.build_for_fallback(self, &inner, fallback_index);
+                                location_index = build_result.get_location_index().clone();
+
+                                if let Some(grpc_web_mode) = grpc_web {
+                                    req.translate_grpc_web_request(grpc_web_mode).await?;
+                                }
+
+                                None
+                            }
+                            None => return Err(ProxyPassError::CircuitOpen(retry_after)),
                         }
                     }
                 };
 
-                (future1, future2, build_result, request_executor, is_http_1)
-            };
+                let proxy_pass_location = inner.locations.find_mut(&location_index);
 
-            let result = if let Some(future1) = future1 {
-                match future1 {
-                    Ok(result) => {
-                        let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, result).await;
+                // Connection errors go the same way as request errors, so they can be retried
+                if let Err(err) = proxy_pass_location
+                    .connect_if_require(app, self.endpoint_info.debug)
+                    .await
+                {
+                    (None, None, build_result, None, None, Some(err))
+                } else {
+                    let (future1, future2, request_executor, is_http_1) = {
+                        match &mut proxy_pass_location.content_source {
+                            super::HttpProxyPassContentSource::Http(http_content_source) => {
+                                if http_content_source.remote_endpoint.is_http1() {
+                                    let result = http_content_source.send_http1_request(req.get());
+
+                                    (Some(result), None, None, Some(true))
+                                } else {
+                                    let future = http_content_source.send_http2_request(req.get());
+                                    (None, Some(future), None, Some(false))
+                                }
+                            }
+                            super::HttpProxyPassContentSource::LocalPath(file) => {
+                                let executor =
+                                    file.get_request_executor(req.uri(), req.get_headers())?;
 
-                        if result.is_err() {
-                            return Err(ProxyPassError::Timeout);
-                        }
+                                (None, None, Some(executor), None)
+                            }
 
-                        match result.unwrap() {
-                            Ok(result) => Ok(result),
-                            Err(err) => Err(err.into()),
+                            super::HttpProxyPassContentSource::PathOverSsh(ssh) => {
+                                let executor =
+                                    ssh.get_request_executor(req.uri(), req.get_headers())?;
+
+                                (None, None, Some(executor), None)
+                            }
+
+                            super::HttpProxyPassContentSource::Static(static_content_src) => {
+                                let static_content_src =
+                                    static_content_src.get_request_executor()?;
+                                (None, None, Some(static_content_src), None)
+                            }
                         }
-                    }
+                    };
+
+                    (
+                        future1,
+                        future2,
+                        build_result,
+                        request_executor,
+                        is_http_1,
+                        None,
+                    )
+                }
+            };
+
+            let result = if let Some(err) = connect_error {
+                Err(err)
+            } else if let Some(future1) = future1 {
+                match future1 {
+                    Ok(result) => match tokio::time::timeout(response_timeout, result).await {
+                        Ok(Ok(result)) => Ok(result),
+                        Ok(Err(err)) => Err(err.into()),
+                        Err(_) => Err(ProxyPassError::Timeout),
+                    },
                     Err(err) => Err(err),
                 }
             } else if let Some(future2) = future2 {
                 match future2 {
-                    Ok(result) => {
-                        let result = tokio::time::timeout(HTTP_CLIENT_TIMEOUT, result).await;
-
-                        if result.is_err() {
-                            return Err(ProxyPassError::Timeout);
-                        }
-
-                        match result.unwrap() {
-                            Ok(result) => Ok(result),
-                            Err(err) => Err(err.into()),
-                        }
-                    }
+                    Ok(result) => match tokio::time::timeout(response_timeout, result).await {
+                        Ok(Ok(result)) => Ok(result),
+                        Ok(Err(err)) => Err(err.into()),
+                        Err(_) => Err(ProxyPassError::Timeout),
+                    },
                     Err(err) => Err(err),
                 }
             } else if let Some(request_executor) = request_executor {
@@ -176,43 +364,71 @@ impl HttpProxyPass {
                     self,
                     &inner,
                     &req,
-                    build_result.get_location_index(),
-                    response.content_type,
-                    response.status_code,
-                    response.body,
+                    &location_index,
+                    response,
                 );
-                return Ok(Ok(result));
+
+                // Upstream http errors are passed as is, only the file locations get custom error pages
+                let result = match self.endpoint_info.error_pages.as_ref() {
+                    Some(error_pages) => {
+                        let proxy_pass_location = inner.locations.find(&location_index);
+
+                        match proxy_pass_location.content_source {
+                            HttpProxyPassContentSource::LocalPath(_)
+                            | HttpProxyPassContentSource::PathOverSsh(_) => {
+                                crate::http_server::apply_error_page(result, error_pages)
+                            }
+                            _ => result,
+                        }
+                    }
+                    None => result,
+                };
+
+                let result = self.add_sticky_cookie(&req, result);
+
+                return Ok(Ok(self.compress_if_required(&req, result)));
             } else {
                 panic!("Both futures are None")
             };
 
+            if let Some(circuit_breaker) = circuit_breaker.as_ref() {
+                report_to_circuit_breaker(circuit_breaker, &result);
+            }
+
             match build_result {
-                BuildResult::HttpRequest(location_index) => match result {
+                BuildResult::HttpRequest(_) => match result {
                     Ok(response) => {
-                        /*
-                                               let mut chunked_response = false;
-                                               if let Some(value) = response.headers().get("Transfer-Encoding") {
-                                                   chunked_response = value == "chunked";
-
-                                                   println!("Chunked response found");
-                                               }
-                        */
-                        let inner = self.inner.lock().await;
+                        if let Some(retry_policy) = retry_policy.as_ref() {
+                            if retry_policy.is_retry_status_code(response.status().as_u16())
+                                && retry_policy.can_retry(req.method(), attempts_done)
+                            {
+                                if self.endpoint_info.debug {
+                                    println!(
+                                        "Retrying request {} after status code {}. Attempt: {}",
+                                        req.uri(),
+                                        response.status(),
+                                        attempts_done + 1
+                                    );
+                                }
 
-                        /*
-                        if chunked_response {
-                            let response =
-                                super::http_response_builder::build_chunked_http_response(
-                                    self,
-                                    &inner,
-                                    &req,
-                                    response,
-                                    &location_index,
-                                )
-                                .await?;
-                            return Ok(Ok(response));
+                                drop(response);
+                                self.prepare_retry(retry_policy, &location_index, attempts_done)
+                                    .await;
+                                attempts_done += 1;
+                                continue;
+                            }
+                        }
+
+                        if response.status().is_server_error() {
+                            if let Some(response) = self
+                                .get_stale_response(&req, cache_request.as_ref(), &location_index)
+                                .await
+                            {
+                                return Ok(Ok(response));
+                            }
                         }
-                         */
+
+                        let inner = self.inner.lock().await;
 
                         let response = super::http_response_builder::build_http_response(
                             self,
@@ -221,8 +437,25 @@ impl HttpProxyPass {
                             response,
                             &location_index,
                             dest_http1.unwrap(),
+                            cache_request.take(),
                         )
                         .await?;
+
+                        let response = match grpc_web {
+                            Some(grpc_web_mode) => {
+                                crate::grpc::translate_grpc_web_response(response, grpc_web_mode)
+                            }
+                            None => response,
+                        };
+
+                        let response = self.add_sticky_cookie(&req, response);
+
+                        // Encoder buffers the data, so streamed responses are not compressed
+                        let response = match streaming.as_ref() {
+                            Some(streaming) => super::build_streaming_response(response, streaming),
+                            None => self.compress_if_required(&req, response),
+                        };
+
                         return Ok(Ok(response));
                     }
                     Err(err) => {
@@ -232,7 +465,44 @@ impl HttpProxyPass {
                         };
 
                         match retry {
-                            RetryType::NoRetry => return Err(err.into()),
+                            RetryType::NoRetry => match retry_policy.as_ref() {
+                                Some(retry_policy)
+                                    if retry_policy.is_retry_error(&err)
+                                        && retry_policy.can_retry(req.method(), attempts_done) =>
+                                {
+                                    if self.endpoint_info.debug {
+                                        println!(
+                                            "Retrying request {} after error {:?}. Attempt: {}",
+                                            req.uri(),
+                                            err,
+                                            attempts_done + 1
+                                        );
+                                    }
+
+                                    self.prepare_retry(
+                                        retry_policy,
+                                        &location_index,
+                                        attempts_done,
+                                    )
+                                    .await;
+                                    attempts_done += 1;
+                                }
+                                _ => {
+                                    if let Some(response) = self
+                                        .get_stale_response(
+                                            &req,
+                                            cache_request.as_ref(),
+                                            &location_index,
+                                        )
+                                        .await
+                                    {
+                                        return Ok(Ok(response));
+                                    }
+
+                                    return Err(err);
+                                }
+                            },
+                            // Stale connection recovery is not counted as an attempt
                             RetryType::Retry(duration) => {
                                 if let Some(duration) = duration {
                                     tokio::time::sleep(duration).await;
@@ -242,7 +512,7 @@ impl HttpProxyPass {
                     }
                 },
                 BuildResult::WebSocketUpgrade {
-                    location_index: _, //todo!("Handle errors here properly")
+                    location_index,
                     upgrade_response,
                     web_socket,
                 } => {
@@ -250,36 +520,90 @@ impl HttpProxyPass {
                         println!("Doing web_socket upgrade");
                     }
 
-                    match result {
-                        Ok(res) => match hyper::upgrade::on(res).await {
-                            Ok(upgraded) => {
-                                if self.endpoint_info.debug {
-                                    println!("Upgrade Ok");
-                                }
+                    // Upstream refused the upgrade. Client gets the answer as is
+                    if let Ok(res) = &result {
+                        if res.status() != hyper::StatusCode::SWITCHING_PROTOCOLS {
+                            let inner = self.inner.lock().await;
+                            let response = super::http_response_builder::build_http_response(
+                                self,
+                                &inner,
+                                &req,
+                                result?,
+                                &location_index,
+                                true,
+                                None,
+                            )
+                            .await?;
+                            return Ok(Ok(response));
+                        }
+                    }
 
-                                if let Some(web_socket) = web_socket.lock().await.take() {
-                                    tokio::spawn(super::web_socket_loop(
-                                        web_socket,
-                                        upgraded,
-                                        self.endpoint_info.debug,
-                                    ));
+                    match result {
+                        Ok(res) => {
+                            let web_socket_protocol = res
+                                .headers()
+                                .get(hyper::header::SEC_WEBSOCKET_PROTOCOL)
+                                .cloned();
+
+                            match hyper::upgrade::on(res).await {
+                                Ok(upgraded) => {
+                                    if self.endpoint_info.debug {
+                                        println!("Upgrade Ok");
+                                    }
+
+                                    // Upgraded connection is not usable for http requests anymore
+                                    let web_socket_config = {
+                                        let mut inner = self.inner.lock().await;
+                                        let location = inner.locations.find_mut(&location_index);
+                                        if let HttpProxyPassContentSource::Http(
+                                            remote_http_content_source,
+                                        ) = &mut location.content_source
+                                        {
+                                            remote_http_content_source.dispose();
+                                        }
+
+                                        location.config.web_socket.clone()
+                                    };
+
+                                    if let Some(web_socket) = web_socket.lock().await.take() {
+                                        let guard = app.web_socket_connections.add(
+                                            self.endpoint_info.as_str().to_string(),
+                                            req.uri().path().to_string(),
+                                            self.listening_port_info.socket_addr.to_string(),
+                                        );
+
+                                        tokio::spawn(super::web_socket_loop(
+                                            web_socket,
+                                            upgraded,
+                                            web_socket_config,
+                                            guard,
+                                            self.endpoint_info.debug,
+                                        ));
+                                    }
+
+                                    let (mut parts, body) = upgrade_response.into_parts();
+
+                                    if let Some(web_socket_protocol) = web_socket_protocol {
+                                        parts.headers.insert(
+                                            hyper::header::SEC_WEBSOCKET_PROTOCOL,
+                                            web_socket_protocol,
+                                        );
+                                    }
+
+                                    return Ok(Ok(hyper::Response::from_parts(
+                                        parts,
+                                        body.map_err(|e| crate::to_hyper_error(e)).boxed(),
+                                    )));
                                 }
+                                Err(e) => {
+                                    if self.endpoint_info.debug {
+                                        println!("Upgrade Error: {:?}", e);
+                                    }
 
-                                let (parts, body) = upgrade_response.into_parts();
-
-                                return Ok(Ok(hyper::Response::from_parts(
-                                    parts,
-                                    body.map_err(|e| crate::to_hyper_error(e)).boxed(),
-                                )));
-                            }
-                            Err(e) => {
-                                if self.endpoint_info.debug {
-                                    println!("Upgrade Error: {:?}", e);
+                                    return Err(e.into());
                                 }
-
-                                return Err(e.into());
                             }
-                        },
+                        }
                         Err(err) => {
                             if self.endpoint_info.debug {
                                 println!("Upgrade Request Error: {:?}", err);
@@ -293,11 +617,209 @@ impl HttpProxyPass {
         }
     }
 
+    fn add_sticky_cookie(
+        &self,
+        req: &HttpRequestBuilder,
+        mut response: hyper::Response<BoxBody<Bytes, String>>,
+    ) -> hyper::Response<BoxBody<Bytes, String>> {
+        if let Some(sticky_cookie) = req.get_sticky_cookie() {
+            if let Ok(value) = sticky_cookie.parse() {
+                response
+                    .headers_mut()
+                    .append(hyper::header::SET_COOKIE, value);
+            }
+        }
+
+        response
+    }
+
+    // Mirror gets a copy of the prepared request in the background, primary request does not wait for it
+    fn mirror_if_required(
+        &self,
+        app: &Arc<AppContext>,
+        location_config: &Arc<ProxyPassLocationConfig>,
+        req: &HttpRequestBuilder,
+    ) {
+        let mirror = match location_config.mirror.as_ref() {
+            Some(mirror) => mirror,
+            None => return,
+        };
+
+        if !mirror.should_mirror() {
+            return;
+        }
+
+        if !mirror.try_acquire() {
+            if self.endpoint_info.debug {
+                println!(
+                    "Mirror of location {} is busy. Request {} is not mirrored",
+                    location_config.path,
+                    req.uri()
+                );
+            }
+            return;
+        }
+
+        tokio::spawn(crate::traffic_mirror::send_mirror_request(
+            app.clone(),
+            location_config.clone(),
+            req.get(),
+            self.endpoint_info.debug,
+        ));
+    }
+
+    // Failed connection is dropped and the next upstream in turn becomes active
+    async fn prepare_retry(
+        &self,
+        retry_policy: &RetryPolicy,
+        location_index: &LocationIndex,
+        attempts_done: usize,
+    ) {
+        {
+            let mut inner = self.inner.lock().await;
+            let location = inner.locations.find_mut(location_index);
+
+            if let HttpProxyPassContentSource::Http(remote_http_content_source) =
+                &mut location.content_source
+            {
+                remote_http_content_source.dispose();
+            }
+
+            location.switch_upstream();
+        }
+
+        tokio::time::sleep(retry_policy.get_backoff(attempts_done)).await;
+    }
+
+    // Upstream failure is hidden from the client while the stored response may be served stale
+    async fn get_stale_response(
+        &self,
+        req: &HttpRequestBuilder,
+        cache_request: Option<&HttpCacheRequest>,
+        location_index: &LocationIndex,
+    ) -> Option<hyper::Response<BoxBody<Bytes, String>>> {
+        let cache_request = cache_request?;
+        let stale_entry = cache_request.stale_entry.as_ref()?;
+
+        if !stale_entry.can_serve_stale_on_error(SystemTime::now()) {
+            return None;
+        }
+
+        if self.endpoint_info.debug {
+            println!(
+                "Upstream of {} failed. Serving stale response from cache",
+                req.uri()
+            );
+        }
+
+        let inner = self.inner.lock().await;
+
+        let response = super::http_response_builder::build_cached_response(
+            self,
+            &inner,
+            req,
+            stale_entry,
+            location_index,
+            cache_request.is_head,
+            CACHE_STATUS_STALE,
+        );
+
+        Some(self.compress_if_required(req, response))
+    }
+
     pub async fn dispose(&self) {
         let mut inner = self.inner.lock().await;
         inner.disposed = true;
     }
 
+    fn compress_if_required(
+        &self,
+        req: &HttpRequestBuilder,
+        response: hyper::Response<BoxBody<Bytes, String>>,
+    ) -> hyper::Response<BoxBody<Bytes, String>> {
+        let compression = match self.endpoint_info.compression.as_ref() {
+            Some(compression) => compression,
+            None => return response,
+        };
+
+        let accept_encoding = req
+            .get_headers()
+            .get(hyper::header::ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok());
+
+        crate::http_compression::compress_response(response, accept_encoding, compression)
+    }
+
+    // Tokens are given back when one of the limiters rejects the request,
+    // so request rejected by the location limit does not use up the endpoint budget
+    fn check_rate_limits(
+        &self,
+        inner: &HttpProxyPassInner,
+        req: &HttpRequestBuilder,
+    ) -> Result<(), ProxyPassError> {
+        let mut rate_limiters = Vec::with_capacity(2);
+
+        if let Some(rate_limiter) = self.endpoint_info.rate_limiter.as_ref() {
+            rate_limiters.push(rate_limiter.as_ref());
+        }
+
+        if let Ok(location_index) = inner.locations.find_location_index(req.uri()) {
+            let location = inner.locations.find(&location_index);
+            if let Some(rate_limiter) = location.config.rate_limiter.as_ref() {
+                rate_limiters.push(rate_limiter.as_ref());
+            }
+        }
+
+        let mut taken: Vec<(&RateLimiter, String)> = Vec::with_capacity(rate_limiters.len());
+
+        for rate_limiter in rate_limiters {
+            let key = self.get_rate_limit_key(rate_limiter, inner, req);
+
+            if let Err(retry_after) = rate_limiter.try_take(key.as_str()) {
+                for (rate_limiter, key) in taken {
+                    rate_limiter.give_back(key.as_str());
+                }
+
+                if self.endpoint_info.debug {
+                    println!(
+                        "Request to {} with rate limit key '{}' is rejected. Retry after: {:?}",
+                        self.endpoint_info.as_str(),
+                        key,
+                        retry_after
+                    );
+                }
+
+                return Err(ProxyPassError::TooManyRequests(retry_after));
+            }
+
+            taken.push((rate_limiter, key));
+        }
+
+        Ok(())
+    }
+
+    fn get_rate_limit_key(
+        &self,
+        rate_limiter: &RateLimiter,
+        inner: &HttpProxyPassInner,
+        req: &HttpRequestBuilder,
+    ) -> String {
+        // Requests which have no value for the key are limited by client ip
+        let key = match &rate_limiter.key {
+            RateLimitKey::Ip => None,
+            RateLimitKey::User => inner.identity.get_identity(),
+            RateLimitKey::Header(header) => req
+                .get_headers()
+                .get(header.as_str())
+                .and_then(|value| value.to_str().ok()),
+        };
+
+        match key {
+            Some(key) => key.to_string(),
+            None => self.listening_port_info.socket_addr.ip().to_string(),
+        }
+    }
+
     pub async fn handle_error(
         &self,
         app: &AppContext,
@@ -363,3 +885,28 @@ impl HttpProxyPass {
         Ok(do_retry)
     }
 }
+
+// Only the results which tell about the upstream health are counted
+fn report_to_circuit_breaker(
+    circuit_breaker: &CircuitBreaker,
+    result: &Result<hyper::Response<hyper::body::Incoming>, ProxyPassError>,
+) {
+    match result {
+        Ok(response) => {
+            if circuit_breaker.is_failure_status_code(response.status().as_u16()) {
+                circuit_breaker.on_failure();
+            } else {
+                circuit_breaker.on_success();
+            }
+        }
+        // Connection closed by the upstream between requests is reconnected
+        Err(ProxyPassError::HyperError(err)) if err.is_canceled() => circuit_breaker.release(),
+        Err(err) => {
+            if RetryOnError::from_error(err).is_some() {
+                circuit_breaker.on_failure();
+            } else {
+                circuit_breaker.release();
+            }
+        }
+    }
+}
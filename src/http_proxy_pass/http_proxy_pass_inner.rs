@@ -87,6 +87,12 @@ impl HttpProxyPassInner {
                             }
                         }
 
+                        "GOOGLE_EMAIL" => {
+                            if let Some(value) = self.identity.ga_user.as_ref() {
+                                result.push_str(value.as_str());
+                            }
+                        }
+
                         "ENDPOINT_SCHEMA" => {
                             if self.http_listen_port_info.http_type.is_https() {
                                 result.push_str("https");
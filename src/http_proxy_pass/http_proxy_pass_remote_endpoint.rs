@@ -2,18 +2,24 @@ use std::sync::Arc;
 
 use my_ssh::SshCredentials;
 
-use crate::configurations::*;
+use crate::{configurations::*, ssh_host_keys::SshChainHostKeys};
 
 #[derive(Debug)]
 pub enum HttpProxyPassRemoteEndpoint {
     Http(RemoteHost),
     Http2(RemoteHost),
+    Http1UnixSocket(String),
+    Http2UnixSocket(String),
     Http1OverSsh {
         ssh_credentials: Arc<SshCredentials>,
+        ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+        ssh_host_keys: Arc<SshChainHostKeys>,
         remote_host: RemoteHost,
     },
     Http2OverSsh {
         ssh_credentials: Arc<SshCredentials>,
+        ssh_jump_hosts: Vec<Arc<SshCredentials>>,
+        ssh_host_keys: Arc<SshChainHostKeys>,
         remote_host: RemoteHost,
     },
 }
@@ -23,12 +29,18 @@ impl HttpProxyPassRemoteEndpoint {
         match self {
             HttpProxyPassRemoteEndpoint::Http(_) => true,
             HttpProxyPassRemoteEndpoint::Http2(_) => false,
+            HttpProxyPassRemoteEndpoint::Http1UnixSocket(_) => true,
+            HttpProxyPassRemoteEndpoint::Http2UnixSocket(_) => false,
             HttpProxyPassRemoteEndpoint::Http1OverSsh {
                 ssh_credentials: _,
+                ssh_jump_hosts: _,
+                ssh_host_keys: _,
                 remote_host: _,
             } => true,
             HttpProxyPassRemoteEndpoint::Http2OverSsh {
                 ssh_credentials: _,
+                ssh_jump_hosts: _,
+                ssh_host_keys: _,
                 remote_host: _,
             } => false,
         }
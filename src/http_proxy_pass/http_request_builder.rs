@@ -1,17 +1,19 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full};
 use hyper::{
-    header::{HeaderName, HeaderValue},
-    HeaderMap, Request, Uri,
+    header::{self, HeaderName, HeaderValue},
+    HeaderMap, Method, Request, Uri,
 };
-use hyper_tungstenite::{tungstenite::http::request::Parts, HyperWebsocket};
+use hyper_tungstenite::tungstenite::{handshake::client::generate_key, http::request::Parts};
 use tokio::sync::Mutex;
 
-use crate::{configurations::*, settings::ModifyHttpHeadersSettings};
+use crate::{configurations::*, grpc::GrpcWebMode, settings::ModifyHttpHeadersSettings};
 
-use super::{HostPort, HttpProxyPass, HttpProxyPassInner, LocationIndex, ProxyPassError};
+use super::{
+    ClientWebSocket, HostPort, HttpProxyPass, HttpProxyPassInner, LocationIndex, ProxyPassError,
+};
 
 pub const AUTHORIZED_COOKIE_NAME: &str = "x-authorized";
 
@@ -21,7 +23,7 @@ pub enum BuildResult {
     WebSocketUpgrade {
         location_index: LocationIndex,
         upgrade_response: hyper::Response<Full<Bytes>>,
-        web_socket: Arc<Mutex<Option<HyperWebsocket>>>,
+        web_socket: Arc<Mutex<Option<ClientWebSocket>>>,
     },
 }
 
@@ -37,8 +39,12 @@ impl BuildResult {
 pub struct HttpRequestBuilder {
     src: Option<hyper::Request<hyper::body::Incoming>>,
     prepared_request: Option<hyper::Request<Full<Bytes>>>,
+    // Source request with the collected body. Used to prepare the request again for the fallback location
+    src_copy: Option<hyper::Request<Full<Bytes>>>,
     src_http_type: HttpType,
     last_result: Option<BuildResult>,
+    // Set-Cookie value which keeps the client on the selected split variant
+    sticky_cookie: Option<String>,
 }
 
 impl HttpRequestBuilder {
@@ -46,8 +52,10 @@ impl HttpRequestBuilder {
         Self {
             src: Some(src),
             prepared_request: None,
+            src_copy: None,
             src_http_type,
             last_result: None,
+            sticky_cookie: None,
         }
     }
 
@@ -61,6 +69,12 @@ impl HttpRequestBuilder {
             return Ok(last_result.clone());
         }
 
+        let (location_index, sticky_cookie) =
+            inner
+                .locations
+                .select_variant(location_index, self.uri(), self.get_headers());
+        self.sticky_cookie = sticky_cookie;
+
         let dest_http1 = inner.locations.find(&location_index).is_http1();
 
         if dest_http1.is_none() {
@@ -73,20 +87,24 @@ impl HttpRequestBuilder {
             if dest_http1 {
                 // src_http1 && dest_http1
                 let (mut parts, incoming) = self.src.take().unwrap().into_parts();
+                let body = into_full_bytes(incoming).await?;
+                self.keep_src(&parts, &body);
 
                 let websocket_update = parts.headers.get("sec-websocket-key").is_some();
 
                 handle_headers(proxy_pass, inner, &mut parts, &location_index);
 
-                let body = into_full_bytes(incoming).await?;
-
                 if websocket_update {
                     if proxy_pass.endpoint_info.debug {
                         println!("Detected Upgrade http1->http1");
                     }
 
                     let upgrade_req = hyper::Request::from_parts(parts.clone(), body.clone());
-                    let (response, web_socket) = hyper_tungstenite::upgrade(upgrade_req, None)?;
+                    let (response, web_socket) = hyper_tungstenite::upgrade(
+                        upgrade_req,
+                        Some(get_web_socket_config(inner, &location_index)),
+                    )?;
+                    parts.headers.remove(header::SEC_WEBSOCKET_EXTENSIONS);
                     //tokio::spawn(super::web_socket_loop(web_socket));
 
                     let request = hyper::Request::from_parts(parts, body);
@@ -98,7 +116,7 @@ impl HttpRequestBuilder {
                     return Ok(BuildResult::WebSocketUpgrade {
                         location_index,
                         upgrade_response: response,
-                        web_socket: Arc::new(Mutex::new(Some(web_socket))),
+                        web_socket: Arc::new(Mutex::new(Some(ClientWebSocket::Http1(web_socket)))),
                     });
                 }
 
@@ -108,9 +126,10 @@ impl HttpRequestBuilder {
                 return Ok(BuildResult::HttpRequest(location_index));
             } else {
                 let (mut parts, incoming) = self.src.take().unwrap().into_parts();
+                let body = into_full_bytes(incoming).await?;
+                self.keep_src(&parts, &body);
 
                 handle_headers(proxy_pass, inner, &mut parts, &location_index);
-                let body = into_full_bytes(incoming).await?;
 
                 let request = hyper::Request::from_parts(parts, body);
 
@@ -121,14 +140,14 @@ impl HttpRequestBuilder {
             }
         } else {
             if dest_http1 {
-                return self
-                    .http2_to_http1(location_index, proxy_pass.endpoint_info.debug)
-                    .await;
+                return self.http2_to_http1(proxy_pass, inner, location_index).await;
             } else {
                 // src_http2 && dest_http2
                 let (mut parts, incoming) = self.src.take().unwrap().into_parts();
-                handle_headers(proxy_pass, inner, &mut parts, &location_index);
                 let body = into_full_bytes(incoming).await?;
+                self.keep_src(&parts, &body);
+
+                handle_headers(proxy_pass, inner, &mut parts, &location_index);
 
                 self.prepared_request = Some(hyper::Request::from_parts(parts, body));
 
@@ -140,42 +159,80 @@ impl HttpRequestBuilder {
 
     async fn http2_to_http1(
         &mut self,
+        proxy_pass: &HttpProxyPass,
+        inner: &HttpProxyPassInner,
         location_index: LocationIndex,
-        debug: bool,
     ) -> Result<BuildResult, ProxyPassError> {
-        let (parts, incoming) = self.src.take().unwrap().into_parts();
+        let debug = proxy_pass.endpoint_info.debug;
+        let mut src = self.src.take().unwrap();
 
-        let path_and_query = if let Some(path_and_query) = parts.uri.path_and_query() {
-            path_and_query.as_str()
+        let on_upgrade = if is_web_socket_extended_connect(&src) {
+            Some(hyper::upgrade::on(&mut src))
         } else {
-            "/"
+            None
         };
 
-        let uri: Uri = path_and_query.parse().unwrap();
+        let (mut parts, incoming) = src.into_parts();
+        let body = into_full_bytes(incoming).await?;
+
+        if on_upgrade.is_none() {
+            self.keep_src(&parts, &body);
+        }
 
-        let host_header = if let Some(port) = parts.uri.port() {
-            format!("{}:{}", parts.uri.host().unwrap(), port)
+        handle_headers(proxy_pass, inner, &mut parts, &location_index);
+
+        // Extended CONNECT is turned into the http1 upgrade request
+        let method = if on_upgrade.is_some() {
+            Method::GET
         } else {
-            parts.uri.host().unwrap().to_string()
+            parts.method.clone()
         };
 
-        let mut builder = Request::builder()
-            .uri(uri)
-            .method(parts.method.clone())
-            .header("host", host_header);
+        let mut builder = create_http1_request_builder(&parts, method);
 
-        for header in parts.headers.iter() {
-            builder = builder.header(header.0, header.1);
-        }
+        if let Some(on_upgrade) = on_upgrade {
+            if debug {
+                println!("Detected extended CONNECT http2->http1");
+            }
 
-        let body = into_full_bytes(incoming).await?;
+            if let Some(headers) = builder.headers_mut() {
+                headers.remove(header::SEC_WEBSOCKET_EXTENSIONS);
+                headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+                headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+                headers.insert(
+                    header::SEC_WEBSOCKET_VERSION,
+                    HeaderValue::from_static("13"),
+                );
+                headers.insert(
+                    header::SEC_WEBSOCKET_KEY,
+                    HeaderValue::from_str(generate_key().as_str()).unwrap(),
+                );
+            }
+
+            self.prepared_request = Some(builder.body(body).unwrap());
+            self.last_result = Some(BuildResult::HttpRequest(location_index.clone()));
+
+            let client_web_socket = ClientWebSocket::Http2 {
+                on_upgrade,
+                config: get_web_socket_config(inner, &location_index),
+            };
+
+            return Ok(BuildResult::WebSocketUpgrade {
+                location_index,
+                upgrade_response: hyper::Response::new(Full::new(Bytes::new())),
+                web_socket: Arc::new(Mutex::new(Some(client_web_socket))),
+            });
+        }
 
         if parts.headers.get("sec-websocket-key").is_some() {
             if debug {
                 println!("Detected Upgrade");
             }
             let req = hyper::Request::from_parts(parts, body.clone());
-            let (response, web_socket) = hyper_tungstenite::upgrade(req, None)?;
+            let (response, web_socket) = hyper_tungstenite::upgrade(
+                req,
+                Some(get_web_socket_config(inner, &location_index)),
+            )?;
             //tokio::spawn(super::web_socket_loop(web_socket));
             let request = builder.body(body).unwrap();
 
@@ -186,7 +243,7 @@ impl HttpRequestBuilder {
             return Ok(BuildResult::WebSocketUpgrade {
                 location_index,
                 upgrade_response: response,
-                web_socket: Arc::new(Mutex::new(Some(web_socket))),
+                web_socket: Arc::new(Mutex::new(Some(ClientWebSocket::Http1(web_socket)))),
             });
         }
         let result = builder.body(body).unwrap();
@@ -195,6 +252,35 @@ impl HttpRequestBuilder {
         return Ok(BuildResult::HttpRequest(location_index));
     }
 
+    // Circuit breaker fallback location gets the request with its own headers and http protocol
+    pub fn build_for_fallback(
+        &mut self,
+        proxy_pass: &HttpProxyPass,
+        inner: &HttpProxyPassInner,
+        location_index: LocationIndex,
+    ) -> BuildResult {
+        if let Some(src) = self.src_copy.as_ref() {
+            let prepared_request = match inner.locations.find(&location_index).is_http1() {
+                Some(dest_http1) => prepare_http_request(
+                    src,
+                    self.src_http_type.is_protocol_http1(),
+                    dest_http1,
+                    |parts| handle_headers(proxy_pass, inner, parts, &location_index),
+                ),
+                None => src.clone(),
+            };
+
+            self.prepared_request = Some(prepared_request);
+        }
+
+        self.last_result = Some(BuildResult::HttpRequest(location_index.clone()));
+        BuildResult::HttpRequest(location_index)
+    }
+
+    fn keep_src(&mut self, parts: &Parts, body: &Full<Bytes>) {
+        self.src_copy = Some(hyper::Request::from_parts(parts.clone(), body.clone()));
+    }
+
     pub fn uri(&self) -> &Uri {
         if let Some(src) = self.src.as_ref() {
             return src.uri();
@@ -203,6 +289,14 @@ impl HttpRequestBuilder {
         self.prepared_request.as_ref().unwrap().uri()
     }
 
+    pub fn method(&self) -> &Method {
+        if let Some(src) = self.src.as_ref() {
+            return src.method();
+        }
+
+        self.prepared_request.as_ref().unwrap().method()
+    }
+
     pub fn get_from_query(&self, param: &str) -> Option<String> {
         let query = self.get_uri().query()?;
 
@@ -245,11 +339,55 @@ impl HttpRequestBuilder {
         }
     }
 
+    pub fn get_sticky_cookie(&self) -> Option<&str> {
+        self.sticky_cookie.as_deref()
+    }
+
     pub fn get_authorization_token(&self) -> Option<&str> {
         let result = self.get_cookie(AUTHORIZED_COOKIE_NAME);
         result
     }
 
+    // Called on each retry, request which is already translated is kept as is
+    pub async fn translate_grpc_web_request(
+        &mut self,
+        mode: GrpcWebMode,
+    ) -> Result<(), ProxyPassError> {
+        let prepared_request = match self.prepared_request.take() {
+            Some(prepared_request) => prepared_request,
+            None => return Ok(()),
+        };
+
+        if GrpcWebMode::detect(prepared_request.headers()).is_none() {
+            self.prepared_request = Some(prepared_request);
+            return Ok(());
+        }
+
+        let (mut parts, body) = prepared_request.into_parts();
+
+        let body = body.collect().await.unwrap().to_bytes();
+
+        let body = mode
+            .decode_request_body(body)
+            .map_err(ProxyPassError::InvalidGrpcWebRequest)?;
+
+        crate::grpc::translate_grpc_web_request_headers(&mut parts.headers, mode);
+        parts
+            .headers
+            .insert(header::CONTENT_LENGTH, body.len().into());
+
+        self.prepared_request = Some(hyper::Request::from_parts(parts, Full::new(body)));
+
+        Ok(())
+    }
+
+    // Headers which are added by the proxy itself after the request is prepared
+    pub fn get_prepared_headers_mut(&mut self) -> Option<&mut HeaderMap<HeaderValue>> {
+        self.prepared_request
+            .as_mut()
+            .map(|prepared_request| prepared_request.headers_mut())
+    }
+
     pub fn get(&self) -> hyper::Request<Full<Bytes>> {
         self.prepared_request.as_ref().unwrap().clone()
     }
@@ -285,6 +423,54 @@ impl HttpRequestBuilder {
      */
 }
 
+fn prepare_http_request(
+    src: &hyper::Request<Full<Bytes>>,
+    src_http1: bool,
+    dest_http1: bool,
+    handle_headers: impl FnOnce(&mut Parts),
+) -> hyper::Request<Full<Bytes>> {
+    let (mut parts, body) = src.clone().into_parts();
+
+    handle_headers(&mut parts);
+
+    if src_http1 || !dest_http1 {
+        return hyper::Request::from_parts(parts, body);
+    }
+
+    let method = parts.method.clone();
+    create_http1_request_builder(&parts, method)
+        .body(body)
+        .unwrap()
+}
+
+// Http2 request has the absolute uri. Http1 one has the path and the host header
+fn create_http1_request_builder(parts: &Parts, method: Method) -> hyper::http::request::Builder {
+    let path_and_query = if let Some(path_and_query) = parts.uri.path_and_query() {
+        path_and_query.as_str()
+    } else {
+        "/"
+    };
+
+    let uri: Uri = path_and_query.parse().unwrap();
+
+    let host_header = if let Some(port) = parts.uri.port() {
+        format!("{}:{}", parts.uri.host().unwrap(), port)
+    } else {
+        parts.uri.host().unwrap().to_string()
+    };
+
+    let mut builder = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("host", host_header);
+
+    for header in parts.headers.iter() {
+        builder = builder.header(header.0, header.1);
+    }
+
+    builder
+}
+
 pub async fn into_full_bytes(
     incoming: impl hyper::body::Body<Data = hyper::body::Bytes, Error = hyper::Error>,
 ) -> Result<Full<Bytes>, ProxyPassError> {
@@ -297,12 +483,38 @@ pub async fn into_full_bytes(
     Ok(body)
 }
 
+// RFC 8441. Http2 client opens web socket with CONNECT method and :protocol pseudo header
+fn is_web_socket_extended_connect(req: &hyper::Request<hyper::body::Incoming>) -> bool {
+    if req.method() != Method::CONNECT {
+        return false;
+    }
+
+    match req.extensions().get::<hyper::ext::Protocol>() {
+        Some(protocol) => protocol.as_str().eq_ignore_ascii_case("websocket"),
+        None => false,
+    }
+}
+
+fn get_web_socket_config(
+    inner: &HttpProxyPassInner,
+    location_index: &LocationIndex,
+) -> hyper_tungstenite::tungstenite::protocol::WebSocketConfig {
+    inner
+        .locations
+        .find(location_index)
+        .config
+        .web_socket
+        .get_protocol_config()
+}
+
 fn handle_headers(
     proxy_pass: &HttpProxyPass,
     inner: &HttpProxyPassInner,
     parts: &mut Parts,
     location_index: &LocationIndex,
 ) {
+    super::remove_identity_headers(&mut parts.headers);
+
     if let Some(modify_headers_settings) = proxy_pass
         .endpoint_info
         .modify_headers_settings
@@ -326,6 +538,10 @@ fn handle_headers(
     if let Some(modify_headers_settings) = proxy_pass_location.config.modify_headers.as_ref() {
         modify_headers(inner, parts, modify_headers_settings);
     }
+
+    if let Some(identity_headers) = proxy_pass.endpoint_info.identity_headers.as_ref() {
+        super::apply_identity_headers(identity_headers, &inner.identity, &mut parts.headers);
+    }
 }
 
 fn modify_headers<'s>(
@@ -375,3 +591,82 @@ impl HostPort for HttpRequestBuilder {
         self.prepared_request.as_ref().unwrap().headers()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http_body_util::Full;
+    use hyper::{header::HeaderValue, Request, Version};
+
+    use super::prepare_http_request;
+
+    fn http2_request() -> Request<Full<Bytes>> {
+        Request::builder()
+            .uri("https://example.com:8443/api/items?id=1")
+            .version(Version::HTTP_2)
+            .header("x-location", "primary")
+            .body(Full::new(Bytes::from_static(b"body")))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_request_is_prepared_again_for_http1_fallback() {
+        let src = http2_request();
+
+        let primary = prepare_http_request(&src, false, false, |parts| {
+            parts
+                .headers
+                .insert("x-primary", HeaderValue::from_static("true"));
+        });
+
+        assert_eq!(
+            primary.uri().to_string(),
+            "https://example.com:8443/api/items?id=1"
+        );
+        assert!(primary.headers().get("x-primary").is_some());
+
+        let fallback = prepare_http_request(&src, false, true, |parts| {
+            parts
+                .headers
+                .insert("x-fallback", HeaderValue::from_static("true"));
+        });
+
+        assert_eq!(fallback.uri().to_string(), "/api/items?id=1");
+        assert_eq!(fallback.version(), Version::HTTP_11);
+        assert_eq!(
+            fallback.headers().get("host").unwrap().to_str().unwrap(),
+            "example.com:8443"
+        );
+        assert_eq!(
+            fallback
+                .headers()
+                .get("x-location")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "primary"
+        );
+
+        // Headers of the primary location are not carried to the fallback one
+        assert!(fallback.headers().get("x-primary").is_none());
+        assert!(fallback.headers().get("x-fallback").is_some());
+    }
+
+    #[test]
+    fn test_http1_request_is_kept_for_any_fallback() {
+        let src = Request::builder()
+            .uri("/api/items")
+            .header("host", "example.com")
+            .body(Full::new(Bytes::from_static(b"body")))
+            .unwrap();
+
+        for dest_http1 in [true, false] {
+            let result = prepare_http_request(&src, true, dest_http1, |_| {});
+            assert_eq!(result.uri().to_string(), "/api/items");
+            assert_eq!(
+                result.headers().get("host").unwrap().to_str().unwrap(),
+                "example.com"
+            );
+        }
+    }
+}
@@ -6,7 +6,11 @@ use hyper::{
     HeaderMap,
 };
 
-use crate::{http_content_source::WebContentType, settings::ModifyHttpHeadersSettings};
+use crate::{
+    http_cache::{HttpCacheEntry, HttpCacheRequest},
+    http_content_source::{RequestExecutorBody, RequestExecutorResult},
+    settings::ModifyHttpHeadersSettings,
+};
 
 use super::{HostPort, HttpProxyPass, HttpProxyPassInner, LocationIndex, ProxyPassError};
 
@@ -17,8 +21,20 @@ pub async fn build_http_response<THostPort: HostPort + Send + Sync + 'static>(
     response: hyper::Response<Incoming>,
     location_index: &LocationIndex,
     dest_http1: bool,
+    cache_request: Option<HttpCacheRequest>,
 ) -> Result<hyper::Response<BoxBody<Bytes, String>>, ProxyPassError> {
-    let (mut parts, incoming) = response.into_parts();
+    let response = response.map(|incoming| incoming.map_err(|e| e.to_string()).boxed());
+
+    // Cache keeps the upstream response, headers of the proxy are applied on each serve
+    let response = match cache_request {
+        Some(cache_request) => {
+            let (parts, body) = response.into_parts();
+            cache_request.handle_response(parts, body)
+        }
+        None => response,
+    };
+
+    let (mut parts, body) = response.into_parts();
 
     if dest_http1 && !proxy_pass.listening_port_info.http_type.is_protocol_http1() {
         parts.headers.remove(header::TRANSFER_ENCODING);
@@ -37,21 +53,20 @@ pub async fn build_http_response<THostPort: HostPort + Send + Sync + 'static>(
         location_index,
     );
 
-    //let body = into_full_bytes(incoming).await?;
-    Ok(hyper::Response::from_parts(
-        parts,
-        incoming.map_err(|e| e.to_string()).boxed(),
-    ))
+    Ok(hyper::Response::from_parts(parts, body))
 }
 
-/*
-pub async fn build_chunked_http_response<THostPort: HostPort + Send + Sync + 'static>(
+pub fn build_cached_response<THostPort: HostPort + Send + Sync + 'static>(
     proxy_pass: &HttpProxyPass,
     inner: &HttpProxyPassInner,
     req_host_port: &THostPort,
-    mut response: hyper::Response<Incoming>,
+    entry: &HttpCacheEntry,
     location_index: &LocationIndex,
-) -> Result<hyper::Response<BoxBody<Bytes, String>>, ProxyPassError> {
+    is_head: bool,
+    cache_status: &'static str,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let mut response = entry.to_response(is_head, cache_status);
+
     modify_req_headers(
         proxy_pass,
         inner,
@@ -60,65 +75,26 @@ pub async fn build_chunked_http_response<THostPort: HostPort + Send + Sync + 'st
         location_index,
     );
 
-    let (parts, body) = response.into_parts();
-
-
-       let mut in_stream = body.into_data_stream();
-       let (mut sender, receiver) = futures::channel::mpsc::channel(1024);
-
-       let stream_body = StreamBody::new(receiver);
-
-       tokio::spawn(async move {
-           while let Some(chunk) = in_stream.next().await {
-               match chunk {
-                   Ok(chunk) => {
-                       let data_len = chunk.len();
-
-                       let chunk = hyper::body::Frame::data(chunk);
-                       let send_result = sender.send(Ok(chunk)).await;
-
-                       if let Err(err) = send_result {
-                           println!("Channel send error: {:?}", err);
-                           break;
-                       } else {
-                           println!("Sent to channel: {} bytes", data_len);
-                       }
-                   }
-                   Err(e) => {
-                       println!("Channel receive error: {:?}", e);
-                       break;
-                   }
-               }
-           }
-
-           println!("Http 1.1 Channel closed");
-       });
-
-    // let response = response.map_err(|e| e.to_string()).boxed();
-
-    // let box_body = stream_body.map_err(|e: hyper::Error| e.to_string()).boxed();
-    Ok(hyper::Response::from_parts(
-        parts,
-        body.map_err(|e| e.to_string()).boxed(),
-    ))
+    response
 }
-    */
 
 pub fn build_response_from_content<THostPort: HostPort + Send + Sync + 'static>(
     http_proxy_pass: &HttpProxyPass,
     inner: &HttpProxyPassInner,
     req_host_port: &THostPort,
     location_index: &LocationIndex,
-    content_type: Option<WebContentType>,
-    status_code: u16,
-    content: Vec<u8>,
+    content: RequestExecutorResult,
 ) -> hyper::Response<BoxBody<Bytes, String>> {
-    let mut builder = hyper::Response::builder().status(status_code);
+    let mut builder = hyper::Response::builder().status(content.status_code);
 
-    if let Some(content_type) = content_type {
+    if let Some(content_type) = content.content_type {
         builder = builder.header("Content-Type", content_type.as_str());
     }
 
+    for (name, value) in content.headers {
+        builder = builder.header(name, value);
+    }
+
     if let Some(headers) = builder.headers_mut() {
         modify_req_headers(
             http_proxy_pass,
@@ -129,10 +105,21 @@ pub fn build_response_from_content<THostPort: HostPort + Send + Sync + 'static>(
         );
     }
 
-    let full_body = http_body_util::Full::new(hyper::body::Bytes::from(content));
-    builder
-        .body(full_body.map_err(|e| crate::to_hyper_error(e)).boxed())
-        .unwrap()
+    match content.body {
+        RequestExecutorBody::Full(content) => {
+            let full_body = http_body_util::Full::new(hyper::body::Bytes::from(content));
+            builder
+                .body(full_body.map_err(|e| crate::to_hyper_error(e)).boxed())
+                .unwrap()
+        }
+        RequestExecutorBody::Stream {
+            content_length,
+            body,
+        } => builder
+            .header(header::CONTENT_LENGTH, content_length)
+            .body(body)
+            .unwrap(),
+    }
 }
 
 fn modify_req_headers<THostPort: HostPort + Send + Sync + 'static>(
@@ -0,0 +1,181 @@
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use hyper::{header::HeaderValue, HeaderMap};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use sha2::Sha256;
+
+use crate::configurations::*;
+
+use super::HttpProxyPassIdentity;
+
+pub const X_AUTH_USER_HEADER: &str = "x-auth-user";
+pub const X_AUTH_EMAIL_HEADER: &str = "x-auth-email";
+pub const X_AUTH_METHOD_HEADER: &str = "x-auth-method";
+pub const X_CLIENT_CERT_SERIAL_HEADER: &str = "x-client-cert-serial";
+pub const X_AUTH_TIMESTAMP_HEADER: &str = "x-auth-timestamp";
+pub const X_AUTH_SIGNATURE_HEADER: &str = "x-auth-signature";
+pub const X_AUTH_TOKEN_HEADER: &str = "x-auth-token";
+
+const IDENTITY_HEADERS: [&str; 7] = [
+    X_AUTH_USER_HEADER,
+    X_AUTH_EMAIL_HEADER,
+    X_AUTH_METHOD_HEADER,
+    X_CLIENT_CERT_SERIAL_HEADER,
+    X_AUTH_TIMESTAMP_HEADER,
+    X_AUTH_SIGNATURE_HEADER,
+    X_AUTH_TOKEN_HEADER,
+];
+
+pub const AUTH_METHOD_CLIENT_CERT: &str = "client-cert";
+pub const AUTH_METHOD_GOOGLE: &str = "google";
+
+// Client must never be able to pass identity headers to the upstream on its own
+pub fn remove_identity_headers(headers: &mut HeaderMap<HeaderValue>) {
+    for header in IDENTITY_HEADERS {
+        headers.remove(header);
+    }
+}
+
+pub fn apply_identity_headers(
+    config: &IdentityHeadersConfig,
+    identity: &HttpProxyPassIdentity,
+    headers: &mut HeaderMap<HeaderValue>,
+) {
+    let user = match identity.get_identity() {
+        Some(user) => user,
+        None => return,
+    };
+
+    let email = identity.ga_user.as_ref().map(|itm| itm.as_str());
+
+    let method = if identity.client_cert_cn.is_some() {
+        AUTH_METHOD_CLIENT_CERT
+    } else {
+        AUTH_METHOD_GOOGLE
+    };
+
+    let serial = identity
+        .client_cert_cn
+        .as_ref()
+        .map(|itm| itm.serial.to_str_radix(16));
+
+    insert_header(headers, X_AUTH_USER_HEADER, user);
+
+    if let Some(email) = email {
+        insert_header(headers, X_AUTH_EMAIL_HEADER, email);
+    }
+
+    insert_header(headers, X_AUTH_METHOD_HEADER, method);
+
+    if let Some(serial) = serial.as_ref() {
+        insert_header(headers, X_CLIENT_CERT_SERIAL_HEADER, serial);
+    }
+
+    let now = DateTimeAsMicroseconds::now().unix_microseconds / 1_000_000;
+
+    match &config.sign {
+        IdentityHeadersSignType::None => {}
+        IdentityHeadersSignType::Hmac => {
+            let timestamp = now.to_string();
+            let payload = format!(
+                "{}\n{}\n{}\n{}\n{}",
+                user,
+                email.unwrap_or_default(),
+                method,
+                serial.as_deref().unwrap_or_default(),
+                timestamp
+            );
+
+            let signature = hex_encode(&sign(&config.key, payload.as_bytes()));
+
+            insert_header(headers, X_AUTH_TIMESTAMP_HEADER, &timestamp);
+            insert_header(headers, X_AUTH_SIGNATURE_HEADER, &signature);
+        }
+        IdentityHeadersSignType::Jwt { ttl } => {
+            let mut claims = serde_json::Map::new();
+            claims.insert("sub".to_string(), user.into());
+            if let Some(email) = email {
+                claims.insert("email".to_string(), email.into());
+            }
+            claims.insert("auth_method".to_string(), method.into());
+            if let Some(serial) = serial.as_ref() {
+                claims.insert("cert_serial".to_string(), serial.as_str().into());
+            }
+            claims.insert("iat".to_string(), now.into());
+            claims.insert("exp".to_string(), (now + ttl.as_secs() as i64).into());
+
+            let token = create_jwt(&config.key, &serde_json::Value::Object(claims));
+
+            insert_header(headers, X_AUTH_TOKEN_HEADER, &token);
+        }
+    }
+}
+
+fn create_jwt(key: &[u8], claims: &serde_json::Value) -> String {
+    let encoder = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+    let mut result = encoder.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+    result.push('.');
+    result.push_str(&encoder.encode(claims.to_string()));
+
+    let signature = sign(key, result.as_bytes());
+
+    result.push('.');
+    result.push_str(&encoder.encode(signature));
+    result
+}
+
+fn sign(key: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex_encode(src: &[u8]) -> String {
+    let mut result = String::with_capacity(src.len() * 2);
+    for b in src {
+        result.push_str(format!("{:02x}", b).as_str());
+    }
+    result
+}
+
+fn insert_header(headers: &mut HeaderMap<HeaderValue>, name: &'static str, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    #[test]
+    fn test_hmac_signature() {
+        let signature = super::sign(b"key", b"The quick brown fox jumps over the lazy dog");
+
+        assert_eq!(
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+            super::hex_encode(&signature)
+        );
+    }
+
+    #[test]
+    fn test_jwt_signature() {
+        let claims = serde_json::json!({"sub": "user@domain.com"});
+        let token = super::create_jwt(b"secret", &claims);
+
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(3, parts.len());
+
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(parts[1])
+            .unwrap();
+        assert_eq!(claims.to_string().as_bytes(), payload.as_slice());
+
+        let signature = super::sign(b"secret", format!("{}.{}", parts[0], parts[1]).as_bytes());
+        assert_eq!(
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature),
+            parts[2]
+        );
+    }
+}
@@ -25,3 +25,9 @@ mod http_proxy_pass_identity;
 pub use http_proxy_pass_identity::*;
 mod http_proxy_pass_remote_endpoint;
 pub use http_proxy_pass_remote_endpoint::*;
+mod identity_headers;
+pub use identity_headers::*;
+mod web_socket_connections;
+pub use web_socket_connections::*;
+mod streaming_body;
+pub use streaming_body::*;
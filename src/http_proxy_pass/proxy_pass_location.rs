@@ -2,28 +2,38 @@ use std::{sync::Arc, time::Duration};
 
 use hyper::Uri;
 
-use crate::{app::AppContext, configurations::*, http_proxy_pass::HttpProxyPassContentSource};
+use crate::{
+    app::AppContext, circuit_breaker::CircuitBreaker, configurations::*,
+    http_proxy_pass::HttpProxyPassContentSource,
+};
 
-use super::ProxyPassError;
+use super::{LocationIndex, ProxyPassError};
 
 pub struct ProxyPassLocation {
     pub content_source: HttpProxyPassContentSource,
+    // Retry upstreams. Active one is always kept as content_source
+    retry_content_sources: Vec<HttpProxyPassContentSource>,
+    // Index of the active upstream: 0 - proxy_pass_to, then retry upstreams
+    active_upstream: usize,
     pub config: Arc<ProxyPassLocationConfig>,
     pub is_http1: Option<bool>,
+    // Locations of the split variants in the same order as in the split config
+    pub variants: Vec<LocationIndex>,
 }
 
 impl ProxyPassLocation {
-    pub fn new(
-        config: Arc<ProxyPassLocationConfig>,
-        debug: bool,
-        request_timeout: Duration,
-    ) -> Self {
-        let content_source = config.create_content_source(debug, request_timeout);
+    pub fn new(config: Arc<ProxyPassLocationConfig>, debug: bool) -> Self {
+        let read_timeout = config.timeouts.get_read_timeout();
+        let content_source = config.create_content_source(debug, read_timeout);
+        let retry_content_sources = config.create_retry_content_sources(debug, read_timeout);
         let is_http1 = content_source.is_http1();
         Self {
             content_source: content_source,
+            retry_content_sources,
+            active_upstream: 0,
             config,
             is_http1,
+            variants: vec![],
         }
     }
 
@@ -33,6 +43,10 @@ impl ProxyPassLocation {
             self.config.path.as_str(),
         );
 
+        if result && self.config.grpc.is_some() {
+            return is_grpc_path_boundary(uri.path(), self.config.path.as_str());
+        }
+
         result
     }
 
@@ -40,6 +54,52 @@ impl ProxyPassLocation {
         self.is_http1
     }
 
+    pub fn has_retry_upstreams(&self) -> bool {
+        !self.retry_content_sources.is_empty()
+    }
+
+    // Upstreams are tried in turn: the failed one goes to the end of the queue
+    pub fn switch_upstream(&mut self) {
+        if self.retry_content_sources.is_empty() {
+            return;
+        }
+
+        std::mem::swap(&mut self.content_source, &mut self.retry_content_sources[0]);
+        self.retry_content_sources.rotate_left(1);
+        self.is_http1 = self.content_source.is_http1();
+        self.active_upstream = (self.active_upstream + 1) % (self.retry_content_sources.len() + 1);
+    }
+
+    // Finds the upstream which circuit lets the request through.
+    // Returns Err with the shortest time after which one of the upstreams is going to be probed
+    pub fn acquire_upstream(&mut self) -> Result<Option<Arc<CircuitBreaker>>, Duration> {
+        // Config is cloned since switch_upstream borrows self mutably
+        let config = self.config.clone();
+        let circuit_breakers = match config.circuit_breaker.as_ref() {
+            Some(circuit_breaker_config) => &circuit_breaker_config.circuit_breakers,
+            None => return Ok(None),
+        };
+
+        let mut retry_after = Duration::MAX;
+
+        for _ in 0..circuit_breakers.len() {
+            // Count of circuit breakers matches the count of upstreams. It is checked on config load
+            let circuit_breaker = match circuit_breakers.get(self.active_upstream) {
+                Some(circuit_breaker) => circuit_breaker,
+                None => return Ok(None),
+            };
+
+            match circuit_breaker.try_acquire() {
+                Ok(()) => return Ok(Some(circuit_breaker.clone())),
+                Err(duration) => retry_after = retry_after.min(duration),
+            }
+
+            self.switch_upstream();
+        }
+
+        Err(retry_after)
+    }
+
     pub async fn connect_if_require(
         &mut self,
         app: &AppContext,
@@ -50,3 +110,15 @@ impl ProxyPassLocation {
             .await
     }
 }
+
+// Grpc path is /package.Service/Method. Location /package.Service/Get must not catch /package.Service/GetAll
+fn is_grpc_path_boundary(uri_path: &str, location_path: &str) -> bool {
+    if location_path.ends_with('/') {
+        return true;
+    }
+
+    match uri_path.as_bytes().get(location_path.len()) {
+        Some(next) => *next == b'/',
+        None => true,
+    }
+}
@@ -1,6 +1,4 @@
-use std::time::Duration;
-
-use hyper::Uri;
+use hyper::{HeaderMap, Uri};
 
 use crate::configurations::*;
 
@@ -17,16 +15,37 @@ pub struct ProxyPassLocations {
 }
 
 impl ProxyPassLocations {
-    pub fn new(endpoint_info: &HttpEndpointInfo, request_timeout: Duration) -> Self {
+    pub fn new(endpoint_info: &HttpEndpointInfo) -> Self {
         let mut data = Vec::with_capacity(endpoint_info.locations.len());
         for location in &endpoint_info.locations {
             data.push(ProxyPassLocation::new(
                 location.clone(),
                 endpoint_info.debug,
-                request_timeout,
             ))
         }
 
+        // Variants go after all the locations, so they are never matched by path
+        for index in 0..endpoint_info.locations.len() {
+            let split = match endpoint_info.locations[index].split.as_ref() {
+                Some(split) => split,
+                None => continue,
+            };
+
+            for variant in &split.variants {
+                let variant_index = LocationIndex {
+                    index: data.len(),
+                    id: variant.config.id,
+                };
+
+                data.push(ProxyPassLocation::new(
+                    variant.config.clone(),
+                    endpoint_info.debug,
+                ));
+
+                data[index].variants.push(variant_index);
+            }
+        }
+
         Self { data }
     }
 
@@ -43,6 +62,41 @@ impl ProxyPassLocations {
         return Err(ProxyPassError::NoLocationFound);
     }
 
+    // Returns the location of the selected split variant and the sticky cookie to set
+    pub fn select_variant(
+        &self,
+        location_index: LocationIndex,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> (LocationIndex, Option<String>) {
+        let location = self.find(&location_index);
+
+        let split = match location.config.split.as_ref() {
+            Some(split) => split,
+            None => return (location_index, None),
+        };
+
+        let selection = split.select(uri, headers);
+
+        match selection.variant {
+            Some(variant) => (location.variants[variant].clone(), selection.set_cookie),
+            None => (location_index, selection.set_cookie),
+        }
+    }
+
+    pub fn find_by_path(&self, path: &str) -> Option<LocationIndex> {
+        for (index, proxy_pass) in self.data.iter().enumerate() {
+            if proxy_pass.config.path == path {
+                return Some(LocationIndex {
+                    index,
+                    id: proxy_pass.config.id,
+                });
+            }
+        }
+
+        None
+    }
+
     pub fn find(&self, location_index: &LocationIndex) -> &ProxyPassLocation {
         if let Some(location) = self.data.get(location_index.index) {
             return location;
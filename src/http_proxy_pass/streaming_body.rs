@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::SinkExt;
+use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
+use hyper::header::HeaderValue;
+
+use crate::configurations::StreamingConfig;
+
+pub fn build_streaming_response(
+    response: hyper::Response<BoxBody<Bytes, String>>,
+    config: &StreamingConfig,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let (mut parts, body) = response.into_parts();
+
+    // Asks the proxies in front of us not to buffer the response
+    parts
+        .headers
+        .insert("x-accel-buffering", HeaderValue::from_static("no"));
+
+    let body = match config.idle_body_timeout {
+        Some(idle_body_timeout) => with_idle_body_timeout(body, idle_body_timeout),
+        None => body,
+    };
+
+    hyper::Response::from_parts(parts, body)
+}
+
+// Each frame is sent to the client as soon as it is received from the upstream
+fn with_idle_body_timeout(
+    mut body: BoxBody<Bytes, String>,
+    idle_body_timeout: Duration,
+) -> BoxBody<Bytes, String> {
+    let (mut sender, receiver) = futures::channel::mpsc::channel(1);
+
+    tokio::spawn(async move {
+        loop {
+            let frame = match tokio::time::timeout(idle_body_timeout, body.frame()).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return,
+                Err(_) => {
+                    let _ = sender
+                        .send(Err(format!(
+                            "No data from upstream for {:?}",
+                            idle_body_timeout
+                        )))
+                        .await;
+                    return;
+                }
+            };
+
+            let is_err = frame.is_err();
+
+            // Client is gone
+            if sender.send(frame).await.is_err() || is_err {
+                return;
+            }
+        }
+    });
+
+    StreamBody::new(receiver).boxed()
+}
@@ -0,0 +1,99 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+pub struct WebSocketConnection {
+    pub id: i64,
+    pub endpoint: String,
+    pub path: String,
+    pub client_addr: String,
+    pub connected_at: DateTimeAsMicroseconds,
+    pub messages_from_client: AtomicU64,
+    pub bytes_from_client: AtomicU64,
+    pub messages_from_remote: AtomicU64,
+    pub bytes_from_remote: AtomicU64,
+}
+
+impl WebSocketConnection {
+    pub fn add_from_client(&self, size: usize) {
+        self.messages_from_client.fetch_add(1, Ordering::Relaxed);
+        self.bytes_from_client
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_from_remote(&self, size: usize) {
+        self.messages_from_remote.fetch_add(1, Ordering::Relaxed);
+        self.bytes_from_remote
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+// Active web socket connections. Rendered on the control page and returned by the current configuration api
+pub struct WebSocketConnections {
+    items: Mutex<BTreeMap<i64, Arc<WebSocketConnection>>>,
+    next_id: AtomicI64,
+}
+
+impl WebSocketConnections {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(BTreeMap::new()),
+            next_id: AtomicI64::new(0),
+        }
+    }
+
+    pub fn add(
+        self: &Arc<Self>,
+        endpoint: String,
+        path: String,
+        client_addr: String,
+    ) -> WebSocketConnectionGuard {
+        let connection = Arc::new(WebSocketConnection {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            endpoint,
+            path,
+            client_addr,
+            connected_at: DateTimeAsMicroseconds::now(),
+            messages_from_client: AtomicU64::new(0),
+            bytes_from_client: AtomicU64::new(0),
+            messages_from_remote: AtomicU64::new(0),
+            bytes_from_remote: AtomicU64::new(0),
+        });
+
+        self.items
+            .lock()
+            .unwrap()
+            .insert(connection.id, connection.clone());
+
+        WebSocketConnectionGuard {
+            connection,
+            connections: self.clone(),
+        }
+    }
+
+    pub fn get_snapshot(&self) -> Vec<Arc<WebSocketConnection>> {
+        self.items.lock().unwrap().values().cloned().collect()
+    }
+}
+
+// Connection is removed from the list once the web socket loop is finished
+pub struct WebSocketConnectionGuard {
+    pub connection: Arc<WebSocketConnection>,
+    connections: Arc<WebSocketConnections>,
+}
+
+impl Drop for WebSocketConnectionGuard {
+    fn drop(&mut self) {
+        self.connections
+            .items
+            .lock()
+            .unwrap()
+            .remove(&self.connection.id);
+    }
+}
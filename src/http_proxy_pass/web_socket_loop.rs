@@ -1,66 +1,224 @@
+use std::time::Duration;
+
 use futures::{
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
-use hyper::upgrade::Upgraded;
+use hyper::upgrade::{OnUpgrade, Upgraded};
 use hyper_tungstenite::{
-    tungstenite::Message, HyperWebsocket, HyperWebsocketStream, WebSocketStream,
+    tungstenite::{
+        self,
+        protocol::{frame::coding::CloseCode, CloseFrame, Role, WebSocketConfig},
+        Message,
+    },
+    HyperWebsocket, HyperWebsocketStream, WebSocketStream,
 };
 use hyper_util::rt::TokioIo;
 
-type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+use crate::configurations::WebSocketLocationConfig;
+
+use super::WebSocketConnectionGuard;
+
+// Time to wait for the close frame reply of the other side before dropping the connection
+const CLOSE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type WsSink = SplitSink<HyperWebsocketStream, Message>;
+type WsStream = SplitStream<HyperWebsocketStream>;
+
+pub enum ClientWebSocket {
+    Http1(HyperWebsocket),
+    // RFC 8441 extended CONNECT. Stream is available once 200 response is sent to the client
+    Http2 {
+        on_upgrade: OnUpgrade,
+        config: WebSocketConfig,
+    },
+}
+
+impl ClientWebSocket {
+    async fn accept(self) -> Result<HyperWebsocketStream, String> {
+        match self {
+            ClientWebSocket::Http1(web_socket) => {
+                web_socket.await.map_err(|err| format!("{}", err))
+            }
+            ClientWebSocket::Http2 { on_upgrade, config } => {
+                let upgraded = on_upgrade.await.map_err(|err| format!("{}", err))?;
+                Ok(WebSocketStream::from_raw_socket(
+                    TokioIo::new(upgraded),
+                    Role::Server,
+                    Some(config),
+                )
+                .await)
+            }
+        }
+    }
+}
 
 pub async fn web_socket_loop(
-    server_web_socket: HyperWebsocket,
+    server_web_socket: ClientWebSocket,
     to_remote_upgraded: Upgraded,
+    config: WebSocketLocationConfig,
+    guard: WebSocketConnectionGuard,
     debug: bool,
 ) {
-    let ws_stream = server_web_socket.await;
+    let ws_stream = match server_web_socket.accept().await {
+        Ok(ws_stream) => ws_stream,
+        Err(err) => {
+            if debug {
+                println!("Error in websocket connection: {}", err);
+            }
+            return;
+        }
+    };
 
     let to_remote = WebSocketStream::from_raw_socket(
         TokioIo::new(to_remote_upgraded),
-        hyper_tungstenite::tungstenite::protocol::Role::Client,
-        None,
+        Role::Client,
+        Some(config.get_protocol_config()),
     )
     .await;
 
-    let (to_remote_write, mut from_remote_read) = to_remote.split();
+    let (mut client_write, mut client_read) = ws_stream.split();
+    let (mut remote_write, mut remote_read) = to_remote.split();
 
-    match ws_stream {
-        Ok(ws_stream) => {
-            let (mut ws_sender, ws_receiver) = ws_stream.split();
+    let mut ping_interval = config.ping_interval.map(|ping_interval| {
+        let mut result =
+            tokio::time::interval_at(tokio::time::Instant::now() + ping_interval, ping_interval);
+        result.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        result
+    });
 
-            tokio::spawn(serve_from_server_to_client(ws_receiver, to_remote_write));
+    // Tokio treats too big duration as the far future, so there is no idle timeout by default
+    let idle_timeout = config.idle_timeout.unwrap_or(Duration::MAX);
+    let idle_sleep = tokio::time::sleep(idle_timeout);
+    tokio::pin!(idle_sleep);
 
-            while let Some(message) = from_remote_read.next().await {
-                let message = message.unwrap();
+    loop {
+        let ping_tick = async {
+            match ping_interval.as_mut() {
+                Some(ping_interval) => {
+                    ping_interval.tick().await;
+                }
+                None => futures::future::pending().await,
+            }
+        };
 
-                if let Err(err) = ws_sender.send(message).await {
-                    if debug {
-                        println!("ws_sender.send error: {:?}", err);
+        tokio::select! {
+            message = client_read.next() => {
+                let message = match read_message(message, &mut client_write, debug).await {
+                    Some(message) => message,
+                    None => {
+                        close(&mut remote_write, CloseCode::Away, "Client is disconnected").await;
+                        return;
                     }
+                };
+
+                guard.connection.add_from_client(message.len());
+
+                if let Message::Close(frame) = message {
+                    let _ = remote_write.send(Message::Close(frame)).await;
+                    let _ = client_write.close().await;
+                    wait_for_close_reply(&mut remote_read).await;
+                    return;
+                }
 
+                if remote_write.send(message).await.is_err() {
+                    close(&mut client_write, CloseCode::Away, "Remote is disconnected").await;
                     return;
                 }
             }
+
+            message = remote_read.next() => {
+                let message = match read_message(message, &mut remote_write, debug).await {
+                    Some(message) => message,
+                    None => {
+                        close(&mut client_write, CloseCode::Away, "Remote is disconnected").await;
+                        return;
+                    }
+                };
+
+                guard.connection.add_from_remote(message.len());
+
+                if let Message::Close(frame) = message {
+                    let _ = client_write.send(Message::Close(frame)).await;
+                    let _ = remote_write.close().await;
+                    wait_for_close_reply(&mut client_read).await;
+                    return;
+                }
+
+                if client_write.send(message).await.is_err() {
+                    close(&mut remote_write, CloseCode::Away, "Client is disconnected").await;
+                    return;
+                }
+            }
+
+            _ = ping_tick => {
+                if client_write.send(Message::Ping(Default::default())).await.is_err() {
+                    close(&mut remote_write, CloseCode::Away, "Client is disconnected").await;
+                    return;
+                }
+                continue;
+            }
+
+            _ = &mut idle_sleep => {
+                if debug {
+                    println!("Web socket connection {} is idle. Closing", guard.connection.id);
+                }
+                close(&mut client_write, CloseCode::Away, "Idle timeout").await;
+                close(&mut remote_write, CloseCode::Away, "Idle timeout").await;
+                return;
+            }
+        }
+
+        if config.idle_timeout.is_some() {
+            idle_sleep
+                .as_mut()
+                .reset(tokio::time::Instant::now() + idle_timeout);
+        }
+    }
+}
+
+// Returns None if the side is gone. Too big message is answered with 1009 close code to the side which sent it
+async fn read_message(
+    message: Option<Result<Message, tungstenite::Error>>,
+    reply_to: &mut WsSink,
+    debug: bool,
+) -> Option<Message> {
+    match message? {
+        Ok(message) => Some(message),
+        Err(tungstenite::Error::Capacity(err)) => {
+            if debug {
+                println!("Web socket message is over the limit: {}", err);
+            }
+            close(reply_to, CloseCode::Size, "Message is too big").await;
+            None
         }
         Err(err) => {
             if debug {
-                println!("Error in websocket connection: {}", err);
+                println!("Web socket read error: {}", err);
             }
+            None
         }
     }
 }
 
-async fn serve_from_server_to_client(
-    mut websocket: SplitStream<HyperWebsocketStream>,
-    mut to_remote_write: SplitSink<WebSocketStream<TokioIo<Upgraded>>, Message>,
-) -> Result<(), Error> {
-    while let Some(message) = websocket.next().await {
-        let msg: Message = message?;
+async fn close(sink: &mut WsSink, code: CloseCode, reason: &'static str) {
+    let frame = CloseFrame {
+        code,
+        reason: reason.into(),
+    };
 
-        to_remote_write.send(msg).await.unwrap();
-    }
+    let _ = sink.send(Message::Close(Some(frame))).await;
+    let _ = sink.close().await;
+}
 
-    Ok(())
+async fn wait_for_close_reply(stream: &mut WsStream) {
+    let _ = tokio::time::timeout(CLOSE_HANDSHAKE_TIMEOUT, async {
+        while let Some(message) = stream.next().await {
+            match message {
+                Ok(Message::Close(_)) | Err(_) => return,
+                Ok(_) => {}
+            }
+        }
+    })
+    .await;
 }
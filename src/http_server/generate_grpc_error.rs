@@ -0,0 +1,45 @@
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Empty};
+
+use crate::grpc::GrpcStatus;
+use crate::http_proxy_pass::ProxyPassError;
+
+// Grpc clients do not read html pages. Error is sent as trailers-only response: http 200 with grpc-status in headers
+pub fn generate_grpc_error(
+    err: ProxyPassError,
+    content_type: &str,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let (grpc_status, message) = match err {
+        ProxyPassError::Timeout => (GrpcStatus::DeadlineExceeded, "Timeout"),
+        ProxyPassError::NoLocationFound => (GrpcStatus::Unimplemented, "Not found"),
+        ProxyPassError::Unauthorized => (GrpcStatus::Unauthenticated, "Unauthorized request"),
+        ProxyPassError::UserIsForbidden => (GrpcStatus::PermissionDenied, "Access is forbidden"),
+        ProxyPassError::IpRestricted(_) => (GrpcStatus::PermissionDenied, "Restricted by IP"),
+        ProxyPassError::TooManyRequests(_) => (GrpcStatus::ResourceExhausted, "Too many requests"),
+        ProxyPassError::CircuitOpen(_) => (GrpcStatus::Unavailable, "Service unavailable"),
+        ProxyPassError::HttpClientError(_)
+        | ProxyPassError::ConnectionIsDisposed
+        | ProxyPassError::SshSessionError(_)
+        | ProxyPassError::SshConnectError(_) => (GrpcStatus::Unavailable, "Bad gateway"),
+        ProxyPassError::HyperError(_) | ProxyPassError::IoError(_) => {
+            (GrpcStatus::Unavailable, "Upstream connection error")
+        }
+        ProxyPassError::InvalidGrpcWebRequest(_) => {
+            (GrpcStatus::Internal, "Invalid grpc-web request")
+        }
+        _ => (GrpcStatus::Unknown, "Internal Server Error"),
+    };
+
+    hyper::Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .header("grpc-status", grpc_status.as_code().to_string())
+        .header("grpc-message", message)
+        .body(
+            Empty::<Bytes>::new()
+                .map_err(|e| crate::to_hyper_error(e))
+                .boxed(),
+        )
+        .unwrap()
+}
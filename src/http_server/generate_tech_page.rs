@@ -3,17 +3,18 @@ use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Full};
 
 use crate::app::APP_VERSION;
+use crate::configurations::ErrorPagesConfig;
 use crate::http_proxy_pass::ProxyPassError;
 
 pub fn generate_tech_page(err: ProxyPassError) -> hyper::Response<BoxBody<Bytes, String>> {
     match err {
         ProxyPassError::Timeout => {
-            let body: Bytes = generate_layout(500, "Timeout", None).into();
+            let body: Bytes = generate_layout(504, "Timeout", None).into();
             let body = Full::new(body)
                 .map_err(|e| crate::to_hyper_error(e))
                 .boxed();
             return hyper::Response::builder()
-                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .status(hyper::StatusCode::GATEWAY_TIMEOUT)
                 .body(body)
                 .unwrap();
         }
@@ -52,6 +53,32 @@ pub fn generate_tech_page(err: ProxyPassError) -> hyper::Response<BoxBody<Bytes,
                 .unwrap();
         }
 
+        ProxyPassError::TooManyRequests(retry_after) => {
+            let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::TOO_MANY_REQUESTS)
+                .header("Retry-After", retry_after.to_string())
+                .body(
+                    Full::from(generate_layout(429, "Too many requests", None))
+                        .map_err(|e| crate::to_hyper_error(e))
+                        .boxed(),
+                )
+                .unwrap();
+        }
+
+        ProxyPassError::CircuitOpen(retry_after) => {
+            let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::SERVICE_UNAVAILABLE)
+                .header("Retry-After", retry_after.to_string())
+                .body(
+                    Full::from(generate_layout(503, "Service unavailable", None))
+                        .map_err(|e| crate::to_hyper_error(e))
+                        .boxed(),
+                )
+                .unwrap();
+        }
+
         ProxyPassError::IpRestricted(ip) => {
             return hyper::Response::builder()
                 .status(hyper::StatusCode::UNAUTHORIZED)
@@ -62,6 +89,44 @@ pub fn generate_tech_page(err: ProxyPassError) -> hyper::Response<BoxBody<Bytes,
                 )
                 .unwrap();
         }
+
+        ProxyPassError::InvalidGrpcWebRequest(err) => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_REQUEST)
+                .body(
+                    Full::from(generate_layout(400, "Bad request", Some(err.as_str())))
+                        .map_err(|e| crate::to_hyper_error(e))
+                        .boxed(),
+                )
+                .unwrap();
+        }
+
+        ProxyPassError::HttpClientError(_) => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_GATEWAY)
+                .body(
+                    Full::from(generate_layout(502, "Bad gateway", None))
+                        .map_err(|e| crate::to_hyper_error(e))
+                        .boxed(),
+                )
+                .unwrap();
+        }
+
+        ProxyPassError::SshSessionError(err) => {
+            let second_line = crate::ssh_host_keys::get_host_key_verification_error(&err);
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_GATEWAY)
+                .body(
+                    Full::from(generate_layout(
+                        502,
+                        "Ssh session error",
+                        second_line.as_deref(),
+                    ))
+                    .map_err(|e| crate::to_hyper_error(e))
+                    .boxed(),
+                )
+                .unwrap();
+        }
         _ => {
             return hyper::Response::builder()
                 .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
@@ -75,6 +140,33 @@ pub fn generate_tech_page(err: ProxyPassError) -> hyper::Response<BoxBody<Bytes,
     }
 }
 
+// Replaces the body of the tech page with the custom one. Headers like Retry-After are kept
+pub fn apply_error_page(
+    response: hyper::Response<BoxBody<Bytes, String>>,
+    error_pages: &ErrorPagesConfig,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let error_page = match error_pages.get(response.status().as_u16()) {
+        Some(error_page) => error_page,
+        None => return response,
+    };
+
+    let (mut parts, _) = response.into_parts();
+
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+
+    if let Ok(content_type) = error_page.content_type.parse() {
+        parts
+            .headers
+            .insert(hyper::header::CONTENT_TYPE, content_type);
+    }
+
+    let body = Full::from(error_page.content.clone())
+        .map_err(|e| crate::to_hyper_error(e))
+        .boxed();
+
+    hyper::Response::from_parts(parts, body)
+}
+
 pub fn generate_layout(status_code: u16, text: &str, second_line: Option<&str>) -> Bytes {
     let second_line = if let Some(second_line) = second_line {
         format!("<h4>{}</h4>", second_line)
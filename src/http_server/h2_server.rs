@@ -14,9 +14,10 @@ pub fn start_h2_server(addr: SocketAddr, app: Arc<AppContext>) {
 
 async fn start_https2_server_loop(listening_addr: SocketAddr, app: Arc<AppContext>) {
     let listener = tokio::net::TcpListener::bind(listening_addr).await.unwrap();
-    let http2_builder = Arc::new(hyper::server::conn::http2::Builder::new(
-        TokioExecutor::new(),
-    ));
+    let mut http2_builder = hyper::server::conn::http2::Builder::new(TokioExecutor::new());
+    // Web sockets over http2 (RFC 8441)
+    http2_builder.enable_connect_protocol();
+    let http2_builder = Arc::new(http2_builder);
     loop {
         let accepted_connection = listener.accept().await;
 
@@ -35,14 +36,32 @@ async fn start_https2_server_loop(listening_addr: SocketAddr, app: Arc<AppContex
 
         let (stream, socket_addr) = accepted_connection.unwrap();
 
+        let connection_permit = match super::handle_request::acquire_connection_on_accept(
+            &app,
+            listening_addr.port(),
+            socket_addr,
+        )
+        .await
+        {
+            Ok(connection_permit) => connection_permit,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+
         let app = app.clone();
         let builder = http2_builder.clone();
 
         tokio::spawn(async move {
             let io = TokioIo::new(stream);
 
-            let http_request_handler =
-                HttpRequestHandler::new_lazy(app.clone(), listening_addr.port(), socket_addr);
+            let http_request_handler = HttpRequestHandler::new_lazy(
+                app.clone(),
+                listening_addr.port(),
+                socket_addr,
+                connection_permit,
+            );
 
             let http_request_handler = Arc::new(http_request_handler);
 
@@ -51,11 +70,7 @@ async fn start_https2_server_loop(listening_addr: SocketAddr, app: Arc<AppContex
                 .serve_connection(
                     io,
                     service_fn(move |req| {
-                        super::handle_request::handle_request(
-                            http_request_handler.clone(),
-                            req,
-                            app.connection_settings.remote_connect_timeout,
-                        )
+                        super::handle_request::handle_request(http_request_handler.clone(), req)
                     }),
                 )
                 .await;
@@ -0,0 +1,414 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use base64::Engine;
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::{client::conn::http1::SendRequest, header, Method, StatusCode};
+use hyper_util::rt::TokioIo;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::{
+    app::AppContext,
+    configurations::*,
+    http_client::HTTP_CLIENT_TIMEOUT,
+    tcp_port_forward::{ForwardProxyConnectError, ForwardProxyRemoteStream},
+};
+
+const PROXY_AUTHORIZATION_HEADER: &str = "proxy-authorization";
+const PROXY_CONNECTION_HEADER: &str = "proxy-connection";
+const PROXY_AUTHENTICATE_HEADER: &str = "proxy-authenticate";
+
+// Headers which describe the connection between the client and the proxy (RFC 9110 7.6.1).
+// Headers listed in Connection header are hop-by-hop as well
+const HOP_BY_HOP_HEADERS: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    PROXY_CONNECTION_HEADER,
+    PROXY_AUTHORIZATION_HEADER,
+    PROXY_AUTHENTICATE_HEADER,
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+pub async fn handle_forward_proxy_request(
+    app: &Arc<AppContext>,
+    endpoint_info: &Arc<ForwardProxyEndpointHostConfig>,
+    socket_addr: SocketAddr,
+    req: hyper::Request<hyper::body::Incoming>,
+) -> hyper::Result<hyper::Response<BoxBody<Bytes, String>>> {
+    if let Some(credentials) = endpoint_info.credentials.as_ref() {
+        if !is_authorized(&req, credentials) {
+            if endpoint_info.debug {
+                println!(
+                    "Forward proxy request from {} is not authorized",
+                    socket_addr
+                );
+            }
+
+            return Ok(hyper::Response::builder()
+                .status(StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+                .header(PROXY_AUTHENTICATE_HEADER, r#"Basic realm="proxy""#)
+                .body(create_body(super::generate_layout(
+                    407,
+                    "Proxy authentication required",
+                    None,
+                )))
+                .unwrap());
+        }
+    }
+
+    let (host, port) = match get_destination(&req) {
+        Some(destination) => destination,
+        None => {
+            return Ok(create_error_response(
+                StatusCode::BAD_REQUEST,
+                "Request must be CONNECT or has absolute http uri",
+            ));
+        }
+    };
+
+    if req.method() == Method::CONNECT {
+        return handle_connect(app, endpoint_info, socket_addr, req, host, port).await;
+    }
+
+    handle_absolute_uri(app, endpoint_info, socket_addr, req, host, port).await
+}
+
+async fn handle_connect(
+    app: &Arc<AppContext>,
+    endpoint_info: &Arc<ForwardProxyEndpointHostConfig>,
+    socket_addr: SocketAddr,
+    req: hyper::Request<hyper::body::Incoming>,
+    host: String,
+    port: u16,
+) -> hyper::Result<hyper::Response<BoxBody<Bytes, String>>> {
+    let remote_stream = match crate::tcp_port_forward::connect_forward_proxy_destination(
+        app,
+        endpoint_info,
+        &host,
+        port,
+    )
+    .await
+    {
+        Ok(remote_stream) => remote_stream,
+        Err(ForwardProxyConnectError::NotAllowed) => {
+            return Ok(create_not_allowed_response(socket_addr, &host, port));
+        }
+        Err(err) => {
+            if endpoint_info.debug {
+                println!(
+                    "Forward proxy connection from {} can not connect to {}:{}. Err: {}",
+                    socket_addr,
+                    host,
+                    port,
+                    err.to_string()
+                );
+            }
+
+            return Ok(create_connect_error_response(err));
+        }
+    };
+
+    let buffer_size = app.connection_settings.buffer_size;
+    let debug = endpoint_info.debug;
+
+    tokio::spawn(async move {
+        let upgraded = match hyper::upgrade::on(req).await {
+            Ok(upgraded) => upgraded,
+            Err(err) => {
+                if debug {
+                    println!(
+                        "Forward proxy connection from {} can not be upgraded. Err: {:?}",
+                        socket_addr, err
+                    );
+                }
+                return;
+            }
+        };
+
+        let (client_reader, client_writer) = tokio::io::split(TokioIo::new(upgraded));
+
+        crate::tcp_port_forward::relay_forward_proxy_connection(
+            client_reader,
+            client_writer,
+            remote_stream,
+            buffer_size,
+            debug,
+            move || {
+                if debug {
+                    println!(
+                        "Dead forward proxy connection {}->{}:{} detected. Closing",
+                        socket_addr, host, port
+                    );
+                }
+            },
+        )
+        .await;
+    });
+
+    Ok(hyper::Response::builder()
+        .status(StatusCode::OK)
+        .body(create_body(Bytes::new()))
+        .unwrap())
+}
+
+async fn handle_absolute_uri(
+    app: &Arc<AppContext>,
+    endpoint_info: &Arc<ForwardProxyEndpointHostConfig>,
+    socket_addr: SocketAddr,
+    req: hyper::Request<hyper::body::Incoming>,
+    host: String,
+    port: u16,
+) -> hyper::Result<hyper::Response<BoxBody<Bytes, String>>> {
+    let remote_host = get_host_port(&host, port);
+
+    // Destination is connected the same way as for CONNECT: all the allowed addresses are tried within the connect timeout
+    let remote_stream = match crate::tcp_port_forward::connect_forward_proxy_destination(
+        app,
+        endpoint_info,
+        &host,
+        port,
+    )
+    .await
+    {
+        Ok(remote_stream) => remote_stream,
+        Err(ForwardProxyConnectError::NotAllowed) => {
+            return Ok(create_not_allowed_response(socket_addr, &host, port));
+        }
+        Err(err) => {
+            if endpoint_info.debug {
+                println!(
+                    "Forward proxy can not connect to {}. Err: {}",
+                    remote_host,
+                    err.to_string()
+                );
+            }
+
+            return Ok(create_connect_error_response(err));
+        }
+    };
+
+    let mut sender = match start_http1_connection(remote_stream).await {
+        Ok(sender) => sender,
+        Err(err) => {
+            if endpoint_info.debug {
+                println!(
+                    "Forward proxy can not start http connection to {}. Err: {:?}",
+                    remote_host, err
+                );
+            }
+            return Ok(create_error_response(
+                StatusCode::BAD_GATEWAY,
+                "Can not connect to destination",
+            ));
+        }
+    };
+
+    let (mut parts, incoming) = req.into_parts();
+
+    remove_hop_by_hop_headers(&mut parts.headers);
+
+    // Upstream expects origin-form uri
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|itm| itm.as_str())
+        .unwrap_or("/");
+    parts.uri = path_and_query.parse().unwrap();
+
+    if !parts.headers.contains_key(header::HOST) {
+        if let Ok(value) = remote_host.parse() {
+            parts.headers.insert(header::HOST, value);
+        }
+    }
+
+    // Request body is streamed to the destination as it comes
+    let future = sender.send_request(hyper::Request::from_parts(parts, incoming.boxed()));
+
+    let response = match tokio::time::timeout(HTTP_CLIENT_TIMEOUT, future).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(err)) => {
+            if endpoint_info.debug {
+                println!(
+                    "Forward proxy request to {} failed. Err: {:?}",
+                    remote_host, err
+                );
+            }
+            return Ok(create_error_response(
+                StatusCode::BAD_GATEWAY,
+                "Can not get response from destination",
+            ));
+        }
+        Err(_) => {
+            if endpoint_info.debug {
+                println!("Forward proxy request to {} is timed out", remote_host);
+            }
+            return Ok(create_error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                "Timeout",
+            ));
+        }
+    };
+
+    let (mut parts, incoming) = response.into_parts();
+
+    remove_hop_by_hop_headers(&mut parts.headers);
+
+    Ok(hyper::Response::from_parts(
+        parts,
+        incoming.map_err(|e| e.to_string()).boxed(),
+    ))
+}
+
+async fn start_http1_connection(
+    remote_stream: ForwardProxyRemoteStream,
+) -> Result<SendRequest<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    match remote_stream {
+        ForwardProxyRemoteStream::Direct(tcp_stream) => {
+            start_http1_connection_over(TokioIo::new(tcp_stream)).await
+        }
+        ForwardProxyRemoteStream::Ssh(ssh_channel) => {
+            start_http1_connection_over(TokioIo::new(ssh_channel.compat())).await
+        }
+    }
+}
+
+async fn start_http1_connection_over(
+    io: impl hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+) -> Result<SendRequest<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    let (sender, conn) = hyper::client::conn::http1::handshake(io).await?;
+
+    tokio::spawn(async move {
+        if let Err(err) = conn.await {
+            println!("Forward proxy http connection is failed: {:?}", err);
+        }
+    });
+
+    Ok(sender)
+}
+
+fn remove_hop_by_hop_headers(headers: &mut hyper::HeaderMap) {
+    let connection_headers: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    for name in connection_headers {
+        headers.remove(name.as_str());
+    }
+
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+}
+
+fn get_destination(req: &hyper::Request<hyper::body::Incoming>) -> Option<(String, u16)> {
+    let authority = req.uri().authority()?;
+
+    // IPv6 host comes in brackets
+    let host = authority
+        .host()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+
+    if req.method() == Method::CONNECT {
+        return Some((host, authority.port_u16().unwrap_or(443)));
+    }
+
+    if req.uri().scheme_str() != Some("http") {
+        return None;
+    }
+
+    Some((host, authority.port_u16().unwrap_or(80)))
+}
+
+fn get_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn is_authorized(
+    req: &hyper::Request<hyper::body::Incoming>,
+    credentials: &(String, String),
+) -> bool {
+    let header = match req.headers().get(PROXY_AUTHORIZATION_HEADER) {
+        Some(header) => header,
+        None => return false,
+    };
+
+    let header = match header.to_str() {
+        Ok(header) => header,
+        Err(_) => return false,
+    };
+
+    // Auth scheme is case-insensitive (RFC 9110 11.1)
+    let encoded = match header.trim().split_once(' ') {
+        Some((scheme, encoded)) if scheme.eq_ignore_ascii_case("basic") => encoded.trim(),
+        _ => return false,
+    };
+
+    let decoded = match base64::engine::general_purpose::STANDARD.decode(encoded) {
+        Ok(decoded) => decoded,
+        Err(_) => return false,
+    };
+
+    let (username, password) = credentials;
+    crate::tcp_port_forward::are_credentials_same(
+        &decoded,
+        format!("{}:{}", username, password).as_bytes(),
+    )
+}
+
+fn create_not_allowed_response(
+    socket_addr: SocketAddr,
+    host: &str,
+    port: u16,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    println!(
+        "Forward proxy request from {} to {}:{} is not allowed",
+        socket_addr, host, port
+    );
+
+    create_error_response(StatusCode::FORBIDDEN, "Destination is not allowed")
+}
+
+fn create_connect_error_response(
+    err: ForwardProxyConnectError,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    match err {
+        ForwardProxyConnectError::Timeout => {
+            create_error_response(StatusCode::GATEWAY_TIMEOUT, "Timeout")
+        }
+        _ => create_error_response(StatusCode::BAD_GATEWAY, "Can not connect to destination"),
+    }
+}
+
+fn create_error_response(
+    status_code: StatusCode,
+    text: &str,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    hyper::Response::builder()
+        .status(status_code)
+        .body(create_body(super::generate_layout(
+            status_code.as_u16(),
+            text,
+            None,
+        )))
+        .unwrap()
+}
+
+fn create_body(content: Bytes) -> BoxBody<Bytes, String> {
+    Full::new(content)
+        .map_err(|e| crate::to_hyper_error(e))
+        .boxed()
+}
@@ -7,7 +7,9 @@ use tokio::sync::Mutex;
 
 use crate::{
     app::AppContext,
+    configurations::ForwardProxyEndpointHostConfig,
     http_proxy_pass::{HostPort, HttpProxyPass},
+    rate_limit::ConnectionPermit,
 };
 
 pub enum HttpRequestHandler {
@@ -16,15 +18,27 @@ pub enum HttpRequestHandler {
         app: Arc<AppContext>,
         listen_port: u16,
         socket_addr: SocketAddr,
+        // Taken at accept when the endpoint is known before the first request
+        connection_permit: std::sync::Mutex<Option<ConnectionPermit>>,
     },
     Direct {
         proxy_pass: HttpProxyPass,
         app: Arc<AppContext>,
     },
+    ForwardProxy {
+        endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+        app: Arc<AppContext>,
+        socket_addr: SocketAddr,
+    },
 }
 
 impl HttpRequestHandler {
-    pub fn new_lazy(app: Arc<AppContext>, listen_port: u16, socket_addr: SocketAddr) -> Self {
+    pub fn new_lazy(
+        app: Arc<AppContext>,
+        listen_port: u16,
+        socket_addr: SocketAddr,
+        connection_permit: Option<ConnectionPermit>,
+    ) -> Self {
         app.http_connections
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         Self::LazyInit {
@@ -32,6 +46,7 @@ impl HttpRequestHandler {
             app,
             listen_port,
             socket_addr,
+            connection_permit: std::sync::Mutex::new(connection_permit),
         }
     }
 
@@ -41,14 +56,23 @@ impl HttpRequestHandler {
         Self::Direct { proxy_pass, app }
     }
 
+    pub fn new_forward_proxy(
+        endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+        app: Arc<AppContext>,
+        socket_addr: SocketAddr,
+    ) -> Self {
+        app.http_connections
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Self::ForwardProxy {
+            endpoint_info,
+            app,
+            socket_addr,
+        }
+    }
+
     pub async fn dispose(&self) {
         match self {
-            HttpRequestHandler::LazyInit {
-                proxy_pass,
-                app: _,
-                listen_port: _,
-                socket_addr: _,
-            } => {
+            HttpRequestHandler::LazyInit { proxy_pass, .. } => {
                 let proxy_pass = proxy_pass.lock().await.clone();
 
                 if let Some(proxy_pass) = proxy_pass {
@@ -56,6 +80,7 @@ impl HttpRequestHandler {
                 }
             }
             HttpRequestHandler::Direct { proxy_pass, app: _ } => proxy_pass.dispose().await,
+            HttpRequestHandler::ForwardProxy { .. } => {}
         }
     }
 }
@@ -63,7 +88,6 @@ impl HttpRequestHandler {
 pub async fn handle_request(
     handler: Arc<HttpRequestHandler>,
     req: hyper::Request<hyper::body::Incoming>,
-    request_timeout: std::time::Duration,
 ) -> hyper::Result<hyper::Response<BoxBody<Bytes, String>>> {
     match handler.as_ref() {
         HttpRequestHandler::LazyInit {
@@ -71,6 +95,7 @@ pub async fn handle_request(
             app,
             listen_port,
             socket_addr,
+            connection_permit,
         } => {
             let mut proxy_pass_result = {
                 let proxy_pass = proxy_pass.lock().await;
@@ -85,6 +110,19 @@ pub async fn handle_request(
 
                 match http_endpoint_info {
                     Ok(endpoint_info) => {
+                        let accepted_permit = connection_permit.lock().unwrap().take();
+
+                        let connection_permit = match accepted_permit {
+                            Some(connection_permit) => Some(connection_permit),
+                            None => match endpoint_info.try_acquire_connection(*socket_addr) {
+                                Ok(connection_permit) => connection_permit,
+                                Err(err) => {
+                                    println!("{}", err);
+                                    return Ok(generate_connection_rejected_response(&req));
+                                }
+                            },
+                        };
+
                         let listening_port_info =
                             endpoint_info.get_listening_port_info(*socket_addr);
 
@@ -92,7 +130,7 @@ pub async fn handle_request(
                             endpoint_info,
                             listening_port_info,
                             None,
-                            request_timeout,
+                            connection_permit,
                         ));
 
                         proxy_pass_result = Some(http_proxy_pass);
@@ -120,6 +158,58 @@ pub async fn handle_request(
         HttpRequestHandler::Direct { proxy_pass, app } => {
             handle_requests(app, req, proxy_pass).await
         }
+        HttpRequestHandler::ForwardProxy {
+            endpoint_info,
+            app,
+            socket_addr,
+        } => {
+            super::handle_forward_proxy_request::handle_forward_proxy_request(
+                app,
+                endpoint_info,
+                *socket_addr,
+                req,
+            )
+            .await
+        }
+    }
+}
+
+// Endpoint of the port with several endpoints is known only by the request host.
+// Connection over the limit gets the response and is closed
+fn generate_connection_rejected_response(
+    req: &hyper::Request<hyper::body::Incoming>,
+) -> hyper::Response<BoxBody<Bytes, String>> {
+    let content = super::generate_layout(503, "Too many connections", None);
+
+    let mut builder = hyper::Response::builder().status(hyper::StatusCode::SERVICE_UNAVAILABLE);
+
+    if req.version() < hyper::Version::HTTP_2 {
+        builder = builder.header(hyper::header::CONNECTION, "close");
+    }
+
+    builder
+        .body(
+            Full::new(content)
+                .map_err(|e| crate::to_hyper_error(e))
+                .boxed(),
+        )
+        .unwrap()
+}
+
+// Connection to the port with the only endpoint is checked against the limit before it is served
+pub async fn acquire_connection_on_accept(
+    app: &AppContext,
+    listen_port: u16,
+    socket_addr: SocketAddr,
+) -> Result<Option<ConnectionPermit>, String> {
+    let endpoint_info = app
+        .get_current_app_configuration()
+        .await
+        .get_single_http_endpoint_info(listen_port);
+
+    match endpoint_info {
+        Some(endpoint_info) => endpoint_info.try_acquire_connection(socket_addr),
+        None => Ok(None),
     }
 }
 
@@ -143,6 +233,13 @@ async fn handle_requests(
         None
     };
 
+    // Response has to mirror the grpc content type of the request, so it is taken before the request is consumed
+    let grpc_content_type = if crate::grpc::is_grpc_request(req.headers()) {
+        crate::grpc::get_grpc_content_type(req.headers()).map(|itm| itm.to_string())
+    } else {
+        None
+    };
+
     match proxy_pass.send_payload(&app, req).await {
         Ok(response) => {
             match response.as_ref() {
@@ -168,7 +265,17 @@ async fn handle_requests(
             return response;
         }
         Err(err) => {
-            return Ok(super::generate_tech_page(err));
+            if let Some(grpc_content_type) = grpc_content_type {
+                return Ok(super::generate_grpc_error(err, grpc_content_type.as_str()));
+            }
+
+            let response = super::generate_tech_page(err);
+
+            if let Some(error_pages) = proxy_pass.endpoint_info.error_pages.as_ref() {
+                return Ok(super::apply_error_page(response, error_pages));
+            }
+
+            return Ok(response);
         }
     }
 }
@@ -0,0 +1,119 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use hyper::{server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+
+use crate::{app::AppContext, configurations::ForwardProxyEndpointHostConfig};
+
+use super::handle_request::HttpRequestHandler;
+
+pub fn start_http_forward_proxy_server(
+    addr: SocketAddr,
+    app: Arc<AppContext>,
+    endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+) {
+    println!(
+        "Enabled Http forward proxy: {} -> {}",
+        addr,
+        endpoint_info.get_egress_as_string()
+    );
+    tokio::spawn(start_http_forward_proxy_server_loop(
+        addr,
+        app,
+        endpoint_info,
+    ));
+}
+
+async fn start_http_forward_proxy_server_loop(
+    listening_addr: SocketAddr,
+    app: Arc<AppContext>,
+    endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+) {
+    let listener = tokio::net::TcpListener::bind(listening_addr).await;
+
+    if let Err(err) = listener {
+        println!(
+            "Error binding to tcp port {} for http forward proxy has Error: {:?}",
+            listening_addr, err
+        );
+        return;
+    }
+
+    let listener = listener.unwrap();
+
+    let mut http1 = http1::Builder::new();
+    http1.keep_alive(true);
+
+    loop {
+        let accepted_connection = listener.accept().await;
+
+        if app.states.is_shutting_down() {
+            println!("Shutting down http forward proxy server");
+            break;
+        }
+
+        if let Err(err) = &accepted_connection {
+            println!(
+                "Error accepting connection {}. Err: {:?}",
+                listening_addr, err
+            );
+            continue;
+        }
+
+        let (stream, socket_addr) = accepted_connection.unwrap();
+
+        if !endpoint_info
+            .whitelisted_ip
+            .is_whitelisted(&socket_addr.ip())
+        {
+            if endpoint_info.debug {
+                println!(
+                    "Incoming http forward proxy connection from {} is not whitelisted. Closing it",
+                    socket_addr
+                );
+            }
+            continue;
+        }
+
+        let connection_permit = match endpoint_info.connections_limit.as_ref() {
+            Some(connections_limit) => match connections_limit.try_acquire(socket_addr.ip()) {
+                Some(permit) => Some(permit),
+                None => {
+                    if endpoint_info.debug {
+                        println!(
+                            "Incoming http forward proxy connection from {} is over the connections limit. Closing it",
+                            socket_addr
+                        );
+                    }
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let io = TokioIo::new(stream);
+
+        let http_request_handler = Arc::new(HttpRequestHandler::new_forward_proxy(
+            endpoint_info.clone(),
+            app.clone(),
+            socket_addr,
+        ));
+
+        let http_request_handler_disposed = http_request_handler.clone();
+
+        let connection = http1
+            .serve_connection(
+                io,
+                service_fn(move |req| {
+                    super::handle_request::handle_request(http_request_handler.clone(), req)
+                }),
+            )
+            .with_upgrades();
+
+        tokio::task::spawn(async move {
+            let _ = connection.await;
+            http_request_handler_disposed.dispose().await;
+            drop(connection_permit);
+        });
+    }
+}
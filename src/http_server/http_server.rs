@@ -17,8 +17,6 @@ async fn start_http_server_loop(listening_addr: SocketAddr, app: Arc<AppContext>
     let mut http1 = http1::Builder::new();
     http1.keep_alive(true);
 
-    let request_timeout = app.connection_settings.remote_connect_timeout;
-
     loop {
         let accepted_connection = listener.accept().await;
 
@@ -38,10 +36,28 @@ async fn start_http_server_loop(listening_addr: SocketAddr, app: Arc<AppContext>
 
         let (stream, socket_addr) = accepted_connection.unwrap();
 
+        let connection_permit = match super::handle_request::acquire_connection_on_accept(
+            &app,
+            listening_addr.port(),
+            socket_addr,
+        )
+        .await
+        {
+            Ok(connection_permit) => connection_permit,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+
         let io = TokioIo::new(stream);
 
-        let http_request_handler =
-            HttpRequestHandler::new_lazy(app.clone(), listening_addr.port(), socket_addr);
+        let http_request_handler = HttpRequestHandler::new_lazy(
+            app.clone(),
+            listening_addr.port(),
+            socket_addr,
+            connection_permit,
+        );
 
         let http_request_handler = Arc::new(http_request_handler);
 
@@ -51,11 +67,7 @@ async fn start_http_server_loop(listening_addr: SocketAddr, app: Arc<AppContext>
             .serve_connection(
                 io,
                 service_fn(move |req| {
-                    super::handle_request::handle_request(
-                        http_request_handler.clone(),
-                        req,
-                        request_timeout,
-                    )
+                    super::handle_request::handle_request(http_request_handler.clone(), req)
                 }),
             )
             .with_upgrades();
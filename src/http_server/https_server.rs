@@ -11,6 +11,7 @@ use crate::app::AppContext;
 use crate::configurations::*;
 use crate::http_proxy_pass::HttpProxyPass;
 use crate::http_server::handle_request::HttpRequestHandler;
+use crate::rate_limit::ConnectionPermit;
 
 use super::ClientCertificateData;
 
@@ -48,7 +49,12 @@ async fn start_https_server_loop(addr: SocketAddr, app: Arc<AppContext>) {
         println!("Accepted connection from  {}", socket_addr);
 
         let app = app.clone();
-        handle_connection(app, endpoint_port, tcp_stream, socket_addr).await;
+        tokio::spawn(handle_connection(
+            app,
+            endpoint_port,
+            tcp_stream,
+            socket_addr,
+        ));
     }
 }
 
@@ -76,10 +82,33 @@ async fn handle_connection(
 
     let (tls_stream, endpoint_info, cn_user_name) = result.unwrap();
 
+    // Endpoint is known after the handshake. Connection over the limit is closed right away
+    let connection_permit = match endpoint_info.try_acquire_connection(socket_addr) {
+        Ok(connection_permit) => connection_permit,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
     if endpoint_info.http_type.is_protocol_http1() {
-        kick_off_https1(app, socket_addr, endpoint_info, tls_stream, cn_user_name);
+        kick_off_https1(
+            app,
+            socket_addr,
+            endpoint_info,
+            tls_stream,
+            cn_user_name,
+            connection_permit,
+        );
     } else {
-        kick_off_https2(app, socket_addr, endpoint_info, tls_stream, cn_user_name);
+        kick_off_https2(
+            app,
+            socket_addr,
+            endpoint_info,
+            tls_stream,
+            cn_user_name,
+            connection_permit,
+        );
     }
 }
 
@@ -175,6 +204,7 @@ fn kick_off_https1(
     endpoint_info: Arc<HttpEndpointInfo>,
     tls_stream: tokio_rustls::server::TlsStream<tokio::net::TcpStream>,
     cn_user_name: Option<ClientCertificateData>,
+    connection_permit: Option<ConnectionPermit>,
 ) {
     use hyper::{server::conn::http1, service::service_fn};
     let mut http1 = http1::Builder::new();
@@ -187,7 +217,7 @@ fn kick_off_https1(
             endpoint_info,
             listening_port_info,
             cn_user_name,
-            app.connection_settings.remote_connect_timeout,
+            connection_permit,
         );
 
         let http_request_handler = HttpRequestHandler::new(http_proxy_pass, app.clone());
@@ -201,11 +231,7 @@ fn kick_off_https1(
             .serve_connection(
                 TokioIo::new(tls_stream),
                 service_fn(move |req| {
-                    super::handle_request::handle_request(
-                        http_request_handler.clone(),
-                        req,
-                        app.connection_settings.remote_connect_timeout,
-                    )
+                    super::handle_request::handle_request(http_request_handler.clone(), req)
                 }),
             )
             .with_upgrades()
@@ -224,6 +250,7 @@ fn kick_off_https2(
     endpoint_info: Arc<HttpEndpointInfo>,
     tls_stream: tokio_rustls::server::TlsStream<tokio::net::TcpStream>,
     client_certificate: Option<ClientCertificateData>,
+    connection_permit: Option<ConnectionPermit>,
 ) {
     use hyper::service::service_fn;
     use hyper_util::server::conn::auto::Builder;
@@ -231,7 +258,9 @@ fn kick_off_https2(
     use hyper_util::rt::TokioExecutor;
 
     tokio::spawn(async move {
-        let http_builder = Builder::new(TokioExecutor::new());
+        let mut http_builder = Builder::new(TokioExecutor::new());
+        // Web sockets over http2 (RFC 8441)
+        http_builder.http2().enable_connect_protocol();
 
         let listening_port_info = endpoint_info.get_listening_port_info(socket_addr);
 
@@ -239,7 +268,7 @@ fn kick_off_https2(
             endpoint_info,
             listening_port_info,
             client_certificate,
-            app.connection_settings.remote_connect_timeout,
+            connection_permit,
         );
 
         let http_request_handler = HttpRequestHandler::new(http_proxy_pass, app.clone());
@@ -249,15 +278,10 @@ fn kick_off_https2(
         let http_request_handler_dispose = http_request_handler.clone();
 
         if let Err(err) = http_builder
-            .clone()
-            .serve_connection(
+            .serve_connection_with_upgrades(
                 TokioIo::new(tls_stream),
                 service_fn(move |req| {
-                    super::handle_request::handle_request(
-                        http_request_handler.clone(),
-                        req,
-                        app.connection_settings.remote_connect_timeout,
-                    )
+                    super::handle_request::handle_request(http_request_handler.clone(), req)
                 }),
             )
             .await
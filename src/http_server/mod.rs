@@ -1,19 +1,28 @@
 mod http_server;
 pub use http_server::*;
 
+mod http_forward_proxy_server;
+pub use http_forward_proxy_server::*;
+
 mod h2_server;
 pub use h2_server::*;
 
 mod https_server;
 pub use https_server::*;
 
+mod unix_socket_http_server;
+pub use unix_socket_http_server::*;
+
 mod client_certificate_ca;
 pub use client_certificate_ca::*;
 mod client_cert_verifier;
 pub use client_cert_verifier::*;
 //mod https2_server;
 //pub use https2_server::*;
+mod generate_grpc_error;
 mod generate_tech_page;
+pub use generate_grpc_error::*;
+mod handle_forward_proxy_request;
 mod handle_request;
 pub use generate_tech_page::*;
 mod client_cert_cell;
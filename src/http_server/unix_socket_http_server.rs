@@ -0,0 +1,152 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    os::unix::fs::PermissionsExt,
+    sync::Arc,
+};
+
+use hyper::{server::conn::http1, service::service_fn};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+
+use crate::{
+    app::AppContext, configurations::UnixSocketHttpEndpointConfig, http_proxy_pass::HttpProxyPass,
+};
+
+use super::handle_request::HttpRequestHandler;
+
+// Unix socket peer has no ip address. It is a local process, so whitelists and rate limits see it as localhost
+const UNIX_SOCKET_PEER_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+pub fn start_unix_socket_http_server(
+    app: Arc<AppContext>,
+    endpoint_config: Arc<UnixSocketHttpEndpointConfig>,
+) {
+    println!(
+        "Listening {} on unix socket {}",
+        endpoint_config.endpoint_info.http_type.to_str(),
+        endpoint_config.unix_socket_path
+    );
+    tokio::spawn(start_unix_socket_http_server_loop(app, endpoint_config));
+}
+
+async fn start_unix_socket_http_server_loop(
+    app: Arc<AppContext>,
+    endpoint_config: Arc<UnixSocketHttpEndpointConfig>,
+) {
+    let unix_socket_path = endpoint_config.unix_socket_path.as_str();
+
+    // Socket file is left by the previous run if it was not stopped gracefully
+    if let Ok(metadata) = tokio::fs::symlink_metadata(unix_socket_path).await {
+        if std::os::unix::fs::FileTypeExt::is_socket(&metadata.file_type()) {
+            let _ = tokio::fs::remove_file(unix_socket_path).await;
+        }
+    }
+
+    let listener = match tokio::net::UnixListener::bind(unix_socket_path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!(
+                "Error binding to unix socket {}. Err: {:?}",
+                unix_socket_path, err
+            );
+            return;
+        }
+    };
+
+    if let Some(permissions) = endpoint_config.permissions {
+        if let Err(err) = tokio::fs::set_permissions(
+            unix_socket_path,
+            std::fs::Permissions::from_mode(permissions),
+        )
+        .await
+        {
+            println!(
+                "Can not set permissions {:o} to unix socket {}. Err: {:?}",
+                permissions, unix_socket_path, err
+            );
+        }
+    }
+
+    let mut http1 = http1::Builder::new();
+    http1.keep_alive(true);
+
+    let mut http2 = hyper::server::conn::http2::Builder::new(TokioExecutor::new());
+    http2.enable_connect_protocol();
+
+    loop {
+        let accepted_connection = listener.accept().await;
+
+        if app.states.is_shutting_down() {
+            println!("Shutting down unix socket http server {}", unix_socket_path);
+            let _ = tokio::fs::remove_file(unix_socket_path).await;
+            break;
+        }
+
+        let stream = match accepted_connection {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                println!(
+                    "Error accepting connection {}. Err: {:?}",
+                    unix_socket_path, err
+                );
+                continue;
+            }
+        };
+
+        // Configuration could be reloaded since the listener is started
+        let endpoint_info = match app
+            .get_current_app_configuration()
+            .await
+            .unix_socket_http_endpoints
+            .get(unix_socket_path)
+        {
+            Some(endpoint_config) => endpoint_config.endpoint_info.clone(),
+            None => {
+                println!(
+                    "Unix socket {} is not in configuration anymore. Closing connection",
+                    unix_socket_path
+                );
+                continue;
+            }
+        };
+
+        let connection_permit = match endpoint_info.try_acquire_connection(UNIX_SOCKET_PEER_ADDR) {
+            Ok(connection_permit) => connection_permit,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+
+        let listening_port_info = endpoint_info.get_listening_port_info(UNIX_SOCKET_PEER_ADDR);
+        let is_http1 = endpoint_info.http_type.is_protocol_http1();
+
+        let http_proxy_pass =
+            HttpProxyPass::new(endpoint_info, listening_port_info, None, connection_permit);
+
+        let http_request_handler = Arc::new(HttpRequestHandler::new(http_proxy_pass, app.clone()));
+
+        let http_request_handler_disposed = http_request_handler.clone();
+
+        let io = TokioIo::new(stream);
+
+        let service = service_fn(move |req| {
+            super::handle_request::handle_request(http_request_handler.clone(), req)
+        });
+
+        if is_http1 {
+            let connection = http1.serve_connection(io, service).with_upgrades();
+
+            tokio::spawn(async move {
+                let _ = connection.await;
+                http_request_handler_disposed.dispose().await;
+            });
+        } else {
+            let connection = http2.serve_connection(io, service);
+
+            tokio::spawn(async move {
+                let _ = connection.await;
+                http_request_handler_disposed.dispose().await;
+            });
+        }
+    }
+}
@@ -7,23 +7,34 @@ use timers::CrlRefresherTimer;
 mod app;
 mod flows;
 //mod http2_executor;
+mod circuit_breaker;
 mod configurations;
 mod crl;
 mod files_cache;
 mod google_auth;
+mod grpc;
+mod http_cache;
 mod http_client;
+mod http_compression;
 mod http_content_source;
 mod http_control;
 mod http_proxy_pass;
 mod http_server;
 mod populate_variable;
+mod rate_limit;
 mod self_signed_cert;
 mod settings;
-mod ssh_to_http_port_forward_pool;
+mod socks5;
+mod ssh_host_keys;
+mod ssh_jump_hosts;
+mod ssh_reverse;
+mod ssh_sessions_pool;
 mod ssl;
 mod tcp_port_forward;
 mod timers;
+mod traffic_mirror;
 mod types;
+mod udp_port_forward;
 mod variables_reader;
 
 pub fn to_hyper_error(e: std::convert::Infallible) -> String {
@@ -58,8 +69,6 @@ async fn main() {
 
     println!("Shutting down...");
 
-    app.ssh_to_http_port_forward_pool.clean_up().await;
-
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
 
     println!("Stopped...");
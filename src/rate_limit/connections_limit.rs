@@ -0,0 +1,103 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc, sync::Mutex};
+
+#[derive(Default)]
+struct ConnectionsLimitInner {
+    total: usize,
+    per_ip: HashMap<IpAddr, usize>,
+}
+
+pub struct ConnectionsLimit {
+    pub max_connections: Option<usize>,
+    pub max_connections_per_ip: Option<usize>,
+    inner: Mutex<ConnectionsLimitInner>,
+}
+
+impl ConnectionsLimit {
+    pub fn new(max_connections: Option<usize>, max_connections_per_ip: Option<usize>) -> Self {
+        Self {
+            max_connections,
+            max_connections_per_ip,
+            inner: Mutex::new(ConnectionsLimitInner::default()),
+        }
+    }
+
+    pub fn try_acquire(self: &Arc<Self>, ip: IpAddr) -> Option<ConnectionPermit> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(max_connections) = self.max_connections {
+            if inner.total >= max_connections {
+                return None;
+            }
+        }
+
+        let per_ip = inner.per_ip.get(&ip).copied().unwrap_or(0);
+
+        if let Some(max_connections_per_ip) = self.max_connections_per_ip {
+            if per_ip >= max_connections_per_ip {
+                return None;
+            }
+        }
+
+        inner.total += 1;
+        inner.per_ip.insert(ip, per_ip + 1);
+
+        Some(ConnectionPermit {
+            limit: self.clone(),
+            ip,
+        })
+    }
+
+    pub fn get_connections_amount(&self) -> usize {
+        self.inner.lock().unwrap().total
+    }
+
+    fn release(&self, ip: IpAddr) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.total -= 1;
+
+        if let Some(amount) = inner.per_ip.get_mut(&ip) {
+            *amount -= 1;
+            if *amount == 0 {
+                inner.per_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+pub struct ConnectionPermit {
+    limit: Arc<ConnectionsLimit>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.limit.release(self.ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, str::FromStr, sync::Arc};
+
+    use super::ConnectionsLimit;
+
+    #[test]
+    fn test_limits() {
+        let limit = Arc::new(ConnectionsLimit::new(Some(3), Some(2)));
+
+        let ip1 = IpAddr::from_str("10.0.0.1").unwrap();
+        let ip2 = IpAddr::from_str("10.0.0.2").unwrap();
+
+        let permit1 = limit.try_acquire(ip1).unwrap();
+        let _permit2 = limit.try_acquire(ip1).unwrap();
+        assert!(limit.try_acquire(ip1).is_none());
+
+        let _permit3 = limit.try_acquire(ip2).unwrap();
+        assert!(limit.try_acquire(ip2).is_none());
+
+        drop(permit1);
+        assert_eq!(2, limit.get_connections_amount());
+        assert!(limit.try_acquire(ip1).is_some());
+    }
+}
@@ -0,0 +1,6 @@
+mod rate_limiter;
+pub use rate_limiter::*;
+mod rate_limit_key;
+pub use rate_limit_key::*;
+mod connections_limit;
+pub use connections_limit::*;
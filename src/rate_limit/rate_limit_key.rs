@@ -0,0 +1,44 @@
+const KEY_IP: &str = "ip";
+const KEY_USER: &str = "user";
+const KEY_HEADER_PREFIX: &str = "header:";
+
+#[derive(Debug, Clone)]
+pub enum RateLimitKey {
+    Ip,
+    User,
+    Header(String),
+}
+
+impl RateLimitKey {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let src = src.trim();
+
+        if src == KEY_IP {
+            return Ok(Self::Ip);
+        }
+
+        if src == KEY_USER {
+            return Ok(Self::User);
+        }
+
+        if let Some(header) = src.strip_prefix(KEY_HEADER_PREFIX) {
+            let header = header.trim();
+            if !header.is_empty() {
+                return Ok(Self::Header(header.to_lowercase()));
+            }
+        }
+
+        Err(format!(
+            "Invalid rate_limit key '{}'. Supported values: {}, {}, {}<name>",
+            src, KEY_IP, KEY_USER, KEY_HEADER_PREFIX
+        ))
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            RateLimitKey::Ip => KEY_IP.to_string(),
+            RateLimitKey::User => KEY_USER.to_string(),
+            RateLimitKey::Header(header) => format!("{}{}", KEY_HEADER_PREFIX, header),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration, time::Instant};
+
+use super::RateLimitKey;
+
+const CLEAN_UP_THRESHOLD: usize = 10_000;
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct RateLimiter {
+    pub key: RateLimitKey,
+    requests_per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(key: RateLimitKey, requests: u32, period: Duration, burst: u32) -> Self {
+        Self {
+            key,
+            requests_per_second: requests as f64 / period.as_secs_f64(),
+            burst: burst as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Token is checked and taken under the same lock, so concurrent requests can not overspend the bucket.
+    // Returns Err with the delay after which next request is going to be accepted
+    pub fn try_take(&self, key: &str) -> Result<(), Duration> {
+        self.try_take_at(key, Instant::now())
+    }
+
+    // Gives back the token of the request which is rejected by the other limiter
+    pub fn give_back(&self, key: &str) {
+        self.give_back_at(key, Instant::now())
+    }
+
+    fn try_take_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        self.with_bucket(key, now, |bucket| {
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                return Ok(());
+            }
+
+            let retry_after = (1.0 - bucket.tokens) / self.requests_per_second;
+            Err(Duration::from_secs_f64(retry_after))
+        })
+    }
+
+    fn give_back_at(&self, key: &str, now: Instant) {
+        let burst = self.burst;
+        self.with_bucket(key, now, |bucket| {
+            bucket.tokens = (bucket.tokens + 1.0).min(burst);
+        })
+    }
+
+    fn with_bucket<TResult>(
+        &self,
+        key: &str,
+        now: Instant,
+        callback: impl FnOnce(&mut TokenBucket) -> TResult,
+    ) -> TResult {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() > CLEAN_UP_THRESHOLD {
+            let requests_per_second = self.requests_per_second;
+            let burst = self.burst;
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens + elapsed * requests_per_second < burst
+            });
+        }
+
+        let bucket = match buckets.get_mut(key) {
+            Some(bucket) => bucket,
+            None => {
+                buckets.insert(
+                    key.to_string(),
+                    TokenBucket {
+                        tokens: self.burst,
+                        last_refill: now,
+                    },
+                );
+                buckets.get_mut(key).unwrap()
+            }
+        };
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.last_refill = now;
+
+        callback(bucket)
+    }
+
+    pub fn to_string(&self) -> String {
+        format!(
+            "{:.2} req/s, burst: {}, key: {}",
+            self.requests_per_second,
+            self.burst,
+            self.key.to_string()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_burst_and_refill() {
+        let limiter = RateLimiter::new(RateLimitKey::Ip, 1, Duration::from_secs(1), 2);
+
+        let now = Instant::now();
+
+        assert!(limiter.try_take_at("127.0.0.1", now).is_ok());
+        assert!(limiter.try_take_at("127.0.0.1", now).is_ok());
+
+        let retry_after = limiter.try_take_at("127.0.0.1", now).unwrap_err();
+        assert_eq!(1, retry_after.as_secs());
+
+        assert!(limiter.try_take_at("127.0.0.2", now).is_ok());
+
+        let now = now + Duration::from_secs(1);
+        assert!(limiter.try_take_at("127.0.0.1", now).is_ok());
+        assert!(limiter.try_take_at("127.0.0.1", now).is_err());
+    }
+
+    #[test]
+    fn test_given_back_token_is_taken_again() {
+        let limiter = RateLimiter::new(RateLimitKey::Ip, 1, Duration::from_secs(1), 1);
+
+        let now = Instant::now();
+
+        assert!(limiter.try_take_at("127.0.0.1", now).is_ok());
+        assert!(limiter.try_take_at("127.0.0.1", now).is_err());
+
+        limiter.give_back_at("127.0.0.1", now);
+        assert!(limiter.try_take_at("127.0.0.1", now).is_ok());
+
+        // Bucket is not filled over the burst
+        limiter.give_back_at("127.0.0.2", now);
+        assert!(limiter.try_take_at("127.0.0.2", now).is_ok());
+        assert!(limiter.try_take_at("127.0.0.2", now).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_requests_do_not_overspend() {
+        let limiter = std::sync::Arc::new(RateLimiter::new(
+            RateLimitKey::Ip,
+            1,
+            Duration::from_secs(3600),
+            10,
+        ));
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let limiter = limiter.clone();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .filter(|_| limiter.try_take("127.0.0.1").is_ok())
+                        .count()
+                })
+            })
+            .collect();
+
+        let passed: usize = threads.into_iter().map(|itm| itm.join().unwrap()).sum();
+        assert_eq!(10, passed);
+    }
+}
@@ -2,7 +2,10 @@ use std::collections::HashMap;
 
 use serde::*;
 
-use crate::{files_cache::FilesCache, http_proxy_pass::AllowedUserList};
+use crate::{
+    files_cache::FilesCache,
+    http_proxy_pass::{AllowedUserList, ALLOWED_USER_GROUP_PREFIX, ALLOWED_USER_NEGATIVE_PREFIX},
+};
 
 use crate::configurations::*;
 
@@ -47,14 +50,148 @@ impl AllowedUsersSettings {
         Ok(())
     }
 
-    pub fn get_configuration(&self, name: &str) -> Option<AllowedUserList> {
-        let result = self.data.as_ref()?;
-        let result = result.get(name)?;
-        Some(AllowedUserList::new(result.clone()))
+    pub fn get_configuration(&self, name: &str) -> Result<Option<AllowedUserList>, String> {
+        let data = match self.data.as_ref() {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        if !data.contains_key(name) {
+            return Ok(None);
+        }
+
+        let mut users = Vec::new();
+        let mut path = Vec::new();
+        expand_group(data, name, false, &mut path, &mut users)?;
+
+        let result = AllowedUserList::new(users)
+            .map_err(|err| format!("Invalid allowed_users list '{}'. {}", name, err))?;
+
+        Ok(Some(result))
+    }
+}
+
+fn expand_group(
+    data: &HashMap<String, Vec<String>>,
+    name: &str,
+    negative: bool,
+    path: &mut Vec<String>,
+    result: &mut Vec<String>,
+) -> Result<(), String> {
+    if path.iter().any(|itm| itm == name) {
+        return Err(format!(
+            "Circular reference in allowed_users groups: {} -> {}",
+            path.join(" -> "),
+            name
+        ));
+    }
+
+    let items = match data.get(name) {
+        Some(items) => items,
+        None => {
+            return Err(format!(
+                "allowed_users group '{}' is not found{}",
+                name,
+                match path.last() {
+                    Some(parent) => format!(". Referenced from '{}'", parent),
+                    None => String::new(),
+                }
+            ));
+        }
+    };
+
+    path.push(name.to_string());
+
+    for item in items {
+        let item = item.trim();
+
+        let (item_is_negative, value) = match item.strip_prefix(ALLOWED_USER_NEGATIVE_PREFIX) {
+            Some(value) => (true, value.trim()),
+            None => (false, item),
+        };
+
+        if let Some(group_name) = value.strip_prefix(ALLOWED_USER_GROUP_PREFIX) {
+            if negative && item_is_negative {
+                // Negative entries of a negated group would mean "allow"; they are ignored
+                // so that a negated group can only narrow the list down.
+                continue;
+            }
+
+            expand_group(data, group_name, negative || item_is_negative, path, result)?;
+            continue;
+        }
+
+        if negative {
+            if !item_is_negative {
+                result.push(format!("{}{}", ALLOWED_USER_NEGATIVE_PREFIX, value));
+            }
+        } else {
+            result.push(item.to_string());
+        }
     }
+
+    path.pop();
+
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AllowedUsersRemoteYamlModel {
     allowed_users: Option<HashMap<String, Vec<String>>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::AllowedUsersSettings;
+
+    fn create_settings(src: &[(&str, &[&str])]) -> AllowedUsersSettings {
+        let mut data = HashMap::new();
+        for (name, items) in src {
+            data.insert(
+                name.to_string(),
+                items.iter().map(|itm| itm.to_string()).collect(),
+            );
+        }
+        AllowedUsersSettings::new(Some(data))
+    }
+
+    #[test]
+    fn test_group_expansion() {
+        let settings = create_settings(&[
+            ("admins", &["admin@domain.com"]),
+            ("team", &["*@team.domain.com", "!intern@team.domain.com"]),
+            ("list_id", &["@admins", "@team"]),
+        ]);
+
+        let list = settings.get_configuration("list_id").unwrap().unwrap();
+
+        assert!(list.is_allowed("admin@domain.com"));
+        assert!(list.is_allowed("user@team.domain.com"));
+        assert!(!list.is_allowed("intern@team.domain.com"));
+        assert!(!list.is_allowed("user@domain.com"));
+    }
+
+    #[test]
+    fn test_negative_group() {
+        let settings = create_settings(&[
+            ("banned", &["bad@team.domain.com"]),
+            ("list_id", &["*@team.domain.com", "!@banned"]),
+        ]);
+
+        let list = settings.get_configuration("list_id").unwrap().unwrap();
+
+        assert!(list.is_allowed("good@team.domain.com"));
+        assert!(!list.is_allowed("bad@team.domain.com"));
+    }
+
+    #[test]
+    fn test_missing_and_circular_groups() {
+        let settings = create_settings(&[("a", &["@b"]), ("b", &["@a"]), ("c", &["@unknown"])]);
+
+        assert!(settings.get_configuration("a").is_err());
+        assert!(settings.get_configuration("c").is_err());
+        assert!(settings.get_configuration("d").unwrap().is_none());
+    }
+}
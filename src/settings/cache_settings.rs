@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::{
+    configurations::LocalFilePath,
+    http_cache::{DiskCache, HttpCache, HttpCacheParams},
+};
+
+const DEFAULT_MAX_MEMORY_SIZE: u64 = 64 * 1024 * 1024;
+const DEFAULT_MAX_ENTRY_SIZE: u64 = 1024 * 1024;
+const DEFAULT_MAX_DISK_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheSettings {
+    pub max_memory_size: Option<String>,
+    pub max_entry_size: Option<String>,
+    pub disk_path: Option<String>,
+    pub max_disk_size: Option<String>,
+    pub stale_if_error: Option<String>,
+}
+
+impl CacheSettings {
+    pub fn to_http_cache(&self) -> Result<HttpCache, String> {
+        let max_entry_size = parse_size("max_entry_size", self.max_entry_size.as_ref())?
+            .unwrap_or(DEFAULT_MAX_ENTRY_SIZE);
+
+        let stale_if_error = match self.stale_if_error.as_ref() {
+            Some(stale_if_error) => match parse_duration(stale_if_error) {
+                Ok(result) => result,
+                Err(_) => {
+                    return Err(format!(
+                        "Can not parse cache stale_if_error value: '{}'",
+                        stale_if_error
+                    ))
+                }
+            },
+            None => Duration::ZERO,
+        };
+
+        let disk = match self.disk_path.as_ref() {
+            Some(disk_path) => {
+                let disk_path = LocalFilePath::new(disk_path.to_string());
+                let max_disk_size = parse_size("max_disk_size", self.max_disk_size.as_ref())?
+                    .unwrap_or(DEFAULT_MAX_DISK_SIZE);
+                Some(DiskCache::new(
+                    disk_path.get_value().to_string(),
+                    max_disk_size,
+                )?)
+            }
+            None => None,
+        };
+
+        Ok(HttpCache::new(
+            HttpCacheParams {
+                max_memory_size: parse_size("max_memory_size", self.max_memory_size.as_ref())?
+                    .unwrap_or(DEFAULT_MAX_MEMORY_SIZE),
+                max_entry_size,
+                stale_if_error,
+            },
+            disk,
+        ))
+    }
+}
+
+fn parse_size(name: &str, value: Option<&String>) -> Result<Option<u64>, String> {
+    let value = match value {
+        Some(value) => value.trim(),
+        None => return Ok(None),
+    };
+
+    let (number, multiplier) = if let Some(number) = value.strip_suffix("Kb") {
+        (number, 1024)
+    } else if let Some(number) = value.strip_suffix("Mb") {
+        (number, 1024 * 1024)
+    } else if let Some(number) = value.strip_suffix("Gb") {
+        (number, 1024 * 1024 * 1024)
+    } else {
+        (value, 1)
+    };
+
+    match number.trim().parse::<u64>() {
+        Ok(number) => Ok(Some(number * multiplier)),
+        Err(_) => Err(format!("Can not parse cache {} value: '{}'", name, value)),
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::circuit_breaker::CircuitBreakerParams;
+
+const DEFAULT_CONSECUTIVE_FAILURES: usize = 5;
+const DEFAULT_MIN_REQUESTS: usize = 20;
+const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CircuitBreakerSettings {
+    pub consecutive_failures: Option<usize>,
+    pub error_rate: Option<f64>,
+    pub min_requests: Option<usize>,
+    pub window: Option<String>,
+    pub open_duration: Option<String>,
+    pub half_open_requests: Option<usize>,
+    pub on_status_codes: Option<Vec<u16>>,
+    pub fallback: Option<String>,
+}
+
+impl CircuitBreakerSettings {
+    pub fn to_circuit_breaker_params(&self) -> Result<CircuitBreakerParams, String> {
+        if let Some(error_rate) = self.error_rate {
+            if error_rate <= 0.0 || error_rate > 100.0 {
+                return Err(format!(
+                    "circuit_breaker error_rate must be in range (0..100]. Value: {}",
+                    error_rate
+                ));
+            }
+        }
+
+        if self.consecutive_failures == Some(0) {
+            return Err("circuit_breaker consecutive_failures must be greater than 0".to_string());
+        }
+
+        if self.half_open_requests == Some(0) {
+            return Err("circuit_breaker half_open_requests must be greater than 0".to_string());
+        }
+
+        // Without thresholds circuit would never open
+        let consecutive_failures =
+            if self.consecutive_failures.is_none() && self.error_rate.is_none() {
+                Some(DEFAULT_CONSECUTIVE_FAILURES)
+            } else {
+                self.consecutive_failures
+            };
+
+        Ok(CircuitBreakerParams {
+            consecutive_failures,
+            error_rate: self.error_rate,
+            min_requests: self.min_requests.unwrap_or(DEFAULT_MIN_REQUESTS).max(1),
+            window: parse_circuit_duration("window", self.window.as_ref())?
+                .unwrap_or(DEFAULT_WINDOW),
+            open_duration: parse_circuit_duration("open_duration", self.open_duration.as_ref())?
+                .unwrap_or(DEFAULT_OPEN_DURATION),
+            half_open_requests: self.half_open_requests.unwrap_or(1),
+            failure_status_codes: self
+                .on_status_codes
+                .clone()
+                .unwrap_or_else(|| vec![502, 503, 504]),
+        })
+    }
+}
+
+fn parse_circuit_duration(name: &str, value: Option<&String>) -> Result<Option<Duration>, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match parse_duration(value) {
+        Ok(result) if !result.is_zero() => Ok(Some(result)),
+        _ => Err(format!(
+            "Can not parse circuit_breaker {} value: '{}'",
+            name, value
+        )),
+    }
+}
@@ -0,0 +1,87 @@
+use serde::*;
+
+use crate::http_compression::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompressionSettings {
+    pub algorithms: Option<Vec<String>>,
+    pub content_types: Option<Vec<String>>,
+    pub min_size: Option<String>,
+}
+
+impl CompressionSettings {
+    pub fn get_encodings(&self) -> Result<Vec<ContentEncoding>, String> {
+        let algorithms = match self.algorithms.as_ref() {
+            Some(algorithms) => algorithms,
+            None => return Ok(ContentEncoding::ALL.to_vec()),
+        };
+
+        let mut result = Vec::new();
+
+        for algorithm in algorithms {
+            match ContentEncoding::parse(algorithm) {
+                Some(encoding) => result.push(encoding),
+                None => {
+                    return Err(format!(
+                        "Unknown compression algorithm '{}'. Supported: gzip, br, zstd",
+                        algorithm
+                    ))
+                }
+            }
+        }
+
+        if result.is_empty() {
+            return Err("compression algorithms list can not be empty".to_string());
+        }
+
+        // Server preference is kept regardless of the order in settings
+        Ok(ContentEncoding::ALL
+            .into_iter()
+            .filter(|itm| result.contains(itm))
+            .collect())
+    }
+
+    pub fn get_content_types(&self) -> Vec<String> {
+        match self.content_types.as_ref() {
+            Some(content_types) => content_types
+                .iter()
+                .map(|itm| itm.trim().to_lowercase())
+                .collect(),
+            None => DEFAULT_COMPRESSIBLE_CONTENT_TYPES
+                .iter()
+                .map(|itm| itm.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn get_min_size(&self) -> Result<u64, String> {
+        let min_size = match self.min_size.as_ref() {
+            Some(min_size) => min_size.trim(),
+            None => return Ok(DEFAULT_COMPRESSION_MIN_SIZE),
+        };
+
+        let (value, multiplier) = if let Some(value) = min_size.strip_suffix("Kb") {
+            (value, 1024)
+        } else if let Some(value) = min_size.strip_suffix("Mb") {
+            (value, 1024 * 1024)
+        } else {
+            (min_size, 1)
+        };
+
+        match value.trim().parse::<u64>() {
+            Ok(value) => Ok(value * multiplier),
+            Err(_) => Err(format!(
+                "Can not parse compression min_size value: '{}'",
+                min_size
+            )),
+        }
+    }
+
+    pub fn to_compression_config(&self) -> Result<CompressionConfig, String> {
+        Ok(CompressionConfig {
+            encodings: self.get_encodings()?,
+            content_types: self.get_content_types(),
+            min_size: self.get_min_size()?,
+        })
+    }
+}
@@ -1,9 +1,17 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use rust_extensions::duration_utils::parse_duration;
 use serde::*;
 
 use crate::{
-    app::AppContext, configurations::*, http_proxy_pass::AllowedUserList, types::WhiteListedIpList,
+    app::AppContext,
+    configurations::*,
+    files_cache::FilesCache,
+    http_compression::CompressionConfig,
+    http_content_source::WebContentType,
+    http_proxy_pass::AllowedUserList,
+    rate_limit::{ConnectionsLimit, RateLimiter},
+    types::WhiteListedIpList,
     variables_reader::VariablesReader,
 };
 
@@ -17,6 +25,15 @@ const HTTPS2_ENDPOINT_TYPE: &str = "https2";
 
 const TCP_ENDPOINT_TYPE: &str = "tcp";
 
+const UDP_ENDPOINT_TYPE: &str = "udp";
+const DEFAULT_UDP_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SOCKS5_ENDPOINT_TYPE: &str = "socks5";
+const HTTP_FORWARD_PROXY_ENDPOINT_TYPE: &str = "http-forward-proxy";
+const FORWARD_PROXY_DIRECT_EGRESS: &str = "direct";
+
+const SSH_REVERSE_ENDPOINT_TYPE: &str = "ssh-reverse";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EndpointSettings {
     #[serde(rename = "type")]
@@ -29,6 +46,16 @@ pub struct EndpointSettings {
     pub whitelisted_ip: Option<String>,
     pub template_id: Option<String>,
     pub allowed_users: Option<String>,
+    pub identity_headers: Option<IdentityHeadersSettings>,
+    pub rate_limit: Option<RateLimitSettings>,
+    pub max_connections: Option<usize>,
+    pub max_connections_per_ip: Option<usize>,
+    pub socks5: Option<ForwardProxySettings>,
+    pub http_forward_proxy: Option<ForwardProxySettings>,
+    pub udp_idle_timeout: Option<String>,
+    pub error_pages: Option<HashMap<u16, String>>,
+    pub compression: Option<CompressionSettings>,
+    pub unix_socket_permissions: Option<String>,
 }
 
 impl EndpointSettings {
@@ -36,6 +63,10 @@ impl EndpointSettings {
         self.debug.unwrap_or(false)
     }
 
+    pub fn is_ssh_reverse(&self) -> bool {
+        self.endpoint_type == SSH_REVERSE_ENDPOINT_TYPE
+    }
+
     pub fn get_http_endpoint_modify_headers_settings(
         &self,
         global_settings: &Option<GlobalSettings>,
@@ -118,6 +149,124 @@ impl EndpointSettings {
         ))
     }
 
+    pub fn get_identity_headers(
+        &self,
+        session_key: Option<&str>,
+    ) -> Result<Option<Arc<IdentityHeadersConfig>>, String> {
+        match self.identity_headers.as_ref() {
+            Some(identity_headers) => Ok(Some(Arc::new(identity_headers.to_config(session_key)?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_rate_limiter(&self) -> Result<Option<Arc<RateLimiter>>, String> {
+        match self.rate_limit.as_ref() {
+            Some(rate_limit) => Ok(Some(Arc::new(rate_limit.to_rate_limiter()?))),
+            None => Ok(None),
+        }
+    }
+
+    // Permissions are in octal format, the same way as for chmod: 660
+    pub fn get_unix_socket_permissions(&self) -> Result<Option<u32>, String> {
+        let permissions = match self.unix_socket_permissions.as_ref() {
+            Some(permissions) => permissions.trim(),
+            None => return Ok(None),
+        };
+
+        match u32::from_str_radix(permissions, 8) {
+            Ok(result) if result <= 0o777 => Ok(Some(result)),
+            _ => Err(format!(
+                "Can not parse unix_socket_permissions value: '{}'. Octal value like 660 is expected",
+                permissions
+            )),
+        }
+    }
+
+    pub fn get_compression(&self) -> Result<Option<Arc<CompressionConfig>>, String> {
+        match self.compression.as_ref() {
+            Some(compression) => Ok(Some(Arc::new(compression.to_compression_config()?))),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_error_pages(
+        &self,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+        variables: VariablesReader,
+        files_cache: &FilesCache,
+    ) -> Result<Option<Arc<ErrorPagesConfig>>, String> {
+        let error_pages = match self.error_pages.as_ref() {
+            Some(error_pages) => error_pages,
+            None => return Ok(None),
+        };
+
+        let mut result = ErrorPagesConfig::new();
+
+        for (status_code, src) in error_pages {
+            if *status_code < 400 || *status_code > 599 {
+                return Err(format!(
+                    "Error page can be set for 4xx and 5xx status codes only. Got: {}",
+                    status_code
+                ));
+            }
+
+            let src = crate::populate_variable::populate_variable(src, variables);
+
+            let content_type = match WebContentType::detect_by_extension(src.as_str()) {
+                Some(content_type) => content_type.as_str().to_string(),
+                None => WebContentType::Html.as_str().to_string(),
+            };
+
+            let file_source = FileSource::from_src(src, ssh_configs, variables)?;
+
+            let content = file_source
+                .load_file_content(Some(files_cache))
+                .await
+                .map_err(|err| {
+                    format!(
+                        "Can not load error page {} from {}. Err: {}",
+                        status_code,
+                        file_source.as_str().as_str(),
+                        err
+                    )
+                })?;
+
+            result.add(
+                *status_code,
+                ErrorPage {
+                    content_type,
+                    content: content.into(),
+                },
+            );
+        }
+
+        Ok(Some(Arc::new(result)))
+    }
+
+    pub fn get_udp_idle_timeout(&self) -> Result<Duration, String> {
+        match self.udp_idle_timeout.as_ref() {
+            Some(udp_idle_timeout) => match parse_duration(udp_idle_timeout) {
+                Ok(result) => Ok(result),
+                Err(_) => Err(format!(
+                    "Can not parse udp_idle_timeout value: '{}'",
+                    udp_idle_timeout
+                )),
+            },
+            None => Ok(DEFAULT_UDP_IDLE_TIMEOUT),
+        }
+    }
+
+    pub fn get_connections_limit(&self) -> Option<Arc<ConnectionsLimit>> {
+        if self.max_connections.is_none() && self.max_connections_per_ip.is_none() {
+            return None;
+        }
+
+        Some(Arc::new(ConnectionsLimit::new(
+            self.max_connections,
+            self.max_connections_per_ip,
+        )))
+    }
+
     pub fn get_ssl_id(
         &self,
         endpoint_template: Option<&EndpointTemplateSettings>,
@@ -189,6 +338,9 @@ impl EndpointSettings {
         ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
         g_auth_settings: &Option<HashMap<String, GoogleAuthSettings>>,
         allowed_user_list: Option<Arc<AllowedUserList>>,
+        identity_headers: Option<Arc<IdentityHeadersConfig>>,
+        error_pages: Option<Arc<ErrorPagesConfig>>,
+        upstream_tls: &[Option<Arc<UpstreamTlsConfig>>],
         global_settings: &Option<GlobalSettings>,
         app: &AppContext,
     ) -> Result<EndpointType, String> {
@@ -204,6 +356,7 @@ impl EndpointSettings {
                     endpoint_template_settings,
                     variables,
                     ssh_configs,
+                    upstream_tls,
                     app,
                 )?;
 
@@ -216,10 +369,15 @@ impl EndpointSettings {
                     None,
                     locations,
                     allowed_user_list,
+                    identity_headers,
+                    self.get_rate_limiter()?,
+                    self.get_connections_limit(),
                     self.get_http_endpoint_modify_headers_settings(
                         global_settings,
                         endpoint_template_settings,
                     ),
+                    error_pages.clone(),
+                    self.get_compression()?,
                 )));
             }
             HTTP2_ENDPOINT_TYPE => {
@@ -230,6 +388,7 @@ impl EndpointSettings {
                     endpoint_template_settings,
                     variables,
                     ssh_configs,
+                    upstream_tls,
                     app,
                 )?;
 
@@ -242,10 +401,15 @@ impl EndpointSettings {
                     None,
                     locations,
                     allowed_user_list,
+                    identity_headers,
+                    self.get_rate_limiter()?,
+                    self.get_connections_limit(),
                     self.get_http_endpoint_modify_headers_settings(
                         global_settings,
                         endpoint_template_settings,
                     ),
+                    error_pages.clone(),
+                    self.get_compression()?,
                 )));
             }
             HTTPS1_ENDPOINT_TYPE => {
@@ -256,6 +420,7 @@ impl EndpointSettings {
                     endpoint_template_settings,
                     variables,
                     ssh_configs,
+                    upstream_tls,
                     app,
                 )?;
 
@@ -268,10 +433,15 @@ impl EndpointSettings {
                     self.get_client_certificate_id(endpoint_template_settings),
                     locations,
                     allowed_user_list,
+                    identity_headers,
+                    self.get_rate_limiter()?,
+                    self.get_connections_limit(),
                     self.get_http_endpoint_modify_headers_settings(
                         global_settings,
                         endpoint_template_settings,
                     ),
+                    error_pages.clone(),
+                    self.get_compression()?,
                 )));
             }
 
@@ -283,6 +453,7 @@ impl EndpointSettings {
                     endpoint_template_settings,
                     variables,
                     ssh_configs,
+                    upstream_tls,
                     app,
                 )?;
 
@@ -295,10 +466,15 @@ impl EndpointSettings {
                     self.get_client_certificate_id(endpoint_template_settings),
                     locations,
                     allowed_user_list,
+                    identity_headers,
+                    self.get_rate_limiter()?,
+                    self.get_connections_limit(),
                     self.get_http_endpoint_modify_headers_settings(
                         global_settings,
                         endpoint_template_settings,
                     ),
+                    error_pages.clone(),
+                    self.get_compression()?,
                 )));
             }
 
@@ -339,13 +515,23 @@ impl EndpointSettings {
                                 .to_string(),
                         );
                     }
+                    super::ProxyPassTo::UnixSocket(_) => {
+                        return Err(
+                            "It is not possible to serve unix socket content over tcp endpoint"
+                                .to_string(),
+                        );
+                    }
                     super::ProxyPassTo::Ssh(model) => match model.ssh_config.remote_content {
                         SshContent::RemoteHost(remote_host) => {
                             return Ok(EndpointType::TcpOverSsh(
                                 TcpOverSshEndpointHostConfig {
                                     ssh_credentials: model.ssh_config.credentials.clone(),
+                                    ssh_jump_hosts: model.ssh_config.jump_hosts.clone(),
+                                    ssh_host_keys: model.ssh_config.host_keys.clone(),
+                                    ssh_keep_alive_interval: model.ssh_config.keep_alive_interval,
                                     remote_host: Arc::new(remote_host),
                                     debug: self.get_debug(),
+                                    connections_limit: self.get_connections_limit(),
                                     host,
                                 }
                                 .into(),
@@ -368,6 +554,7 @@ impl EndpointSettings {
                                 remote_addr,
                                 debug: self.get_debug(),
                                 whitelisted_ip,
+                                connections_limit: self.get_connections_limit(),
                                 host,
                             }
                             .into(),
@@ -375,9 +562,185 @@ impl EndpointSettings {
                     }
                 }
             }
+            UDP_ENDPOINT_TYPE => {
+                if locations.len() != 1 {
+                    return Err(format!(
+                        "Udp Host '{}' has {} locations to proxy_pass. Udp Host must have 1 location",
+                        host.as_str(),
+                        locations.len()
+                    ));
+                }
+
+                let proxy_pass_to = crate::populate_variable::populate_variable(
+                    locations[0].proxy_pass_to.trim(),
+                    variables,
+                );
+
+                let remote_addr = match proxy_pass_to.as_str().parse::<std::net::SocketAddr>() {
+                    Ok(remote_addr) => remote_addr,
+                    Err(_) => {
+                        return Err(format!(
+                            "Udp Host '{}' must have proxy_pass_to as ip:port. Got: '{}'",
+                            host.as_str(),
+                            proxy_pass_to.as_str()
+                        ));
+                    }
+                };
+
+                let mut whitelisted_ip = WhiteListedIpList::new();
+
+                whitelisted_ip.apply(
+                    self.get_white_listed_ip(endpoint_template_settings)
+                        .as_deref(),
+                );
+
+                return Ok(EndpointType::Udp(
+                    UdpEndpointHostConfig {
+                        remote_addr,
+                        idle_timeout: self.get_udp_idle_timeout()?,
+                        debug: self.get_debug(),
+                        whitelisted_ip,
+                        connections_limit: self.get_connections_limit(),
+                        host,
+                    }
+                    .into(),
+                ));
+            }
+            SOCKS5_ENDPOINT_TYPE => {
+                return Ok(EndpointType::Socks5(Arc::new(
+                    self.get_forward_proxy_config(
+                        host,
+                        locations,
+                        self.socks5.as_ref(),
+                        endpoint_template_settings,
+                        variables,
+                        ssh_configs,
+                    )?,
+                )));
+            }
+            HTTP_FORWARD_PROXY_ENDPOINT_TYPE => {
+                return Ok(EndpointType::HttpForwardProxy(Arc::new(
+                    self.get_forward_proxy_config(
+                        host,
+                        locations,
+                        self.http_forward_proxy.as_ref(),
+                        endpoint_template_settings,
+                        variables,
+                        ssh_configs,
+                    )?,
+                )));
+            }
             _ => panic!("Unknown location type: '{}'", self.endpoint_type),
         }
     }
+
+    fn get_forward_proxy_config(
+        &self,
+        host: EndpointHttpHostString,
+        locations: &[LocationSettings],
+        forward_proxy_settings: Option<&ForwardProxySettings>,
+        endpoint_template_settings: Option<&EndpointTemplateSettings>,
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<ForwardProxyEndpointHostConfig, String> {
+        if locations.len() > 1 {
+            return Err(format!(
+                "Forward proxy host '{}' has {} locations. Forward proxy host must have 0 or 1 location",
+                host.as_str(),
+                locations.len()
+            ));
+        }
+
+        let ssh = match locations.get(0) {
+            Some(location_settings) => {
+                let proxy_pass_to = crate::populate_variable::populate_variable(
+                    location_settings.proxy_pass_to.trim(),
+                    variables,
+                );
+
+                if proxy_pass_to.as_str() == FORWARD_PROXY_DIRECT_EGRESS {
+                    None
+                } else if proxy_pass_to.as_str().starts_with(SSH_PREFIX) {
+                    Some(SshConfiguration::parse_session(
+                        proxy_pass_to.as_str(),
+                        ssh_configs,
+                        variables,
+                    )?)
+                } else {
+                    return Err(format!(
+                        "Forward proxy host '{}' must have proxy_pass_to '{}' or 'ssh:user@host'",
+                        host.as_str(),
+                        FORWARD_PROXY_DIRECT_EGRESS
+                    ));
+                }
+            }
+            None => None,
+        };
+
+        let (credentials, allowed_destinations) = match forward_proxy_settings {
+            Some(forward_proxy_settings) => (
+                forward_proxy_settings.get_credentials(variables)?,
+                forward_proxy_settings.get_allowed_destinations()?,
+            ),
+            None => (None, crate::types::AllowedDestinations::new(&[])?),
+        };
+
+        let mut whitelisted_ip = WhiteListedIpList::new();
+        whitelisted_ip.apply(
+            self.get_white_listed_ip(endpoint_template_settings)
+                .as_deref(),
+        );
+
+        Ok(ForwardProxyEndpointHostConfig {
+            host,
+            credentials,
+            allowed_destinations,
+            ssh,
+            debug: self.get_debug(),
+            whitelisted_ip,
+            connections_limit: self.get_connections_limit(),
+        })
+    }
+
+    pub fn get_ssh_reverse_config(
+        &self,
+        host: EndpointHttpHostString,
+        locations: &[LocationSettings],
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<SshReverseEndpointConfig, String> {
+        if locations.len() != 1 {
+            return Err(format!(
+                "Ssh reverse host '{}' has {} locations to proxy_pass. Ssh reverse host must have 1 location",
+                host.as_str(),
+                locations.len()
+            ));
+        }
+
+        let location_settings = locations.get(0).unwrap();
+
+        match location_settings.get_proxy_pass(host.as_str(), variables, ssh_configs)? {
+            super::ProxyPassTo::Ssh(model) => match model.ssh_config.remote_content {
+                SshContent::RemoteHost(target) => Ok(SshReverseEndpointConfig {
+                    ssh_credentials: model.ssh_config.credentials.clone(),
+                    ssh_jump_hosts: model.ssh_config.jump_hosts.clone(),
+                    ssh_host_keys: model.ssh_config.host_keys.clone(),
+                    ssh_keep_alive_interval: model.ssh_config.keep_alive_interval,
+                    target: Arc::new(target),
+                    debug: self.get_debug(),
+                    host,
+                }),
+                SshContent::FilePath(_) => Err(format!(
+                    "Ssh reverse host '{}' must forward to host:port, not to the path",
+                    host.as_str()
+                )),
+            },
+            _ => Err(format!(
+                "Ssh reverse host '{}' must have proxy_pass_to in format ssh:user@host->target_host:port",
+                host.as_str()
+            )),
+        }
+    }
 }
 
 fn convert_to_http_locations(
@@ -387,11 +750,12 @@ fn convert_to_http_locations(
     endpoint_template_settings: Option<&EndpointTemplateSettings>,
     variables: VariablesReader,
     ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    upstream_tls: &[Option<Arc<UpstreamTlsConfig>>],
     app: &AppContext,
 ) -> Result<Vec<Arc<ProxyPassLocationConfig>>, String> {
     let mut result = Vec::with_capacity(src.len());
 
-    for location_settings in src {
+    for (index, location_settings) in src.iter().enumerate() {
         let location_path = if let Some(location) = &location_settings.path {
             location.to_string()
         } else {
@@ -406,18 +770,129 @@ fn convert_to_http_locations(
         );
         whitelisted_ip.apply(location_settings.whitelisted_ip.as_deref());
 
-        result.push(
-            ProxyPassLocationConfig::new(
-                app.get_id(),
-                location_path,
-                location_settings.modify_http_headers.clone(),
-                whitelisted_ip,
-                location_settings.get_proxy_pass(host.as_str(), variables, ssh_configs)?,
-                location_settings.domain_name.clone(),
-                location_settings.get_type(),
-            )
-            .into(),
+        let proxy_pass_to =
+            location_settings.get_proxy_pass(host.as_str(), variables, ssh_configs)?;
+        let retry = location_settings.get_retry_policy(host.as_str(), variables, ssh_configs)?;
+        let circuit_breaker = location_settings.get_circuit_breaker_config(
+            host.as_str(),
+            &proxy_pass_to,
+            retry.as_ref(),
+        )?;
+        let cache = location_settings.get_cache(host.as_str(), &proxy_pass_to)?;
+        let mirror =
+            location_settings.get_mirror(host.as_str(), &proxy_pass_to, variables, ssh_configs)?;
+
+        // Each variant has its own upstream connection. Retries, circuit breaker, cache and mirror stay with proxy_pass_to
+        let split = match location_settings.split.as_ref() {
+            Some(split_settings) => {
+                let mut variants = Vec::with_capacity(split_settings.variants.len());
+
+                for variant_settings in &split_settings.variants {
+                    let variant_proxy_pass_to = location_settings.get_split_variant_proxy_pass(
+                        variant_settings,
+                        host.as_str(),
+                        variables,
+                        ssh_configs,
+                    )?;
+
+                    // Rate limits are checked by the location itself
+                    let mut variant_config = ProxyPassLocationConfig::new(
+                        app.get_id(),
+                        location_path.clone(),
+                        location_settings.modify_http_headers.clone(),
+                        whitelisted_ip.clone(),
+                        variant_proxy_pass_to,
+                        location_settings.domain_name.clone(),
+                        location_settings.get_type(),
+                    );
+
+                    variant_config.upstream_tls = upstream_tls.get(index).cloned().flatten();
+                    variant_config.grpc = location_settings.get_grpc_config();
+                    variant_config.web_socket = location_settings.get_web_socket_config()?;
+                    variant_config.streaming = location_settings.get_streaming_config()?;
+                    variant_config.timeouts = location_settings.get_timeouts_config()?;
+
+                    variants
+                        .push(variant_settings.to_traffic_split_variant(Arc::new(variant_config))?);
+                }
+
+                Some(split_settings.to_traffic_split_config(location_path.clone(), variants)?)
+            }
+            None => None,
+        };
+
+        let mut location_config = ProxyPassLocationConfig::new(
+            app.get_id(),
+            location_path,
+            location_settings.modify_http_headers.clone(),
+            whitelisted_ip,
+            proxy_pass_to,
+            location_settings.domain_name.clone(),
+            location_settings.get_type(),
         );
+
+        location_config.rate_limiter = location_settings.get_rate_limiter()?;
+        location_config.upstream_tls = upstream_tls.get(index).cloned().flatten();
+        location_config.grpc = location_settings.get_grpc_config();
+        location_config.web_socket = location_settings.get_web_socket_config()?;
+        location_config.streaming = location_settings.get_streaming_config()?;
+        location_config.timeouts = location_settings.get_timeouts_config()?;
+        location_config.retry = retry;
+        location_config.circuit_breaker = circuit_breaker;
+        location_config.cache = cache;
+        location_config.mirror = mirror;
+        location_config.split = split;
+
+        result.push(Arc::new(location_config));
+    }
+
+    for location in &result {
+        // Circuit of the active upstream is found by the upstream index
+        if let Some(circuit_breaker) = location.circuit_breaker.as_ref() {
+            let upstreams_count = 1 + location
+                .retry
+                .as_ref()
+                .map(|retry| retry.upstreams.len())
+                .unwrap_or(0);
+
+            if circuit_breaker.circuit_breakers.len() != upstreams_count {
+                return Err(format!(
+                    "Location {} for endpoint {} has {} circuit breakers for {} upstreams",
+                    location.path,
+                    host.as_str(),
+                    circuit_breaker.circuit_breakers.len(),
+                    upstreams_count
+                ));
+            }
+        }
+
+        let fallback = location
+            .circuit_breaker
+            .as_ref()
+            .and_then(|circuit_breaker| circuit_breaker.fallback.as_ref());
+
+        if let Some(fallback) = fallback {
+            let fallback_location = result.iter().find(|itm| itm.path == *fallback);
+
+            match fallback_location {
+                Some(fallback_location) => {
+                    if fallback_location.path == location.path {
+                        return Err(format!(
+                            "circuit_breaker fallback of location {} for endpoint {} points to itself",
+                            location.path,
+                            host.as_str()
+                        ));
+                    }
+                }
+                None => {
+                    return Err(format!(
+                        "circuit_breaker fallback location {} is not found for endpoint {}",
+                        fallback,
+                        host.as_str()
+                    ));
+                }
+            }
+        }
     }
 
     Ok(result)
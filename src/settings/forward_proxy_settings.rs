@@ -0,0 +1,36 @@
+use serde::*;
+
+use crate::{
+    populate_variable::populate_variable, types::AllowedDestinations,
+    variables_reader::VariablesReader,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForwardProxySettings {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub allowed_destinations: Option<Vec<String>>,
+}
+
+impl ForwardProxySettings {
+    pub fn get_credentials(
+        &self,
+        variables: VariablesReader,
+    ) -> Result<Option<(String, String)>, String> {
+        match (self.username.as_ref(), self.password.as_ref()) {
+            (Some(username), Some(password)) => Ok(Some((
+                populate_variable(username, variables).to_string(),
+                populate_variable(password, variables).to_string(),
+            ))),
+            (None, None) => Ok(None),
+            _ => Err("username and password must be set both".to_string()),
+        }
+    }
+
+    pub fn get_allowed_destinations(&self) -> Result<AllowedDestinations, String> {
+        match self.allowed_destinations.as_ref() {
+            Some(allowed_destinations) => AllowedDestinations::new(allowed_destinations),
+            None => AllowedDestinations::new(&[]),
+        }
+    }
+}
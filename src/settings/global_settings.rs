@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::*;
 
 use super::{ConnectionsSettings, ModifyHttpHeadersSettings};
@@ -6,6 +8,7 @@ use super::{ConnectionsSettings, ModifyHttpHeadersSettings};
 pub struct GlobalSettings {
     pub connection_settings: Option<ConnectionsSettings>,
     pub all_http_endpoints: Option<AllHttpEndpointsGlobalSettings>,
+    pub mime_types: Option<HashMap<String, String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
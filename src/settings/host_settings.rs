@@ -20,7 +20,7 @@ impl HostSettings {
     ) -> Result<Option<Arc<AllowedUserList>>, String> {
         let mut result = None;
         if let Some(allowed_user_id) = &self.endpoint.allowed_users {
-            if let Some(allowed_users) = allowed_users.get_configuration(allowed_user_id) {
+            if let Some(allowed_users) = allowed_users.get_configuration(allowed_user_id)? {
                 result = Some(Arc::new(allowed_users));
             }
         }
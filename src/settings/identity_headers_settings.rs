@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::configurations::*;
+
+const SIGN_HMAC: &str = "hmac";
+const SIGN_JWT: &str = "jwt";
+
+const DEFAULT_JWT_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdentityHeadersSettings {
+    pub sign: Option<String>,
+    pub jwt_ttl: Option<String>,
+}
+
+impl IdentityHeadersSettings {
+    pub fn get_jwt_ttl(&self) -> Result<Duration, String> {
+        match self.jwt_ttl.as_ref() {
+            Some(jwt_ttl) => match parse_duration(jwt_ttl) {
+                Ok(result) => Ok(result),
+                Err(_) => Err(format!(
+                    "Can not parse identity_headers jwt_ttl value: '{}'",
+                    jwt_ttl
+                )),
+            },
+            None => Ok(DEFAULT_JWT_TTL),
+        }
+    }
+
+    pub fn to_config(&self, session_key: Option<&str>) -> Result<IdentityHeadersConfig, String> {
+        let sign = match self.sign.as_deref() {
+            None => IdentityHeadersSignType::None,
+            Some(SIGN_HMAC) => IdentityHeadersSignType::Hmac,
+            Some(SIGN_JWT) => IdentityHeadersSignType::Jwt {
+                ttl: self.get_jwt_ttl()?,
+            },
+            Some(other) => {
+                return Err(format!(
+                    "Unknown identity_headers sign type: '{}'. Supported types: {}, {}",
+                    other, SIGN_HMAC, SIGN_JWT
+                ));
+            }
+        };
+
+        let key = match &sign {
+            IdentityHeadersSignType::None => vec![],
+            _ => match session_key {
+                Some(session_key) => session_key.as_bytes().to_vec(),
+                None => {
+                    return Err(
+                        "identity_headers are configured to be signed, but global_settings.connection_settings.session_key is not set"
+                            .to_string(),
+                    );
+                }
+            },
+        };
+
+        Ok(IdentityHeadersConfig { sign, key })
+    }
+}
@@ -1,11 +1,16 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use serde::*;
 
-use crate::{configurations::*, variables_reader::VariablesReader};
+use crate::{
+    circuit_breaker::CircuitBreaker, configurations::*, http_cache::HttpCache,
+    rate_limit::RateLimiter, traffic_mirror::TrafficMirror, variables_reader::VariablesReader,
+};
 
 use super::*;
 
+pub const GRPC_LOCATION_TYPE: &str = "grpc";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LocationSettings {
     pub path: Option<String>,
@@ -15,13 +20,237 @@ pub struct LocationSettings {
     pub domain_name: Option<String>,
     pub modify_http_headers: Option<ModifyHttpHeadersSettings>,
     pub default_file: Option<String>,
+    pub autoindex: Option<bool>,
+    pub try_files: Option<Vec<String>>,
+    pub precompressed: Option<bool>,
     pub status_code: Option<u16>,
     pub content_type: Option<String>,
     pub body: Option<String>,
     pub whitelisted_ip: Option<String>,
+    pub rate_limit: Option<RateLimitSettings>,
+    pub upstream_tls: Option<UpstreamTlsSettings>,
+    pub grpc_web: Option<bool>,
+    pub web_socket: Option<WebSocketSettings>,
+    pub streaming: Option<StreamingSettings>,
+    pub timeouts: Option<TimeoutsSettings>,
+    pub retry: Option<RetrySettings>,
+    pub circuit_breaker: Option<CircuitBreakerSettings>,
+    pub cache: Option<CacheSettings>,
+    pub mirror: Option<MirrorSettings>,
+    pub split: Option<TrafficSplitSettings>,
 }
 
 impl LocationSettings {
+    pub fn get_rate_limiter(&self) -> Result<Option<Arc<RateLimiter>>, String> {
+        match self.rate_limit.as_ref() {
+            Some(rate_limit) => Ok(Some(Arc::new(rate_limit.to_rate_limiter()?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_web_socket_config(&self) -> Result<WebSocketLocationConfig, String> {
+        match self.web_socket.as_ref() {
+            Some(web_socket) => web_socket.to_web_socket_config(),
+            None => Ok(WebSocketLocationConfig::default()),
+        }
+    }
+
+    pub fn get_streaming_config(&self) -> Result<Option<StreamingConfig>, String> {
+        match self.streaming.as_ref() {
+            Some(streaming) => Ok(Some(streaming.to_streaming_config()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_timeouts_config(&self) -> Result<LocationTimeouts, String> {
+        match self.timeouts.as_ref() {
+            Some(timeouts) => timeouts.to_location_timeouts(),
+            None => Ok(LocationTimeouts::default()),
+        }
+    }
+
+    pub fn get_retry_policy(
+        &self,
+        endpoint_str: &str,
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<Option<Arc<RetryPolicy>>, String> {
+        let retry = match self.retry.as_ref() {
+            Some(retry) => retry,
+            None => return Ok(None),
+        };
+
+        let mut upstreams = Vec::new();
+
+        if let Some(retry_upstreams) = retry.upstreams.as_ref() {
+            for retry_upstream in retry_upstreams {
+                let proxy_pass_to =
+                    self.parse_proxy_pass(retry_upstream, endpoint_str, variables, ssh_configs)?;
+
+                // Only remote http upstreams have a connection which can be switched
+                match &proxy_pass_to {
+                    ProxyPassTo::Http(_) | ProxyPassTo::Http2(_) | ProxyPassTo::UnixSocket(_) => {}
+                    ProxyPassTo::Ssh(model)
+                        if matches!(model.ssh_config.remote_content, SshContent::RemoteHost(_)) => {
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Retry upstream '{}' for endpoint {} must be a remote http upstream",
+                            retry_upstream, endpoint_str
+                        ))
+                    }
+                }
+
+                upstreams.push(proxy_pass_to);
+            }
+        }
+
+        Ok(Some(Arc::new(retry.to_retry_policy(upstreams)?)))
+    }
+
+    pub fn get_circuit_breaker_config(
+        &self,
+        endpoint_str: &str,
+        proxy_pass_to: &ProxyPassTo,
+        retry: Option<&Arc<RetryPolicy>>,
+    ) -> Result<Option<CircuitBreakerLocationConfig>, String> {
+        let circuit_breaker = match self.circuit_breaker.as_ref() {
+            Some(circuit_breaker) => circuit_breaker,
+            None => return Ok(None),
+        };
+
+        if !proxy_pass_to.is_remote_http() {
+            return Err(format!(
+                "circuit_breaker of location {} for endpoint {} requires remote http proxy_pass_to",
+                self.path.as_deref().unwrap_or("/"),
+                endpoint_str
+            ));
+        }
+
+        let params = Arc::new(circuit_breaker.to_circuit_breaker_params()?);
+
+        let mut circuit_breakers = vec![Arc::new(CircuitBreaker::new(
+            proxy_pass_to.to_string(),
+            params.clone(),
+        ))];
+
+        if let Some(retry) = retry {
+            for upstream in &retry.upstreams {
+                circuit_breakers.push(Arc::new(CircuitBreaker::new(
+                    upstream.to_string(),
+                    params.clone(),
+                )));
+            }
+        }
+
+        Ok(Some(CircuitBreakerLocationConfig {
+            circuit_breakers,
+            fallback: circuit_breaker.fallback.clone(),
+        }))
+    }
+
+    pub fn get_cache(
+        &self,
+        endpoint_str: &str,
+        proxy_pass_to: &ProxyPassTo,
+    ) -> Result<Option<Arc<HttpCache>>, String> {
+        let cache = match self.cache.as_ref() {
+            Some(cache) => cache,
+            None => return Ok(None),
+        };
+
+        if !proxy_pass_to.is_remote_http() || self.get_grpc_config().is_some() {
+            return Err(format!(
+                "cache of location {} for endpoint {} requires remote http proxy_pass_to",
+                self.path.as_deref().unwrap_or("/"),
+                endpoint_str
+            ));
+        }
+
+        Ok(Some(Arc::new(cache.to_http_cache()?)))
+    }
+
+    pub fn get_mirror(
+        &self,
+        endpoint_str: &str,
+        proxy_pass_to: &ProxyPassTo,
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<Option<Arc<TrafficMirror>>, String> {
+        let mirror = match self.mirror.as_ref() {
+            Some(mirror) => mirror,
+            None => return Ok(None),
+        };
+
+        // Mirror gets the copy of the request which is prepared for the remote http upstream of the location
+        if !proxy_pass_to.is_remote_http() {
+            return Err(format!(
+                "mirror of location {} for endpoint {} requires remote http proxy_pass_to",
+                self.path.as_deref().unwrap_or("/"),
+                endpoint_str
+            ));
+        }
+
+        let mirror_proxy_pass_to = self.parse_proxy_pass(
+            mirror.proxy_pass_to.as_str(),
+            endpoint_str,
+            variables,
+            ssh_configs,
+        )?;
+
+        // Request is prepared for the protocol of the location, so mirror is parsed with the same one
+        if !mirror_proxy_pass_to.is_remote_http() {
+            return Err(format!(
+                "Mirror '{}' of location {} for endpoint {} must be a remote http upstream",
+                mirror.proxy_pass_to,
+                self.path.as_deref().unwrap_or("/"),
+                endpoint_str
+            ));
+        }
+
+        Ok(Some(Arc::new(TrafficMirror::new(
+            mirror_proxy_pass_to,
+            mirror.to_traffic_mirror_params()?,
+        ))))
+    }
+
+    // Variant is parsed the same way as proxy_pass_to, so it has the same http protocol
+    pub fn get_split_variant_proxy_pass(
+        &self,
+        variant: &TrafficSplitVariantSettings,
+        endpoint_str: &str,
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<ProxyPassTo, String> {
+        let proxy_pass_to = self.parse_proxy_pass(
+            variant.proxy_pass_to.as_str(),
+            endpoint_str,
+            variables,
+            ssh_configs,
+        )?;
+
+        if !proxy_pass_to.is_remote_http() {
+            return Err(format!(
+                "Split variant '{}' of location {} for endpoint {} must be a remote http upstream",
+                variant.name,
+                self.path.as_deref().unwrap_or("/"),
+                endpoint_str
+            ));
+        }
+
+        Ok(proxy_pass_to)
+    }
+
+    pub fn get_grpc_config(&self) -> Option<GrpcLocationConfig> {
+        if self.location_type.as_deref() != Some(GRPC_LOCATION_TYPE) {
+            return None;
+        }
+
+        Some(GrpcLocationConfig {
+            grpc_web: self.grpc_web.unwrap_or(false),
+        })
+    }
+
     fn get_status_code(&self, endpoint_str: &str) -> Result<u16, String> {
         match self.status_code {
             Some(status_code) => Ok(status_code),
@@ -37,9 +266,24 @@ impl LocationSettings {
         endpoint_str: &str,
         variables: VariablesReader,
         ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
+    ) -> Result<ProxyPassTo, String> {
+        self.parse_proxy_pass(
+            self.proxy_pass_to.as_str(),
+            endpoint_str,
+            variables,
+            ssh_configs,
+        )
+    }
+
+    fn parse_proxy_pass(
+        &self,
+        proxy_pass_to: &str,
+        endpoint_str: &str,
+        variables: VariablesReader,
+        ssh_configs: &Option<HashMap<String, SshConfigSettings>>,
     ) -> Result<ProxyPassTo, String> {
         let proxy_pass_to =
-            crate::populate_variable::populate_variable(self.proxy_pass_to.trim(), variables);
+            crate::populate_variable::populate_variable(proxy_pass_to.trim(), variables);
 
         if proxy_pass_to.as_str().trim() == "static" {
             return Ok(ProxyPassTo::Static(StaticContentModel {
@@ -65,6 +309,20 @@ impl LocationSettings {
             }));
         }
 
+        if let Some(unix_socket_path) = proxy_pass_to.as_str().strip_prefix(UNIX_SOCKET_PREFIX) {
+            if unix_socket_path.is_empty() {
+                return Err(format!(
+                    "Unix socket path is empty in proxy_pass_to for endpoint {}",
+                    endpoint_str
+                ));
+            }
+
+            return Ok(ProxyPassTo::UnixSocket(UnixSocketModel {
+                unix_socket_path: unix_socket_path.to_string(),
+                http2: self.get_type().is_protocol_http2(),
+            }));
+        }
+
         if proxy_pass_to.as_str().starts_with("http") {
             if self.get_type().is_protocol_http2() {
                 return Ok(ProxyPassTo::Http2(RemoteHost::new(
@@ -84,12 +342,20 @@ impl LocationSettings {
             return Ok(ProxyPassTo::LocalPath(LocalPathModel {
                 local_path: LocalFilePath::new(proxy_pass_to.to_string()),
                 default_file: self.default_file.clone(),
+                autoindex: self.autoindex.unwrap_or(false),
+                try_files: self.try_files.clone(),
+                precompressed: self.precompressed.unwrap_or(false),
             }));
         }
 
-        Ok(ProxyPassTo::Tcp(
-            std::net::SocketAddr::from_str(proxy_pass_to.as_str()).unwrap(),
-        ))
+        match std::net::SocketAddr::from_str(proxy_pass_to.as_str()) {
+            Ok(socket_addr) => Ok(ProxyPassTo::Tcp(socket_addr)),
+            Err(_) => Err(format!(
+                "Can not parse proxy_pass_to '{}' for endpoint {}",
+                proxy_pass_to.as_str(),
+                endpoint_str
+            )),
+        }
     }
 
     pub fn get_type(&self) -> HttpType {
@@ -99,6 +365,8 @@ impl LocationSettings {
                 "http2" => HttpType::Http2,
                 "https1" => HttpType::Https1,
                 "https2" => HttpType::Https2,
+                // Grpc is always proxied over http2
+                GRPC_LOCATION_TYPE => HttpType::Http2,
                 _ => HttpType::Http1,
             },
             None => HttpType::Http1,
@@ -119,3 +387,54 @@ impl LocationSettings {
     }
      */
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LocationSettings;
+
+    fn get_location_settings(proxy_pass_to: &str) -> LocationSettings {
+        let yaml = format!(
+            "path: /api\nproxy_pass_to: {}\nstatus_code: 200\nmirror:\n  proxy_pass_to: http://10.0.0.5:5123\n",
+            proxy_pass_to
+        );
+
+        serde_yaml::from_str(yaml.as_str()).unwrap()
+    }
+
+    #[test]
+    fn test_mirror_requires_remote_http_location() {
+        for proxy_pass_to in ["static", "~/web", "ssh:root@10.0.0.1:22->/home/user/web"] {
+            let location = get_location_settings(proxy_pass_to);
+
+            let location_proxy_pass_to = location
+                .get_proxy_pass("localhost:8000", (&None).into(), &None)
+                .unwrap();
+
+            let result = location.get_mirror(
+                "localhost:8000",
+                &location_proxy_pass_to,
+                (&None).into(),
+                &None,
+            );
+
+            assert!(result.is_err(), "{}", proxy_pass_to);
+        }
+
+        let location = get_location_settings("http://10.0.0.4:5123");
+
+        let location_proxy_pass_to = location
+            .get_proxy_pass("localhost:8000", (&None).into(), &None)
+            .unwrap();
+
+        let mirror = location
+            .get_mirror(
+                "localhost:8000",
+                &location_proxy_pass_to,
+                (&None).into(),
+                &None,
+            )
+            .unwrap();
+
+        assert!(mirror.is_some());
+    }
+}
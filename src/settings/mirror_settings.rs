@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::traffic_mirror::TrafficMirrorParams;
+
+const DEFAULT_PERCENT: f64 = 100.0;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_IN_FLIGHT: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MirrorSettings {
+    pub proxy_pass_to: String,
+    pub percent: Option<f64>,
+    pub timeout: Option<String>,
+    pub max_in_flight: Option<usize>,
+}
+
+impl MirrorSettings {
+    pub fn to_traffic_mirror_params(&self) -> Result<TrafficMirrorParams, String> {
+        let percent = self.percent.unwrap_or(DEFAULT_PERCENT);
+
+        if percent <= 0.0 || percent > 100.0 {
+            return Err(format!(
+                "mirror percent must be in range (0..100]. Value: {}",
+                percent
+            ));
+        }
+
+        let timeout = match self.timeout.as_ref() {
+            Some(timeout) => match parse_duration(timeout) {
+                Ok(result) => result,
+                Err(_) => return Err(format!("Can not parse mirror timeout value: '{}'", timeout)),
+            },
+            None => DEFAULT_TIMEOUT,
+        };
+
+        let max_in_flight = self.max_in_flight.unwrap_or(DEFAULT_MAX_IN_FLIGHT);
+
+        if max_in_flight == 0 {
+            return Err("mirror max_in_flight must be greater than 0".to_string());
+        }
+
+        Ok(TrafficMirrorParams {
+            percent,
+            timeout,
+            max_in_flight,
+        })
+    }
+}
@@ -32,3 +32,29 @@ pub use endpoint_template_settings::*;
 
 mod allowed_users_settings;
 pub use allowed_users_settings::*;
+mod identity_headers_settings;
+pub use identity_headers_settings::*;
+mod rate_limit_settings;
+pub use rate_limit_settings::*;
+mod forward_proxy_settings;
+pub use forward_proxy_settings::*;
+mod compression_settings;
+pub use compression_settings::*;
+mod upstream_tls_settings;
+pub use upstream_tls_settings::*;
+mod web_socket_settings;
+pub use web_socket_settings::*;
+mod streaming_settings;
+pub use streaming_settings::*;
+mod timeouts_settings;
+pub use timeouts_settings::*;
+mod retry_settings;
+pub use retry_settings::*;
+mod circuit_breaker_settings;
+pub use circuit_breaker_settings::*;
+mod cache_settings;
+pub use cache_settings::*;
+mod mirror_settings;
+pub use mirror_settings::*;
+mod traffic_split_settings;
+pub use traffic_split_settings::*;
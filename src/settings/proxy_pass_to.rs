@@ -20,6 +20,9 @@ impl StaticContentModel {
 pub struct LocalPathModel {
     pub local_path: LocalFilePath,
     pub default_file: Option<String>,
+    pub autoindex: bool,
+    pub try_files: Option<Vec<String>>,
+    pub precompressed: bool,
 }
 
 impl LocalPathModel {
@@ -28,6 +31,17 @@ impl LocalPathModel {
     }
 }
 
+pub struct UnixSocketModel {
+    pub unix_socket_path: String,
+    pub http2: bool,
+}
+
+impl UnixSocketModel {
+    pub fn to_string(&self) -> String {
+        format!("{}{}", UNIX_SOCKET_PREFIX, self.unix_socket_path)
+    }
+}
+
 pub struct SshProxyPassModel {
     pub ssh_config: SshConfiguration,
     pub http2: bool,
@@ -45,6 +59,7 @@ pub enum ProxyPassTo {
     Http2(RemoteHost),
     LocalPath(LocalPathModel),
     Ssh(SshProxyPassModel),
+    UnixSocket(UnixSocketModel),
     Tcp(std::net::SocketAddr),
     Static(StaticContentModel),
 }
@@ -56,10 +71,22 @@ impl ProxyPassTo {
             ProxyPassTo::Http2(remote_host) => remote_host.to_string(),
             ProxyPassTo::LocalPath(model) => model.to_string(),
             ProxyPassTo::Ssh(model) => model.to_string(),
+            ProxyPassTo::UnixSocket(model) => model.to_string(),
             ProxyPassTo::Tcp(socket_addr) => format!("{}", socket_addr),
             ProxyPassTo::Static(model) => model.to_string(),
         }
     }
+
+    // Upstreams which are reached with http client connection
+    pub fn is_remote_http(&self) -> bool {
+        match self {
+            ProxyPassTo::Http(_) | ProxyPassTo::Http2(_) | ProxyPassTo::UnixSocket(_) => true,
+            ProxyPassTo::Ssh(model) => {
+                matches!(model.ssh_config.remote_content, SshContent::RemoteHost(_))
+            }
+            _ => false,
+        }
+    }
 }
 
 /*
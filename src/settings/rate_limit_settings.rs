@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::rate_limit::{RateLimitKey, RateLimiter};
+
+const DEFAULT_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateLimitSettings {
+    pub requests: u32,
+    pub period: Option<String>,
+    pub burst: Option<u32>,
+    pub key: Option<String>,
+}
+
+impl RateLimitSettings {
+    pub fn get_period(&self) -> Result<Duration, String> {
+        match self.period.as_ref() {
+            Some(period) => match parse_duration(period) {
+                Ok(result) if !result.is_zero() => Ok(result),
+                _ => Err(format!(
+                    "Can not parse rate_limit period value: '{}'",
+                    period
+                )),
+            },
+            None => Ok(DEFAULT_PERIOD),
+        }
+    }
+
+    pub fn get_key(&self) -> Result<RateLimitKey, String> {
+        match self.key.as_ref() {
+            Some(key) => RateLimitKey::parse(key),
+            None => Ok(RateLimitKey::Ip),
+        }
+    }
+
+    pub fn to_rate_limiter(&self) -> Result<RateLimiter, String> {
+        if self.requests == 0 {
+            return Err("rate_limit requests must be greater than 0".to_string());
+        }
+
+        let burst = self.burst.unwrap_or(self.requests);
+
+        // Bucket of 0 tokens never lets the request through
+        if burst == 0 {
+            return Err("rate_limit burst must be greater than 0".to_string());
+        }
+
+        Ok(RateLimiter::new(
+            self.get_key()?,
+            self.requests,
+            self.get_period()?,
+            burst,
+        ))
+    }
+}
@@ -0,0 +1,96 @@
+use std::{str::FromStr, time::Duration};
+
+use hyper::Method;
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::configurations::{RetryOnError, RetryPolicy};
+
+use super::ProxyPassTo;
+
+const DEFAULT_MAX_ATTEMPTS: usize = 2;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(50);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetrySettings {
+    pub max_attempts: Option<usize>,
+    pub methods: Option<Vec<String>>,
+    pub on_status_codes: Option<Vec<u16>>,
+    pub on_errors: Option<Vec<String>>,
+    pub backoff: Option<String>,
+    pub max_backoff: Option<String>,
+    pub upstreams: Option<Vec<String>>,
+}
+
+impl RetrySettings {
+    pub fn to_retry_policy(&self, upstreams: Vec<ProxyPassTo>) -> Result<RetryPolicy, String> {
+        let max_attempts = self.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+
+        if max_attempts == 0 {
+            return Err("retry max_attempts must be greater than 0".to_string());
+        }
+
+        let methods = match self.methods.as_ref() {
+            Some(methods) => {
+                let mut result = Vec::with_capacity(methods.len());
+                for method in methods {
+                    match Method::from_str(method.trim().to_uppercase().as_str()) {
+                        Ok(method) => result.push(method),
+                        Err(_) => return Err(format!("Invalid retry method: '{}'", method)),
+                    }
+                }
+                result
+            }
+            None => RetryPolicy::get_default_methods(),
+        };
+
+        let errors = match self.on_errors.as_ref() {
+            Some(on_errors) => {
+                let mut result = Vec::with_capacity(on_errors.len());
+                for on_error in on_errors {
+                    match RetryOnError::parse(on_error.trim()) {
+                        Some(value) => result.push(value),
+                        None => {
+                            return Err(format!(
+                                "Invalid retry on_errors value: '{}'. Supported values: connect, timeout, reset",
+                                on_error
+                            ))
+                        }
+                    }
+                }
+                result
+            }
+            None => vec![RetryOnError::Connect, RetryOnError::Reset],
+        };
+
+        let backoff = parse_backoff("backoff", self.backoff.as_ref())?.unwrap_or(DEFAULT_BACKOFF);
+        let max_backoff =
+            parse_backoff("max_backoff", self.max_backoff.as_ref())?.unwrap_or(DEFAULT_MAX_BACKOFF);
+
+        Ok(RetryPolicy {
+            max_attempts,
+            methods,
+            status_codes: self
+                .on_status_codes
+                .clone()
+                .unwrap_or_else(|| vec![502, 503, 504]),
+            errors,
+            backoff,
+            max_backoff: max_backoff.max(backoff),
+            upstreams,
+        })
+    }
+}
+
+fn parse_backoff(name: &str, value: Option<&String>) -> Result<Option<Duration>, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match parse_duration(value) {
+        Ok(result) => Ok(Some(result)),
+        Err(_) => Err(format!("Can not parse retry {} value: '{}'", name, value)),
+    }
+}
@@ -1,4 +1,7 @@
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use crate::{app::AppContext, configurations::*, files_cache::FilesCache};
 
@@ -58,6 +61,38 @@ impl SettingsModel {
         None
     }
 
+    pub fn get_mime_types(&self) -> Result<HashMap<String, String>, String> {
+        let mut result = HashMap::new();
+
+        let mime_types = match self.global_settings.as_ref() {
+            Some(global_settings) => global_settings.mime_types.as_ref(),
+            None => None,
+        };
+
+        if let Some(mime_types) = mime_types {
+            for (extension, content_type) in mime_types {
+                let extension = crate::http_content_source::normalize_extension(extension);
+
+                if extension.is_empty() {
+                    return Err("global_settings.mime_types has empty extension".to_string());
+                }
+
+                if hyper::header::HeaderValue::from_str(content_type).is_err()
+                    || !content_type.contains('/')
+                {
+                    return Err(format!(
+                        "global_settings.mime_types has invalid content type '{}' for extension '{}'",
+                        content_type, extension
+                    ));
+                }
+
+                result.insert(extension, content_type.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+
     async fn get_allowed_users_settings(
         &self,
         files_cache: &FilesCache,
@@ -70,7 +105,7 @@ impl SettingsModel {
             files_to_load = allowed_users.remove("from_file");
         }
 
-        let mut result = AllowedUsersSettings::new(self.allowed_users.clone());
+        let mut result = AllowedUsersSettings::new(allowed_users);
 
         if let Some(files_to_load) = files_to_load {
             let variables = (&self.variables).into();
@@ -94,33 +129,25 @@ impl SettingsModel {
         let mut result: BTreeMap<u16, ListenPortConfiguration> = BTreeMap::new();
 
         for (host, proxy_pass) in &self.hosts {
+            // Ssh reverse endpoints are listening on the remote ssh server - not on the local port
+            if proxy_pass.endpoint.is_ssh_reverse() {
+                continue;
+            }
+
             let host = crate::populate_variable::populate_variable(host, (&self.variables).into());
 
             let end_point = EndpointHttpHostString::new(host.as_str().to_string())?;
 
-            let port = end_point.get_port();
-
-            let endpoint_template_settings = proxy_pass
-                .endpoint
-                .get_endpoint_template(&self.endpoint_templates)?;
-
-            let allowed_users_settings = self.get_allowed_users_settings(&files_cache).await?;
+            // Unix socket endpoints are not bound to the port
+            if end_point.get_unix_socket_path().is_some() {
+                continue;
+            }
 
-            let allowed_users = proxy_pass
-                .get_allowed_users(&allowed_users_settings, endpoint_template_settings)?;
+            let port = end_point.get_port();
 
-            let endpoint_type = proxy_pass.endpoint.get_type(
-                end_point,
-                &proxy_pass.endpoint,
-                proxy_pass.locations.as_slice(),
-                endpoint_template_settings,
-                (&self.variables).into(),
-                &self.ssh,
-                &self.g_auth,
-                allowed_users,
-                &self.global_settings,
-                app,
-            )?;
+            let endpoint_type = self
+                .get_endpoint_type(end_point, proxy_pass, &files_cache, app)
+                .await?;
 
             match endpoint_type {
                 EndpointType::Http(http_endpoint_info) => match result.get_mut(&port) {
@@ -163,12 +190,177 @@ impl SettingsModel {
                         result.insert(port, ListenPortConfiguration::TcpOverSsh(endpoint_info));
                     }
                 },
+                EndpointType::Socks5(endpoint_info) => match result.get(&port) {
+                    Some(other_end_point_type) => {
+                        return Err(format!(
+                            "Port {} is used twice by host configurations {} and {}",
+                            port,
+                            host.as_str(),
+                            other_end_point_type.get_endpoint_host_as_str()
+                        ));
+                    }
+                    None => {
+                        result.insert(port, ListenPortConfiguration::Socks5(endpoint_info));
+                    }
+                },
+                EndpointType::HttpForwardProxy(endpoint_info) => match result.get(&port) {
+                    Some(other_end_point_type) => {
+                        return Err(format!(
+                            "Port {} is used twice by host configurations {} and {}",
+                            port,
+                            host.as_str(),
+                            other_end_point_type.get_endpoint_host_as_str()
+                        ));
+                    }
+                    None => {
+                        result.insert(
+                            port,
+                            ListenPortConfiguration::HttpForwardProxy(endpoint_info),
+                        );
+                    }
+                },
+                EndpointType::Udp(endpoint_info) => match result.get(&port) {
+                    Some(other_end_point_type) => {
+                        return Err(format!(
+                            "Port {} is used twice by host configurations {} and {}",
+                            port,
+                            host.as_str(),
+                            other_end_point_type.get_endpoint_host_as_str()
+                        ));
+                    }
+                    None => {
+                        result.insert(port, ListenPortConfiguration::Udp(endpoint_info));
+                    }
+                },
             }
         }
 
         Ok(result)
     }
 
+    pub async fn get_unix_socket_http_endpoints(
+        &self,
+        app: &AppContext,
+    ) -> Result<BTreeMap<String, Arc<UnixSocketHttpEndpointConfig>>, String> {
+        let files_cache = FilesCache::new();
+        let mut result = BTreeMap::new();
+
+        for (host, proxy_pass) in &self.hosts {
+            if proxy_pass.endpoint.is_ssh_reverse() {
+                continue;
+            }
+
+            let host = crate::populate_variable::populate_variable(host, (&self.variables).into());
+
+            let end_point = EndpointHttpHostString::new(host.as_str().to_string())?;
+
+            let unix_socket_path = match end_point.get_unix_socket_path() {
+                Some(unix_socket_path) => unix_socket_path.to_string(),
+                None => continue,
+            };
+
+            let permissions = proxy_pass.endpoint.get_unix_socket_permissions()?;
+
+            let endpoint_type = self
+                .get_endpoint_type(end_point, proxy_pass, &files_cache, app)
+                .await?;
+
+            match endpoint_type {
+                EndpointType::Http(endpoint_info) if !endpoint_info.http_type.is_https() => {
+                    result.insert(
+                        unix_socket_path.clone(),
+                        Arc::new(UnixSocketHttpEndpointConfig {
+                            unix_socket_path,
+                            permissions,
+                            endpoint_info: Arc::new(endpoint_info),
+                        }),
+                    );
+                }
+                _ => {
+                    return Err(format!(
+                        "Unix socket host '{}' supports only http and http2 endpoint types",
+                        host.as_str()
+                    ));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_endpoint_type(
+        &self,
+        end_point: EndpointHttpHostString,
+        proxy_pass: &HostSettings,
+        files_cache: &FilesCache,
+        app: &AppContext,
+    ) -> Result<EndpointType, String> {
+        let endpoint_template_settings = proxy_pass
+            .endpoint
+            .get_endpoint_template(&self.endpoint_templates)?;
+
+        let allowed_users_settings = self.get_allowed_users_settings(files_cache).await?;
+
+        let allowed_users =
+            proxy_pass.get_allowed_users(&allowed_users_settings, endpoint_template_settings)?;
+
+        let identity_headers = proxy_pass
+            .endpoint
+            .get_identity_headers(self.get_session_key().as_deref())?;
+
+        let error_pages = proxy_pass
+            .endpoint
+            .get_error_pages(&self.ssh, (&self.variables).into(), files_cache)
+            .await?;
+
+        let mut upstream_tls = Vec::with_capacity(proxy_pass.locations.len());
+
+        for location in proxy_pass.locations.iter() {
+            upstream_tls.push(self.get_upstream_tls(location, files_cache).await?);
+        }
+
+        proxy_pass.endpoint.get_type(
+            end_point,
+            &proxy_pass.endpoint,
+            proxy_pass.locations.as_slice(),
+            endpoint_template_settings,
+            (&self.variables).into(),
+            &self.ssh,
+            &self.g_auth,
+            allowed_users,
+            identity_headers,
+            error_pages,
+            upstream_tls.as_slice(),
+            &self.global_settings,
+            app,
+        )
+    }
+
+    pub fn get_ssh_reverse_endpoints(&self) -> Result<Vec<Arc<SshReverseEndpointConfig>>, String> {
+        let mut result = Vec::new();
+
+        for (host, proxy_pass) in &self.hosts {
+            if !proxy_pass.endpoint.is_ssh_reverse() {
+                continue;
+            }
+
+            let host = crate::populate_variable::populate_variable(host, (&self.variables).into());
+
+            let end_point = EndpointHttpHostString::new(host.as_str().to_string())?;
+
+            let config = proxy_pass.endpoint.get_ssh_reverse_config(
+                end_point,
+                proxy_pass.locations.as_slice(),
+                (&self.variables).into(),
+                &self.ssh,
+            )?;
+
+            result.push(Arc::new(config));
+        }
+
+        Ok(result)
+    }
+
     pub fn get_client_certificate_ca(&self, id: &str) -> Result<Option<FileSource>, String> {
         if let Some(certs) = &self.client_certificate_ca {
             for ca in certs {
@@ -203,6 +395,68 @@ impl SettingsModel {
         Ok(None)
     }
 
+    pub async fn get_upstream_tls(
+        &self,
+        location: &LocationSettings,
+        files_cache: &FilesCache,
+    ) -> Result<Option<Arc<UpstreamTlsConfig>>, String> {
+        let upstream_tls = match location.upstream_tls.as_ref() {
+            Some(upstream_tls) => upstream_tls,
+            None => return Ok(None),
+        };
+
+        let ca = match upstream_tls.ca.as_ref() {
+            Some(ca) => {
+                let ca = crate::populate_variable::populate_variable(ca, (&self.variables).into());
+                let file_source = FileSource::from_src(ca, &self.ssh, (&self.variables).into())?;
+                Some(file_source.load_file_content(Some(files_cache)).await?)
+            }
+            None => None,
+        };
+
+        let client_certificate = match upstream_tls.client_certificate.as_ref() {
+            Some(ssl_id) => {
+                let ssl_id = SslCertificateId::new(ssl_id.to_string());
+                let (cert, key) = match self.get_ssl_certificate(&ssl_id)? {
+                    Some(result) => result,
+                    None => {
+                        return Err(format!(
+                            "SSL certificate {} not found for upstream client certificate of location {}",
+                            ssl_id.as_str(),
+                            location.proxy_pass_to
+                        ))
+                    }
+                };
+
+                Some(UpstreamTlsClientCertificate {
+                    certificates: cert.load_file_content(Some(files_cache)).await?,
+                    private_key: key.load_file_content(Some(files_cache)).await?,
+                    private_key_file_name: key.as_str().as_str().to_string(),
+                })
+            }
+            None => None,
+        };
+
+        let insecure_skip_verify = upstream_tls.get_insecure_skip_verify();
+
+        if insecure_skip_verify {
+            println!(
+                "WARNING: upstream certificate verification is disabled for {}",
+                location.proxy_pass_to
+            );
+        }
+
+        let result = UpstreamTlsConfig::new(
+            ca,
+            client_certificate,
+            upstream_tls.sni.clone(),
+            insecure_skip_verify,
+            location.get_type().is_protocol_http2(),
+        )?;
+
+        Ok(Some(Arc::new(result)))
+    }
+
     pub fn get_crl(&self) -> Result<HashMap<String, FileSource>, String> {
         let mut result = HashMap::new();
 
@@ -271,6 +525,16 @@ mod tests {
                     whitelisted_ip: None,
                     template_id: None,
                     allowed_users: None,
+                    identity_headers: None,
+                    rate_limit: None,
+                    max_connections: None,
+                    max_connections_per_ip: None,
+                    socks5: None,
+                    http_forward_proxy: None,
+                    udp_idle_timeout: None,
+                    error_pages: None,
+                    compression: None,
+                    unix_socket_permissions: None,
                 },
                 locations: vec![LocationSettings {
                     path: Some("/".to_owned()),
@@ -278,11 +542,25 @@ mod tests {
                     location_type: Some("http".to_owned()),
                     modify_http_headers: None,
                     default_file: None,
+                    autoindex: None,
+                    try_files: None,
+                    precompressed: None,
                     status_code: None,
                     body: None,
                     content_type: None,
                     whitelisted_ip: None,
                     domain_name: None,
+                    rate_limit: None,
+                    upstream_tls: None,
+                    grpc_web: None,
+                    web_socket: None,
+                    streaming: None,
+                    timeouts: None,
+                    retry: None,
+                    circuit_breaker: None,
+                    cache: None,
+                    mirror: None,
+                    split: None,
                 }],
             },
         );
@@ -295,6 +573,11 @@ mod tests {
                 password: "my_password".to_string().into(),
                 private_key_file: None,
                 passphrase: None,
+                keep_alive_interval: None,
+                jump_host: None,
+                host_key_policy: Some("strict".to_string()),
+                known_hosts_file: Some("~/.ssh/known_hosts".to_string()),
+                host_key_fingerprint: None,
             },
         );
 
@@ -304,6 +587,13 @@ mod tests {
                 password: None,
                 private_key_file: Some("~/certs/private_key.ssh".to_string()),
                 passphrase: Some("my_pass_phrase".to_string()),
+                keep_alive_interval: Some("30s".to_string()),
+                jump_host: Some("root@10.0.0.1".to_string()),
+                host_key_policy: None,
+                known_hosts_file: None,
+                host_key_fingerprint: Some(
+                    "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU".to_string(),
+                ),
             },
         );
 
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::configurations::StreamingConfig;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StreamingSettings {
+    pub header_timeout: Option<String>,
+    pub idle_body_timeout: Option<String>,
+}
+
+impl StreamingSettings {
+    pub fn to_streaming_config(&self) -> Result<StreamingConfig, String> {
+        Ok(StreamingConfig {
+            header_timeout: parse_timeout("header_timeout", self.header_timeout.as_ref())?,
+            idle_body_timeout: parse_timeout("idle_body_timeout", self.idle_body_timeout.as_ref())?,
+        })
+    }
+}
+
+fn parse_timeout(name: &str, value: Option<&String>) -> Result<Option<Duration>, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match parse_duration(value) {
+        Ok(result) if !result.is_zero() => Ok(Some(result)),
+        _ => Err(format!(
+            "Can not parse streaming {} value: '{}'",
+            name, value
+        )),
+    }
+}
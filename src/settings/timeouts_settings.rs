@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::configurations::LocationTimeouts;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimeoutsSettings {
+    pub connect: Option<String>,
+    pub read: Option<String>,
+    pub total: Option<String>,
+}
+
+impl TimeoutsSettings {
+    pub fn to_location_timeouts(&self) -> Result<LocationTimeouts, String> {
+        Ok(LocationTimeouts {
+            connect: parse_timeout("connect", self.connect.as_ref())?,
+            read: parse_timeout("read", self.read.as_ref())?,
+            total: parse_timeout("total", self.total.as_ref())?,
+        })
+    }
+}
+
+fn parse_timeout(name: &str, value: Option<&String>) -> Result<Option<Duration>, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match parse_duration(value) {
+        Ok(result) if !result.is_zero() => Ok(Some(result)),
+        _ => Err(format!("Can not parse {} timeout value: '{}'", name, value)),
+    }
+}
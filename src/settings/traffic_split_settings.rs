@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use serde::*;
+
+use crate::configurations::{
+    ProxyPassLocationConfig, TrafficSplitConfig, TrafficSplitMatch, TrafficSplitVariant,
+    PRIMARY_VARIANT_NAME,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrafficSplitSettings {
+    pub sticky_cookie: Option<String>,
+    pub variants: Vec<TrafficSplitVariantSettings>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrafficSplitVariantSettings {
+    pub name: String,
+    pub proxy_pass_to: String,
+    pub weight: Option<f64>,
+    pub header: Option<String>,
+    pub cookie: Option<String>,
+    pub query: Option<String>,
+}
+
+impl TrafficSplitVariantSettings {
+    pub fn to_traffic_split_variant(
+        &self,
+        config: Arc<ProxyPassLocationConfig>,
+    ) -> Result<TrafficSplitVariant, String> {
+        let weight = self.weight.unwrap_or(0.0);
+
+        if !(0.0..=100.0).contains(&weight) {
+            return Err(format!(
+                "split variant {} weight must be in range [0..100]. Value: {}",
+                self.name, weight
+            ));
+        }
+
+        Ok(TrafficSplitVariant {
+            name: self.name.clone(),
+            weight,
+            header: self.header.as_deref().map(TrafficSplitMatch::parse),
+            cookie: self.cookie.as_deref().map(TrafficSplitMatch::parse),
+            query: self.query.as_deref().map(TrafficSplitMatch::parse),
+            config,
+        })
+    }
+}
+
+impl TrafficSplitSettings {
+    pub fn to_traffic_split_config(
+        &self,
+        cookie_path: String,
+        variants: Vec<TrafficSplitVariant>,
+    ) -> Result<TrafficSplitConfig, String> {
+        let mut weights_sum = 0.0;
+
+        for (index, variant) in variants.iter().enumerate() {
+            // Name is the value of the sticky cookie
+            let is_valid_name = !variant.name.is_empty()
+                && variant
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+            if !is_valid_name || variant.name == PRIMARY_VARIANT_NAME {
+                return Err(format!("Invalid split variant name: '{}'", variant.name));
+            }
+
+            if variants[..index].iter().any(|itm| itm.name == variant.name) {
+                return Err(format!("Duplicated split variant name: '{}'", variant.name));
+            }
+
+            weights_sum += variant.weight;
+        }
+
+        if weights_sum > 100.0 {
+            return Err(format!(
+                "Sum of split variant weights must not be greater than 100. Value: {}",
+                weights_sum
+            ));
+        }
+
+        Ok(TrafficSplitConfig {
+            variants,
+            sticky_cookie: self.sticky_cookie.clone(),
+            cookie_path,
+        })
+    }
+}
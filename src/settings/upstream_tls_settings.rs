@@ -0,0 +1,15 @@
+use serde::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpstreamTlsSettings {
+    pub ca: Option<String>,
+    pub client_certificate: Option<String>,
+    pub sni: Option<String>,
+    pub insecure_skip_verify: Option<bool>,
+}
+
+impl UpstreamTlsSettings {
+    pub fn get_insecure_skip_verify(&self) -> bool {
+        self.insecure_skip_verify.unwrap_or(false)
+    }
+}
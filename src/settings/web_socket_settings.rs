@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use rust_extensions::duration_utils::parse_duration;
+use serde::*;
+
+use crate::configurations::WebSocketLocationConfig;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebSocketSettings {
+    pub idle_timeout: Option<String>,
+    pub ping_interval: Option<String>,
+    pub max_message_size: Option<usize>,
+}
+
+impl WebSocketSettings {
+    pub fn to_web_socket_config(&self) -> Result<WebSocketLocationConfig, String> {
+        if self.max_message_size == Some(0) {
+            return Err("web_socket max_message_size must be greater than 0".to_string());
+        }
+
+        Ok(WebSocketLocationConfig {
+            idle_timeout: parse_optional_duration("idle_timeout", self.idle_timeout.as_ref())?,
+            ping_interval: parse_optional_duration("ping_interval", self.ping_interval.as_ref())?,
+            max_message_size: self.max_message_size,
+        })
+    }
+}
+
+fn parse_optional_duration(name: &str, value: Option<&String>) -> Result<Option<Duration>, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match parse_duration(value) {
+        Ok(result) if !result.is_zero() => Ok(Some(result)),
+        _ => Err(format!(
+            "Can not parse web_socket {} value: '{}'",
+            name, value
+        )),
+    }
+}
@@ -0,0 +1,4 @@
+mod socks5_handshake;
+pub use socks5_handshake::*;
+mod start_socks5;
+pub use start_socks5::*;
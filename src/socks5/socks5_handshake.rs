@@ -0,0 +1,198 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const SOCKS5_VERSION: u8 = 0x05;
+const AUTH_VERSION: u8 = 0x01;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USER_PASSWORD: u8 = 0x02;
+const METHOD_NOT_ACCEPTABLE: u8 = 0xFF;
+
+const COMMAND_CONNECT: u8 = 0x01;
+
+const ADDRESS_TYPE_IPV4: u8 = 0x01;
+const ADDRESS_TYPE_DOMAIN: u8 = 0x03;
+const ADDRESS_TYPE_IPV6: u8 = 0x04;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Socks5Reply {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    NotAllowedByRuleset = 0x02,
+    HostUnreachable = 0x04,
+    ConnectionRefused = 0x05,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+pub struct Socks5ConnectRequest {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Socks5ConnectRequest {
+    pub fn to_string(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+// Negotiates auth method and reads CONNECT request.
+// credentials - username and password if authentication is required
+pub async fn socks5_handshake(
+    stream: &mut TcpStream,
+    credentials: Option<&(String, String)>,
+) -> Result<Socks5ConnectRequest, String> {
+    let version = stream.read_u8().await.map_err(|err| err.to_string())?;
+
+    if version != SOCKS5_VERSION {
+        return Err(format!("Unsupported socks version {}", version));
+    }
+
+    let methods_amount = stream.read_u8().await.map_err(|err| err.to_string())?;
+    let mut methods = vec![0u8; methods_amount as usize];
+    stream
+        .read_exact(&mut methods)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let method = if credentials.is_some() {
+        METHOD_USER_PASSWORD
+    } else {
+        METHOD_NO_AUTH
+    };
+
+    if !methods.contains(&method) {
+        let _ = stream
+            .write_all(&[SOCKS5_VERSION, METHOD_NOT_ACCEPTABLE])
+            .await;
+        return Err("Client does not support required auth method".to_string());
+    }
+
+    stream
+        .write_all(&[SOCKS5_VERSION, method])
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if let Some((username, password)) = credentials {
+        authenticate(stream, username, password).await?;
+    }
+
+    let mut header = [0u8; 4];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if header[0] != SOCKS5_VERSION {
+        return Err(format!("Unsupported socks version {}", header[0]));
+    }
+
+    let host = match header[3] {
+        ADDRESS_TYPE_IPV4 => {
+            let mut addr = [0u8; 4];
+            stream
+                .read_exact(&mut addr)
+                .await
+                .map_err(|err| err.to_string())?;
+            std::net::Ipv4Addr::from(addr).to_string()
+        }
+        ADDRESS_TYPE_DOMAIN => {
+            let len = stream.read_u8().await.map_err(|err| err.to_string())?;
+            let mut domain = vec![0u8; len as usize];
+            stream
+                .read_exact(&mut domain)
+                .await
+                .map_err(|err| err.to_string())?;
+            String::from_utf8(domain).map_err(|_| "Invalid domain name".to_string())?
+        }
+        ADDRESS_TYPE_IPV6 => {
+            let mut addr = [0u8; 16];
+            stream
+                .read_exact(&mut addr)
+                .await
+                .map_err(|err| err.to_string())?;
+            std::net::Ipv6Addr::from(addr).to_string()
+        }
+        address_type => {
+            send_reply(stream, Socks5Reply::AddressTypeNotSupported).await;
+            return Err(format!("Unsupported address type {}", address_type));
+        }
+    };
+
+    let port = stream.read_u16().await.map_err(|err| err.to_string())?;
+
+    if header[1] != COMMAND_CONNECT {
+        send_reply(stream, Socks5Reply::CommandNotSupported).await;
+        return Err(format!("Unsupported socks5 command {}", header[1]));
+    }
+
+    Ok(Socks5ConnectRequest { host, port })
+}
+
+async fn authenticate(
+    stream: &mut TcpStream,
+    username: &str,
+    password: &str,
+) -> Result<(), String> {
+    let version = stream.read_u8().await.map_err(|err| err.to_string())?;
+
+    if version != AUTH_VERSION {
+        return Err(format!("Unsupported auth version {}", version));
+    }
+
+    let len = stream.read_u8().await.map_err(|err| err.to_string())?;
+    let mut client_username = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut client_username)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let len = stream.read_u8().await.map_err(|err| err.to_string())?;
+    let mut client_password = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut client_password)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // Both parts are compared, so the response time does not tell which one is wrong
+    let username_is_same =
+        crate::tcp_port_forward::are_credentials_same(&client_username, username.as_bytes());
+    let password_is_same =
+        crate::tcp_port_forward::are_credentials_same(&client_password, password.as_bytes());
+
+    if !(username_is_same & password_is_same) {
+        let _ = stream.write_all(&[AUTH_VERSION, 0x01]).await;
+        return Err(format!(
+            "Invalid username or password for user '{}'",
+            String::from_utf8_lossy(&client_username)
+        ));
+    }
+
+    stream
+        .write_all(&[AUTH_VERSION, 0x00])
+        .await
+        .map_err(|err| err.to_string())
+}
+
+pub async fn send_reply(stream: &mut TcpStream, reply: Socks5Reply) {
+    let _ = stream
+        .write_all(&[
+            SOCKS5_VERSION,
+            reply as u8,
+            0x00,
+            ADDRESS_TYPE_IPV4,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ])
+        .await;
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+use crate::{
+    app::AppContext, configurations::*, rate_limit::ConnectionPermit,
+    tcp_port_forward::ForwardProxyConnectError,
+};
+
+use super::*;
+
+pub fn start_socks5(
+    app: Arc<AppContext>,
+    listen_addr: std::net::SocketAddr,
+    endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+) {
+    tokio::spawn(socks5_server_accept_loop(app, listen_addr, endpoint_info));
+}
+
+async fn socks5_server_accept_loop(
+    app: Arc<AppContext>,
+    listen_addr: std::net::SocketAddr,
+    endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+) {
+    let listener = tokio::net::TcpListener::bind(listen_addr).await;
+
+    if let Err(err) = listener {
+        println!(
+            "Error binding to tcp port {} for socks5 proxy has Error: {:?}",
+            listen_addr, err
+        );
+        return;
+    }
+
+    let listener = listener.unwrap();
+
+    println!(
+        "Enabled Socks5 proxy: {} -> {}",
+        listen_addr,
+        endpoint_info.get_egress_as_string()
+    );
+
+    loop {
+        let (mut server_stream, socket_addr) = listener.accept().await.unwrap();
+
+        if app.states.is_shutting_down() {
+            return;
+        }
+
+        if !endpoint_info
+            .whitelisted_ip
+            .is_whitelisted(&socket_addr.ip())
+        {
+            if endpoint_info.debug {
+                println!(
+                    "Incoming socks5 connection from {} is not whitelisted. Closing it",
+                    socket_addr
+                );
+            }
+
+            let _ = server_stream.shutdown().await;
+            continue;
+        }
+
+        let connection_permit = match endpoint_info.connections_limit.as_ref() {
+            Some(connections_limit) => match connections_limit.try_acquire(socket_addr.ip()) {
+                Some(permit) => Some(permit),
+                None => {
+                    if endpoint_info.debug {
+                        println!(
+                            "Incoming socks5 connection from {} is over the connections limit. Closing it",
+                            socket_addr
+                        );
+                    }
+
+                    let _ = server_stream.shutdown().await;
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        tokio::spawn(handle_connection(
+            app.clone(),
+            endpoint_info.clone(),
+            server_stream,
+            socket_addr,
+            connection_permit,
+        ));
+    }
+}
+
+async fn handle_connection(
+    app: Arc<AppContext>,
+    endpoint_info: Arc<ForwardProxyEndpointHostConfig>,
+    mut server_stream: TcpStream,
+    socket_addr: std::net::SocketAddr,
+    connection_permit: Option<ConnectionPermit>,
+) {
+    let request = tokio::time::timeout(
+        app.connection_settings.remote_connect_timeout,
+        socks5_handshake(&mut server_stream, endpoint_info.credentials.as_ref()),
+    )
+    .await;
+
+    let request = match request {
+        Ok(Ok(request)) => request,
+        Ok(Err(err)) => {
+            if endpoint_info.debug {
+                println!("Socks5 handshake with {} failed. Err: {}", socket_addr, err);
+            }
+            let _ = server_stream.shutdown().await;
+            return;
+        }
+        Err(_) => {
+            if endpoint_info.debug {
+                println!("Socks5 handshake with {} timeout", socket_addr);
+            }
+            let _ = server_stream.shutdown().await;
+            return;
+        }
+    };
+
+    let remote_stream = match crate::tcp_port_forward::connect_forward_proxy_destination(
+        &app,
+        &endpoint_info,
+        &request.host,
+        request.port,
+    )
+    .await
+    {
+        Ok(remote_stream) => remote_stream,
+        Err(ForwardProxyConnectError::NotAllowed) => {
+            println!(
+                "Socks5 connection from {} to {} is not allowed",
+                socket_addr,
+                request.to_string()
+            );
+            send_reply(&mut server_stream, Socks5Reply::NotAllowedByRuleset).await;
+            let _ = server_stream.shutdown().await;
+            return;
+        }
+        Err(err) => {
+            if endpoint_info.debug {
+                println!(
+                    "Socks5 connection from {} can not connect to {}. Err: {}",
+                    socket_addr,
+                    request.to_string(),
+                    err.to_string()
+                );
+            }
+
+            let reply = match err {
+                ForwardProxyConnectError::NotAllowed => Socks5Reply::NotAllowedByRuleset,
+                ForwardProxyConnectError::Ssh(_) => Socks5Reply::GeneralFailure,
+                ForwardProxyConnectError::CanNotConnect(_) => Socks5Reply::ConnectionRefused,
+                ForwardProxyConnectError::Timeout => Socks5Reply::HostUnreachable,
+            };
+
+            send_reply(&mut server_stream, reply).await;
+            let _ = server_stream.shutdown().await;
+            return;
+        }
+    };
+
+    send_reply(&mut server_stream, Socks5Reply::Succeeded).await;
+
+    if endpoint_info.debug {
+        println!(
+            "Socks5 connection {} -> {} is established",
+            socket_addr,
+            request.to_string()
+        );
+    }
+
+    let debug = endpoint_info.debug;
+    let destination = request.to_string();
+
+    let (server_reader, server_writer) = server_stream.into_split();
+
+    crate::tcp_port_forward::relay_forward_proxy_connection(
+        server_reader,
+        server_writer,
+        remote_stream,
+        app.connection_settings.buffer_size,
+        debug,
+        move || {
+            if debug {
+                println!(
+                    "Dead Socks5 connection {}->{} detected. Closing",
+                    socket_addr, destination
+                );
+            }
+        },
+    )
+    .await;
+
+    drop(connection_permit);
+}
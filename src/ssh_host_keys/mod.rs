@@ -0,0 +1,4 @@
+mod ssh_host_key_policy;
+pub use ssh_host_key_policy::*;
+mod verify_ssh_host_key;
+pub use verify_ssh_host_key::*;
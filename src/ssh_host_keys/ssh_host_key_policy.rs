@@ -0,0 +1,92 @@
+use crate::configurations::LocalFilePath;
+
+pub const DEFAULT_KNOWN_HOSTS_FILE: &str = "~/.ssh/known_hosts";
+
+const POLICY_STRICT: &str = "strict";
+const POLICY_ACCEPT_NEW: &str = "accept-new";
+const POLICY_OFF: &str = "off";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SshHostKeyPolicy {
+    Strict,
+    AcceptNew,
+    Off,
+}
+
+impl SshHostKeyPolicy {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        match src.trim() {
+            POLICY_STRICT => Ok(Self::Strict),
+            POLICY_ACCEPT_NEW => Ok(Self::AcceptNew),
+            POLICY_OFF => Ok(Self::Off),
+            other => Err(format!(
+                "Unknown ssh host_key_policy '{}'. Supported values: {}, {}, {}",
+                other, POLICY_STRICT, POLICY_ACCEPT_NEW, POLICY_OFF
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SshHostKeyPolicy::Strict => POLICY_STRICT,
+            SshHostKeyPolicy::AcceptNew => POLICY_ACCEPT_NEW,
+            SshHostKeyPolicy::Off => POLICY_OFF,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SshHostKeySettings {
+    pub policy: SshHostKeyPolicy,
+    pub known_hosts_file: String,
+    pub fingerprint: Option<String>,
+}
+
+impl SshHostKeySettings {
+    pub fn get_known_hosts_file(&self) -> String {
+        LocalFilePath::new(self.known_hosts_file.clone())
+            .get_value()
+            .to_string()
+    }
+
+    pub fn is_verification_off(&self) -> bool {
+        self.policy == SshHostKeyPolicy::Off && self.fingerprint.is_none()
+    }
+
+    pub fn to_string(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.policy.as_str(),
+            self.known_hosts_file,
+            self.fingerprint.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+// Verification is opt-in, so existing ssh settings keep working without known_hosts file
+impl Default for SshHostKeySettings {
+    fn default() -> Self {
+        Self {
+            policy: SshHostKeyPolicy::Off,
+            known_hosts_file: DEFAULT_KNOWN_HOSTS_FILE.to_string(),
+            fingerprint: None,
+        }
+    }
+}
+
+// Host key settings of each host of the ssh chain.
+// Kept next to the credentials, since my_ssh::SshCredentials can not carry them
+#[derive(Debug, Clone, Default)]
+pub struct SshChainHostKeys {
+    pub jump_hosts: Vec<SshHostKeySettings>,
+    pub host: SshHostKeySettings,
+}
+
+impl SshChainHostKeys {
+    pub fn get_jump_host(&self, index: usize) -> SshHostKeySettings {
+        match self.jump_hosts.get(index) {
+            Some(settings) => settings.clone(),
+            None => SshHostKeySettings::default(),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{path::Path, sync::Mutex};
+
+use base64::Engine;
+use my_ssh::{
+    ssh2::{CheckResult, ErrorCode, HostKeyType, KnownHostFileKind, Session},
+    SshSessionError,
+};
+use sha2::{Digest, Sha256};
+
+use super::{SshHostKeyPolicy, SshHostKeySettings};
+
+pub const SSH_HOST_KEY_VERIFICATION_ERROR_CODE: i32 = -11;
+
+pub const HOST_KEY_MISMATCH: &str =
+    "Ssh host key verification failed: remote host key does not match known_hosts";
+pub const HOST_KEY_NOT_FOUND: &str =
+    "Ssh host key verification failed: remote host is not found in known_hosts";
+pub const HOST_KEY_FINGERPRINT_MISMATCH: &str =
+    "Ssh host key verification failed: remote host key does not match pinned fingerprint";
+pub const HOST_KEY_NOT_PROVIDED: &str =
+    "Ssh host key verification failed: host key is not provided";
+pub const HOST_KEY_KNOWN_HOSTS_ERROR: &str =
+    "Ssh host key verification failed: can not read known_hosts file";
+
+// Sessions are established concurrently. known_hosts file is checked and written under the lock,
+// so the host added by one session is not lost when another session writes the file
+static KNOWN_HOSTS_LOCK: Mutex<()> = Mutex::new(());
+
+// Checks host key which is received in the key exchange of the ssh session.
+// host, port - original ssh host which is used for the known_hosts lookup
+pub fn verify_ssh_host_key(
+    host: &str,
+    port: u16,
+    settings: &SshHostKeySettings,
+    host_key: &[u8],
+    host_key_type: HostKeyType,
+) -> Result<(), &'static str> {
+    let fingerprint = get_fingerprint(host_key);
+
+    if let Some(pinned) = settings.fingerprint.as_ref() {
+        let pinned = pinned.trim();
+        let pinned = pinned.strip_prefix("SHA256:").unwrap_or(pinned);
+
+        if pinned.trim_end_matches('=') != fingerprint {
+            println!(
+                "Ssh host {}:{} key fingerprint SHA256:{} does not match pinned fingerprint SHA256:{}",
+                host, port, fingerprint, pinned
+            );
+            return Err(HOST_KEY_FINGERPRINT_MISMATCH);
+        }
+
+        return Ok(());
+    }
+
+    if settings.policy == SshHostKeyPolicy::Off {
+        return Ok(());
+    }
+
+    let known_hosts_file = settings.get_known_hosts_file();
+    let known_hosts_path = Path::new(known_hosts_file.as_str());
+
+    let _known_hosts_lock = KNOWN_HOSTS_LOCK
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+
+    // File is read under the lock, so the hosts added by the other sessions are seen
+    let session = Session::new().map_err(|_| HOST_KEY_KNOWN_HOSTS_ERROR)?;
+    let mut known_hosts = session
+        .known_hosts()
+        .map_err(|_| HOST_KEY_KNOWN_HOSTS_ERROR)?;
+
+    if known_hosts_path.exists() {
+        if let Err(err) = known_hosts.read_file(known_hosts_path, KnownHostFileKind::OpenSSH) {
+            println!(
+                "Can not read known_hosts file {}. Err: {}",
+                known_hosts_file, err
+            );
+            return Err(HOST_KEY_KNOWN_HOSTS_ERROR);
+        }
+    }
+
+    match known_hosts.check_port(host, port, host_key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => {
+            println!(
+                "Ssh host {}:{} key SHA256:{} does not match key in {}. Possible MITM attack",
+                host, port, fingerprint, known_hosts_file
+            );
+            Err(HOST_KEY_MISMATCH)
+        }
+        CheckResult::NotFound => {
+            if settings.policy == SshHostKeyPolicy::Strict {
+                println!(
+                    "Ssh host {}:{} with key SHA256:{} is not found in {}",
+                    host, port, fingerprint, known_hosts_file
+                );
+                return Err(HOST_KEY_NOT_FOUND);
+            }
+
+            let host_entry = if port == 22 {
+                host.to_string()
+            } else {
+                format!("[{}]:{}", host, port)
+            };
+
+            println!(
+                "Adding ssh host {} with key SHA256:{} to {}",
+                host_entry, fingerprint, known_hosts_file
+            );
+
+            let result = known_hosts
+                .add(
+                    host_entry.as_str(),
+                    host_key,
+                    "my-reverse-proxy",
+                    host_key_type.into(),
+                )
+                .and_then(|_| known_hosts.write_file(known_hosts_path, KnownHostFileKind::OpenSSH));
+
+            if let Err(err) = result {
+                println!(
+                    "Can not write known_hosts file {}. Err: {}",
+                    known_hosts_file, err
+                );
+            }
+
+            Ok(())
+        }
+        CheckResult::Failure => Err(HOST_KEY_KNOWN_HOSTS_ERROR),
+    }
+}
+
+pub fn get_fingerprint(host_key: &[u8]) -> String {
+    let hash = Sha256::digest(host_key);
+    base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash)
+}
+
+pub fn create_host_key_verification_error(message: &'static str) -> SshSessionError {
+    let err = my_ssh::ssh2::Error::new(
+        ErrorCode::Session(SSH_HOST_KEY_VERIFICATION_ERROR_CODE),
+        message,
+    );
+    SshSessionError::SshError(err.into())
+}
+
+// Returns message to show on the tech page if error is a host key verification failure
+pub fn get_host_key_verification_error(err: &SshSessionError) -> Option<String> {
+    if let SshSessionError::SshError(ssh_err) = err {
+        if let Some(ssh2_error) = ssh_err.as_ssh2() {
+            if let ErrorCode::Session(value) = ssh2_error.code() {
+                if value == SSH_HOST_KEY_VERIFICATION_ERROR_CODE {
+                    return Some(ssh2_error.message().to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU",
+            super::get_fingerprint(b"")
+        );
+    }
+}
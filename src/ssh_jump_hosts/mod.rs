@@ -0,0 +1,2 @@
+mod ssh_chain_session;
+pub use ssh_chain_session::*;
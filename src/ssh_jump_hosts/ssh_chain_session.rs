@@ -0,0 +1,346 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use async_ssh2_lite::{AsyncChannel, AsyncSession};
+use futures::AsyncReadExt;
+use my_ssh::{
+    ssh2::{ErrorCode, HostKeyType},
+    SshCredentials, SshSessionError,
+};
+use tokio::{
+    net::{TcpStream, UnixStream},
+    task::JoinHandle,
+};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::{
+    http_proxy_pass::ProxyPassError,
+    ssh_host_keys::{SshChainHostKeys, SshHostKeySettings},
+};
+
+// LIBSSH2_ERROR_TIMEOUT
+const SSH_TIMEOUT_ERROR_CODE: i32 = -9;
+
+pub type SshChannel = AsyncChannel<UnixStream>;
+
+// Ssh session to the host which is reached through the chain of jump hosts.
+// Each next session goes through the direct-tcpip channel of the previous session inside of the process,
+// so no local port is opened. The first session goes through the unix stream pair as well,
+// so sessions of all the hops have the same stream type.
+// Host key of each hop is checked right after the handshake, before credentials are sent
+pub struct SshChainSession {
+    name: String,
+    // Jump hosts sessions are kept, since the next sessions go through their channels
+    sessions: Vec<AsyncSession<UnixStream>>,
+    relay_tasks: Vec<JoinHandle<()>>,
+}
+
+impl SshChainSession {
+    pub async fn connect(
+        jump_hosts: &[Arc<SshCredentials>],
+        credentials: &Arc<SshCredentials>,
+        host_keys: &SshChainHostKeys,
+        timeout: Duration,
+    ) -> Result<Self, ProxyPassError> {
+        let mut result = Self {
+            name: get_chain_name(jump_hosts, credentials),
+            sessions: Vec::with_capacity(jump_hosts.len() + 1),
+            relay_tasks: Vec::with_capacity(jump_hosts.len() + 1),
+        };
+
+        for (index, jump_host) in jump_hosts.iter().enumerate() {
+            result
+                .connect_hop(jump_host, &host_keys.get_jump_host(index), timeout)
+                .await?;
+        }
+
+        result
+            .connect_hop(credentials, &host_keys.host, timeout)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    // Session of the last host of the chain
+    pub fn get_session(&self) -> &AsyncSession<UnixStream> {
+        self.sessions.last().unwrap()
+    }
+
+    pub async fn connect_to_remote_host(
+        &self,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<SshChannel, SshSessionError> {
+        let future = self.get_session().channel_direct_tcpip(host, port, None);
+
+        match tokio::time::timeout(timeout, future).await {
+            Ok(Ok(channel)) => Ok(channel),
+            Ok(Err(err)) => Err(SshSessionError::SshError(err)),
+            Err(_) => Err(create_timeout_error()),
+        }
+    }
+
+    pub async fn execute_command(
+        &self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<(String, i32), SshSessionError> {
+        let future = async {
+            let mut channel = self.get_session().channel_session().await?;
+            channel.exec(command).await?;
+
+            let mut output = String::new();
+            channel.read_to_string(&mut output).await?;
+            channel.wait_close().await?;
+
+            let exit_status = channel.exit_status()?;
+            Ok::<_, async_ssh2_lite::Error>((output, exit_status))
+        };
+
+        match tokio::time::timeout(timeout, future).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(err)) => Err(SshSessionError::SshError(err)),
+            Err(_) => Err(create_timeout_error()),
+        }
+    }
+
+    pub async fn download_remote_file(
+        &self,
+        file_path: &str,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, SshSessionError> {
+        let future = async {
+            let (mut channel, stat) = self.get_session().scp_recv(Path::new(file_path)).await?;
+
+            // Scp sends the status byte after the content, so the content is read by its size
+            let mut content = vec![0u8; stat.size() as usize];
+            channel.read_exact(&mut content).await?;
+
+            channel.send_eof().await?;
+            channel.wait_eof().await?;
+            channel.close().await?;
+            channel.wait_close().await?;
+
+            Ok::<_, async_ssh2_lite::Error>(content)
+        };
+
+        match tokio::time::timeout(timeout, future).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(err)) => Err(SshSessionError::SshError(err)),
+            Err(_) => Err(create_timeout_error()),
+        }
+    }
+
+    async fn connect_hop(
+        &mut self,
+        credentials: &SshCredentials,
+        host_key_settings: &SshHostKeySettings,
+        timeout: Duration,
+    ) -> Result<(), ProxyPassError> {
+        let future = self.connect_hop_int(credentials, host_key_settings);
+
+        match tokio::time::timeout(timeout, future).await {
+            Ok(result) => result,
+            Err(_) => Err(ProxyPassError::SshConnectError(format!(
+                "Can not connect to ssh host {}. Timeout",
+                credentials.to_string()
+            ))),
+        }
+    }
+
+    async fn connect_hop_int(
+        &mut self,
+        credentials: &SshCredentials,
+        host_key_settings: &SshHostKeySettings,
+    ) -> Result<(), ProxyPassError> {
+        let (host, port) = credentials.get_host_port();
+
+        let (stream, relay_stream) = UnixStream::pair().map_err(|err| {
+            ProxyPassError::SshConnectError(format!(
+                "Can not create stream for ssh host {}. Err: {}",
+                credentials.to_string(),
+                err
+            ))
+        })?;
+
+        let relay_task = match self.sessions.last() {
+            Some(via) => {
+                let channel = via
+                    .channel_direct_tcpip(host, port, None)
+                    .await
+                    .map_err(|err| {
+                        ProxyPassError::SshConnectError(format!(
+                            "Can not open ssh jump host channel {}->{}:{}. Err: {:?}",
+                            self.name, host, port, err
+                        ))
+                    })?;
+
+                tokio::spawn(relay(channel.compat(), relay_stream))
+            }
+            None => {
+                let tcp_stream = TcpStream::connect((host, port)).await.map_err(|err| {
+                    ProxyPassError::SshConnectError(format!(
+                        "Can not connect to ssh host {}:{}. Err: {}",
+                        host, port, err
+                    ))
+                })?;
+
+                tokio::spawn(relay(tcp_stream, relay_stream))
+            }
+        };
+
+        self.relay_tasks.push(relay_task);
+
+        let mut session = AsyncSession::new(stream, None).map_err(|err| {
+            ProxyPassError::SshConnectError(format!(
+                "Can not create ssh session {}. Err: {:?}",
+                credentials.to_string(),
+                err
+            ))
+        })?;
+
+        session.handshake().await.map_err(|err| {
+            ProxyPassError::SshConnectError(format!(
+                "Ssh handshake error {}. Err: {:?}",
+                credentials.to_string(),
+                err
+            ))
+        })?;
+
+        if !host_key_settings.is_verification_off() {
+            let (host_key, host_key_type) = match session.host_key() {
+                Some((host_key, host_key_type)) => (host_key.to_vec(), host_key_type),
+                None => {
+                    return Err(crate::ssh_host_keys::create_host_key_verification_error(
+                        crate::ssh_host_keys::HOST_KEY_NOT_PROVIDED,
+                    )
+                    .into())
+                }
+            };
+
+            verify_host_key(host, port, host_key_settings, host_key, host_key_type).await?;
+        }
+
+        authenticate(&session, credentials).await.map_err(|err| {
+            ProxyPassError::SshConnectError(format!(
+                "Ssh authentication error {}. Err: {:?}",
+                credentials.to_string(),
+                err
+            ))
+        })?;
+
+        self.sessions.push(session);
+
+        Ok(())
+    }
+}
+
+impl Drop for SshChainSession {
+    fn drop(&mut self) {
+        for relay_task in self.relay_tasks.iter() {
+            relay_task.abort();
+        }
+    }
+}
+
+async fn verify_host_key(
+    host: &str,
+    port: u16,
+    host_key_settings: &SshHostKeySettings,
+    host_key: Vec<u8>,
+    host_key_type: HostKeyType,
+) -> Result<(), ProxyPassError> {
+    let host = host.to_string();
+    let host_key_settings = host_key_settings.clone();
+
+    // known_hosts file is read and written with blocking io
+    let result = tokio::task::spawn_blocking(move || {
+        crate::ssh_host_keys::verify_ssh_host_key(
+            &host,
+            port,
+            &host_key_settings,
+            &host_key,
+            host_key_type,
+        )
+    })
+    .await
+    .unwrap_or(Err(crate::ssh_host_keys::HOST_KEY_KNOWN_HOSTS_ERROR));
+
+    result.map_err(|err| crate::ssh_host_keys::create_host_key_verification_error(err).into())
+}
+
+async fn authenticate(
+    session: &AsyncSession<UnixStream>,
+    credentials: &SshCredentials,
+) -> Result<(), async_ssh2_lite::Error> {
+    match credentials {
+        SshCredentials::SshAgent { ssh_user_name, .. } => {
+            session.userauth_agent_with_try_next(ssh_user_name).await
+        }
+        SshCredentials::UserNameAndPassword {
+            ssh_user_name,
+            password,
+            ..
+        } => session.userauth_password(ssh_user_name, password).await,
+        SshCredentials::PrivateKey {
+            ssh_user_name,
+            private_key,
+            passphrase,
+            ..
+        } => {
+            session
+                .userauth_pubkey_memory(ssh_user_name, None, private_key, passphrase.as_deref())
+                .await
+        }
+    }
+}
+
+async fn relay(
+    mut upstream: impl tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    mut stream: UnixStream,
+) {
+    let _ = tokio::io::copy_bidirectional(&mut upstream, &mut stream).await;
+}
+
+fn create_timeout_error() -> SshSessionError {
+    let err = my_ssh::ssh2::Error::new(ErrorCode::Session(SSH_TIMEOUT_ERROR_CODE), "Timeout");
+    SshSessionError::SshError(err.into())
+}
+
+pub fn get_chain_name(jump_hosts: &[Arc<SshCredentials>], credentials: &SshCredentials) -> String {
+    let mut result = String::new();
+
+    for jump_host in jump_hosts {
+        result.push_str(jump_host.to_string().as_str());
+        result.push_str("->");
+    }
+
+    result.push_str(credentials.to_string().as_str());
+    result
+}
+
+// Chains with the same hosts but different host key settings are different chains
+pub fn get_chain_key(
+    jump_hosts: &[Arc<SshCredentials>],
+    credentials: &SshCredentials,
+    host_keys: &SshChainHostKeys,
+) -> String {
+    let mut result = String::new();
+
+    for (index, jump_host) in jump_hosts.iter().enumerate() {
+        result.push_str(jump_host.to_string().as_str());
+        result.push('[');
+        result.push_str(host_keys.get_jump_host(index).to_string().as_str());
+        result.push_str("]->");
+    }
+
+    result.push_str(credentials.to_string().as_str());
+    result.push('[');
+    result.push_str(host_keys.host.to_string().as_str());
+    result.push(']');
+    result
+}
@@ -0,0 +1,4 @@
+mod ssh_reverse_statuses;
+pub use ssh_reverse_statuses::*;
+mod start_ssh_reverse;
+pub use start_ssh_reverse::*;
@@ -0,0 +1,68 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+#[derive(Debug, Clone)]
+pub enum SshReverseState {
+    Connecting,
+    Listening,
+    Disconnected,
+}
+
+impl SshReverseState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SshReverseState::Connecting => "connecting",
+            SshReverseState::Listening => "listening",
+            SshReverseState::Disconnected => "disconnected",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SshReverseStatus {
+    pub state: SshReverseState,
+    pub connected_at: Option<DateTimeAsMicroseconds>,
+    pub reconnects: usize,
+    pub active_connections: usize,
+    pub last_error: Option<String>,
+}
+
+impl SshReverseStatus {
+    fn new() -> Self {
+        Self {
+            state: SshReverseState::Connecting,
+            connected_at: None,
+            reconnects: 0,
+            active_connections: 0,
+            last_error: None,
+        }
+    }
+}
+
+// Status of each ssh-reverse endpoint by endpoint name. Rendered on the control page
+pub struct SshReverseStatuses {
+    items: Mutex<BTreeMap<String, SshReverseStatus>>,
+}
+
+impl SshReverseStatuses {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn update(&self, name: &str, update: impl Fn(&mut SshReverseStatus)) {
+        let mut items = self.items.lock().unwrap();
+
+        if !items.contains_key(name) {
+            items.insert(name.to_string(), SshReverseStatus::new());
+        }
+
+        update(items.get_mut(name).unwrap());
+    }
+
+    pub fn get_snapshot(&self) -> BTreeMap<String, SshReverseStatus> {
+        self.items.lock().unwrap().clone()
+    }
+}
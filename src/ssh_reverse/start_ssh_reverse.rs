@@ -0,0 +1,225 @@
+use std::{sync::Arc, time::Duration};
+
+use async_ssh2_lite::AsyncListener;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use tokio::net::{TcpStream, UnixStream};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::{
+    app::AppContext,
+    configurations::*,
+    ssh_jump_hosts::{SshChainSession, SshChannel},
+};
+
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+// Remote listener does not notice the broken session on its own, so keep alive is always sent
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn start_ssh_reverse(app: Arc<AppContext>, endpoint_info: Arc<SshReverseEndpointConfig>) {
+    tokio::spawn(ssh_reverse_loop(app, endpoint_info));
+}
+
+async fn ssh_reverse_loop(app: Arc<AppContext>, endpoint_info: Arc<SshReverseEndpointConfig>) {
+    let name = endpoint_info.host.as_str().to_string();
+    let mut reconnect_delay = RECONNECT_MIN_DELAY;
+
+    while !app.states.is_shutting_down() {
+        app.ssh_reverse_statuses.update(&name, |status| {
+            status.state = SshReverseState::Connecting;
+        });
+
+        match start_remote_listener(&app, &endpoint_info).await {
+            Ok((session, listener)) => {
+                println!("Enabled ssh reverse forward: {}", endpoint_info.to_string());
+
+                reconnect_delay = RECONNECT_MIN_DELAY;
+
+                app.ssh_reverse_statuses.update(&name, |status| {
+                    status.state = SshReverseState::Listening;
+                    status.connected_at = Some(DateTimeAsMicroseconds::now());
+                });
+
+                let err = accept_loop(&app, &endpoint_info, &session, listener).await;
+
+                println!(
+                    "Ssh reverse forward {} is disconnected. Err: {}",
+                    endpoint_info.to_string(),
+                    err
+                );
+
+                app.ssh_reverse_statuses.update(&name, |status| {
+                    status.state = SshReverseState::Disconnected;
+                    status.connected_at = None;
+                    status.last_error = Some(err.clone());
+                });
+            }
+            Err(err) => {
+                println!(
+                    "Can not start ssh reverse forward {}. Retrying in {:?}. Err: {}",
+                    endpoint_info.to_string(),
+                    reconnect_delay,
+                    err
+                );
+
+                app.ssh_reverse_statuses.update(&name, |status| {
+                    status.state = SshReverseState::Disconnected;
+                    status.last_error = Some(err.clone());
+                });
+            }
+        }
+
+        tokio::time::sleep(reconnect_delay).await;
+
+        reconnect_delay = reconnect_delay * 2;
+        if reconnect_delay > RECONNECT_MAX_DELAY {
+            reconnect_delay = RECONNECT_MAX_DELAY;
+        }
+
+        app.ssh_reverse_statuses.update(&name, |status| {
+            status.reconnects += 1;
+        });
+    }
+}
+
+// my_ssh does not support remote port forwarding, so the session of the chain is used directly
+async fn start_remote_listener(
+    app: &AppContext,
+    endpoint_info: &SshReverseEndpointConfig,
+) -> Result<(SshChainSession, AsyncListener<UnixStream>), String> {
+    let session = SshChainSession::connect(
+        &endpoint_info.ssh_jump_hosts,
+        &endpoint_info.ssh_credentials,
+        &endpoint_info.ssh_host_keys,
+        app.connection_settings.remote_connect_timeout,
+    )
+    .await
+    .map_err(|err| format!("{:?}", err))?;
+
+    session.get_session().set_keepalive(
+        true,
+        get_keep_alive_interval(endpoint_info).as_secs() as u32,
+    );
+
+    let (listener, port) = session
+        .get_session()
+        .channel_forward_listen(
+            endpoint_info.get_remote_listen_port(),
+            Some(endpoint_info.get_remote_listen_host()),
+            None,
+        )
+        .await
+        .map_err(|err| format!("Remote listen is rejected by ssh server. Err: {:?}", err))?;
+
+    if endpoint_info.debug {
+        println!(
+            "Ssh server {} listens on port {} for ssh reverse forward",
+            session.get_name(),
+            port
+        );
+    }
+
+    Ok((session, listener))
+}
+
+fn get_keep_alive_interval(endpoint_info: &SshReverseEndpointConfig) -> Duration {
+    endpoint_info
+        .ssh_keep_alive_interval
+        .unwrap_or(DEFAULT_KEEP_ALIVE_INTERVAL)
+}
+
+async fn accept_loop(
+    app: &Arc<AppContext>,
+    endpoint_info: &Arc<SshReverseEndpointConfig>,
+    session: &SshChainSession,
+    mut listener: AsyncListener<UnixStream>,
+) -> String {
+    let keep_alive_interval = get_keep_alive_interval(endpoint_info);
+
+    loop {
+        let accept_result = tokio::select! {
+            accept_result = listener.accept() => accept_result,
+            _ = tokio::time::sleep(keep_alive_interval) => {
+                if let Err(err) = session.get_session().keepalive_send().await {
+                    return format!("Keep alive error: {:?}", err);
+                }
+                continue;
+            }
+        };
+
+        let ssh_channel = match accept_result {
+            Ok(ssh_channel) => ssh_channel,
+            Err(err) => return format!("{:?}", err),
+        };
+
+        if app.states.is_shutting_down() {
+            return "Application is shutting down".to_string();
+        }
+
+        tokio::spawn(connection_loop(
+            app.clone(),
+            endpoint_info.clone(),
+            ssh_channel,
+        ));
+    }
+}
+
+async fn connection_loop(
+    app: Arc<AppContext>,
+    endpoint_info: Arc<SshReverseEndpointConfig>,
+    ssh_channel: SshChannel,
+) {
+    let connect_future = TcpStream::connect((
+        endpoint_info.target.get_host(),
+        endpoint_info.target.get_port(),
+    ));
+
+    let mut target_stream = match tokio::time::timeout(
+        app.connection_settings.remote_connect_timeout,
+        connect_future,
+    )
+    .await
+    {
+        Ok(Ok(target_stream)) => target_stream,
+        Ok(Err(err)) => {
+            println!(
+                "Ssh reverse forward {}. Can not connect to target. Err: {}",
+                endpoint_info.to_string(),
+                err
+            );
+            return;
+        }
+        Err(_) => {
+            println!(
+                "Ssh reverse forward {}. Timeout connecting to target",
+                endpoint_info.to_string()
+            );
+            return;
+        }
+    };
+
+    let name = endpoint_info.host.as_str();
+
+    app.ssh_reverse_statuses.update(name, |status| {
+        status.active_connections += 1;
+    });
+
+    let mut ssh_channel = ssh_channel.compat();
+
+    let result = tokio::io::copy_bidirectional(&mut target_stream, &mut ssh_channel).await;
+
+    if endpoint_info.debug {
+        if let Err(err) = result {
+            println!(
+                "Ssh reverse forward {} connection is closed. Err: {}",
+                endpoint_info.to_string(),
+                err
+            );
+        }
+    }
+
+    app.ssh_reverse_statuses.update(name, |status| {
+        status.active_connections -= 1;
+    });
+}
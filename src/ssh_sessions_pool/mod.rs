@@ -0,0 +1,2 @@
+mod ssh_sessions_pool;
+pub use ssh_sessions_pool::*;
@@ -0,0 +1,261 @@
+use std::{sync::Arc, time::Duration};
+
+use my_ssh::SshCredentials;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use tokio::sync::Mutex;
+
+use crate::{
+    http_proxy_pass::ProxyPassError,
+    ssh_host_keys::SshChainHostKeys,
+    ssh_jump_hosts::{SshChainSession, SshChannel},
+};
+
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+struct SshSessionState {
+    session: Option<Arc<SshChainSession>>,
+    failed_attempts: u32,
+    next_attempt: Option<DateTimeAsMicroseconds>,
+    // Destination of the last opened channel. Keep alive opens channels to it, since it is known to be permitted
+    last_destination: Option<(String, u16)>,
+}
+
+impl SshSessionState {
+    fn connection_failed(&mut self) -> Duration {
+        self.session = None;
+        self.failed_attempts += 1;
+
+        let mut delay = RECONNECT_MIN_DELAY;
+        for _ in 1..self.failed_attempts {
+            delay = delay * 2;
+            if delay >= RECONNECT_MAX_DELAY {
+                delay = RECONNECT_MAX_DELAY;
+                break;
+            }
+        }
+
+        let now = DateTimeAsMicroseconds::now();
+        self.next_attempt = Some(DateTimeAsMicroseconds::new(
+            now.unix_microseconds + delay.as_micros() as i64,
+        ));
+
+        delay
+    }
+
+    fn remove_session(&mut self, session: &Arc<SshChainSession>) -> bool {
+        if let Some(current_session) = self.session.as_ref() {
+            if Arc::ptr_eq(current_session, session) {
+                println!("Ssh session {} is dropped", session.get_name());
+                self.session = None;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn set_last_destination(&mut self, remote_host: &str, remote_port: u16) {
+        if let Some((host, port)) = self.last_destination.as_ref() {
+            if host == remote_host && *port == remote_port {
+                return;
+            }
+        }
+
+        self.last_destination = Some((remote_host.to_string(), remote_port));
+    }
+}
+
+// Session of one ssh chain. Its lock is held while session is established, so other chains are not blocked
+struct SshSessionsPoolItem {
+    key: String,
+    name: String,
+    state: Mutex<SshSessionState>,
+}
+
+// One authenticated ssh session per ssh chain. Each forwarded connection opens its own channel
+pub struct SshSessionsPool {
+    items: Mutex<Vec<Arc<SshSessionsPoolItem>>>,
+}
+
+impl SshSessionsPool {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+        }
+    }
+
+    async fn get_item(
+        &self,
+        jump_hosts: &[Arc<SshCredentials>],
+        credentials: &Arc<SshCredentials>,
+        host_keys: &SshChainHostKeys,
+    ) -> Arc<SshSessionsPoolItem> {
+        let key = crate::ssh_jump_hosts::get_chain_key(jump_hosts, credentials, host_keys);
+
+        let mut items = self.items.lock().await;
+
+        if let Some(item) = items.iter().find(|itm| itm.key == key) {
+            return item.clone();
+        }
+
+        let item = Arc::new(SshSessionsPoolItem {
+            key,
+            name: crate::ssh_jump_hosts::get_chain_name(jump_hosts, credentials),
+            state: Mutex::new(SshSessionState {
+                session: None,
+                failed_attempts: 0,
+                next_attempt: None,
+                last_destination: None,
+            }),
+        });
+
+        items.push(item.clone());
+        item
+    }
+
+    pub async fn connect_to_remote_host(
+        &self,
+        jump_hosts: &[Arc<SshCredentials>],
+        credentials: &Arc<SshCredentials>,
+        host_keys: &SshChainHostKeys,
+        keep_alive_interval: Option<Duration>,
+        remote_host: &str,
+        remote_port: u16,
+        timeout: Duration,
+    ) -> Result<SshChannel, ProxyPassError> {
+        let item = self.get_item(jump_hosts, credentials, host_keys).await;
+
+        loop {
+            let mut state = item.state.lock().await;
+
+            if let Some(session) = state.session.clone() {
+                drop(state);
+
+                match session
+                    .connect_to_remote_host(remote_host, remote_port, timeout)
+                    .await
+                {
+                    Ok(channel) => {
+                        let mut state = item.state.lock().await;
+                        state.set_last_destination(remote_host, remote_port);
+                        return Ok(channel);
+                    }
+                    Err(err) => {
+                        // Reused session may be already dead. In this case we try once again with the fresh one
+                        println!(
+                            "Can not open ssh channel {}->{}:{} over the reused session. Err: {:?}",
+                            item.name, remote_host, remote_port, err
+                        );
+
+                        let mut state = item.state.lock().await;
+                        state.remove_session(&session);
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(next_attempt) = state.next_attempt {
+                let now = DateTimeAsMicroseconds::now();
+                if now.unix_microseconds < next_attempt.unix_microseconds {
+                    return Err(ProxyPassError::SshConnectError(format!(
+                        "Ssh session {} is not available. Next reconnect attempt in {:?}",
+                        item.name,
+                        next_attempt.duration_since(now).as_positive_or_zero()
+                    )));
+                }
+            }
+
+            println!("Establishing ssh session {}", item.name);
+
+            let session =
+                match SshChainSession::connect(jump_hosts, credentials, host_keys, timeout).await {
+                    Ok(session) => Arc::new(session),
+                    Err(err) => {
+                        let delay = state.connection_failed();
+                        println!(
+                            "Can not establish ssh session {}. Next attempt in {:?}. Err: {:?}",
+                            item.name, delay, err
+                        );
+                        return Err(err);
+                    }
+                };
+
+            let channel = match session
+                .connect_to_remote_host(remote_host, remote_port, timeout)
+                .await
+            {
+                Ok(channel) => channel,
+                Err(err) => {
+                    let delay = state.connection_failed();
+                    println!(
+                        "Can not open ssh channel {}->{}:{} over the new session. Next attempt in {:?}. Err: {:?}",
+                        item.name, remote_host, remote_port, delay, err
+                    );
+                    return Err(err.into());
+                }
+            };
+
+            state.session = Some(session.clone());
+            state.failed_attempts = 0;
+            state.next_attempt = None;
+            state.set_last_destination(remote_host, remote_port);
+
+            if let Some(keep_alive_interval) = keep_alive_interval {
+                tokio::spawn(keep_alive_loop(
+                    item.clone(),
+                    session,
+                    keep_alive_interval,
+                    timeout,
+                ));
+            }
+
+            return Ok(channel);
+        }
+    }
+}
+
+// Keep alive opens and closes a forwarding channel. It does not execute commands, so accounts
+// which are allowed only port forwarding are supported
+async fn keep_alive_loop(
+    item: Arc<SshSessionsPoolItem>,
+    session: Arc<SshChainSession>,
+    keep_alive_interval: Duration,
+    timeout: Duration,
+) {
+    loop {
+        tokio::time::sleep(keep_alive_interval).await;
+
+        let (remote_host, remote_port) = {
+            let state = item.state.lock().await;
+
+            let is_alive = match state.session.as_ref() {
+                Some(current_session) => Arc::ptr_eq(current_session, &session),
+                None => false,
+            };
+
+            if !is_alive {
+                return;
+            }
+
+            match state.last_destination.clone() {
+                Some(last_destination) => last_destination,
+                None => continue,
+            }
+        };
+
+        if let Err(err) = session
+            .connect_to_remote_host(&remote_host, remote_port, timeout)
+            .await
+        {
+            println!(
+                "Ssh session {} keep alive failed. Err: {:?}",
+                item.name, err
+            );
+
+            let mut state = item.state.lock().await;
+            state.remove_session(&session);
+            return;
+        }
+    }
+}
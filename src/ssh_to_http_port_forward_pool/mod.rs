@@ -1,4 +0,0 @@
-mod ssh_to_http_port_forward_pool;
-pub use ssh_to_http_port_forward_pool::*;
-mod ssh_to_http_port_forward_configuration;
-pub use ssh_to_http_port_forward_configuration::*;
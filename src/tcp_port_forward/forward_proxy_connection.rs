@@ -0,0 +1,176 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use rust_extensions::date_time::AtomicDateTimeAsMicroseconds;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+use crate::{app::AppContext, configurations::*, ssh_jump_hosts::SshChannel};
+
+pub enum ForwardProxyRemoteStream {
+    Direct(TcpStream),
+    Ssh(SshChannel),
+}
+
+pub enum ForwardProxyConnectError {
+    NotAllowed,
+    Ssh(String),
+    CanNotConnect(String),
+    Timeout,
+}
+
+impl ForwardProxyConnectError {
+    pub fn to_string(&self) -> String {
+        match self {
+            ForwardProxyConnectError::NotAllowed => "Destination is not allowed".to_string(),
+            ForwardProxyConnectError::Ssh(err) => format!("Ssh error: {}", err),
+            ForwardProxyConnectError::CanNotConnect(err) => err.to_string(),
+            ForwardProxyConnectError::Timeout => "Timeout".to_string(),
+        }
+    }
+}
+
+// Connects to the destination requested by the forward proxy client (socks5 or http CONNECT).
+// Direct destination is resolved here and the checked address is connected, so dns can not swap it.
+// Destination behind ssh is resolved by the ssh server, so only the host is checked
+pub async fn connect_forward_proxy_destination(
+    app: &AppContext,
+    endpoint_info: &ForwardProxyEndpointHostConfig,
+    host: &str,
+    port: u16,
+) -> Result<ForwardProxyRemoteStream, ForwardProxyConnectError> {
+    let timeout = app.connection_settings.remote_connect_timeout;
+
+    match endpoint_info.ssh.as_ref() {
+        Some(ssh) => {
+            if !endpoint_info
+                .allowed_destinations
+                .is_allowed(host, port, None)
+            {
+                return Err(ForwardProxyConnectError::NotAllowed);
+            }
+
+            let ssh_channel = app
+                .ssh_sessions_pool
+                .connect_to_remote_host(
+                    &ssh.jump_hosts,
+                    &ssh.credentials,
+                    &ssh.host_keys,
+                    ssh.keep_alive_interval,
+                    host,
+                    port,
+                    timeout,
+                )
+                .await
+                .map_err(|err| ForwardProxyConnectError::Ssh(format!("{:?}", err)))?;
+
+            Ok(ForwardProxyRemoteStream::Ssh(ssh_channel))
+        }
+        None => {
+            let addresses = resolve_allowed_destination(endpoint_info, host, port).await?;
+
+            let result =
+                tokio::time::timeout(timeout, TcpStream::connect(addresses.as_slice())).await;
+
+            match result {
+                Ok(Ok(remote_stream)) => Ok(ForwardProxyRemoteStream::Direct(remote_stream)),
+                Ok(Err(err)) => Err(ForwardProxyConnectError::CanNotConnect(err.to_string())),
+                Err(_) => Err(ForwardProxyConnectError::Timeout),
+            }
+        }
+    }
+}
+
+// Returns resolved addresses of the destination which are allowed
+pub async fn resolve_allowed_destination(
+    endpoint_info: &ForwardProxyEndpointHostConfig,
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, ForwardProxyConnectError> {
+    let addresses = tokio::net::lookup_host((host, port)).await.map_err(|err| {
+        ForwardProxyConnectError::CanNotConnect(format!("Can not resolve {}. Err: {}", host, err))
+    })?;
+
+    let addresses: Vec<SocketAddr> = addresses
+        .filter(|addr| {
+            endpoint_info
+                .allowed_destinations
+                .is_allowed(host, port, Some(addr.ip()))
+        })
+        .collect();
+
+    if addresses.is_empty() {
+        return Err(ForwardProxyConnectError::NotAllowed);
+    }
+
+    Ok(addresses)
+}
+
+pub async fn relay_forward_proxy_connection(
+    client_reader: impl AsyncReadExt + Unpin + Send + 'static,
+    client_writer: impl AsyncWriteExt + Unpin + Send + 'static,
+    remote_stream: ForwardProxyRemoteStream,
+    buffer_size: usize,
+    debug: bool,
+    print_detected: impl Fn() -> (),
+) {
+    let client_writer = Arc::new(Mutex::new(client_writer));
+    let incoming_traffic_moment = Arc::new(AtomicDateTimeAsMicroseconds::now());
+
+    match remote_stream {
+        ForwardProxyRemoteStream::Direct(remote_stream) => {
+            let (remote_reader, remote_writer) = remote_stream.into_split();
+            let remote_writer = Arc::new(Mutex::new(remote_writer));
+
+            tokio::spawn(super::copy_loop(
+                client_reader,
+                remote_writer.clone(),
+                incoming_traffic_moment.clone(),
+                buffer_size,
+                debug,
+            ));
+            tokio::spawn(super::copy_loop(
+                remote_reader,
+                client_writer.clone(),
+                incoming_traffic_moment.clone(),
+                buffer_size,
+                debug,
+            ));
+
+            super::await_while_alive(
+                client_writer,
+                remote_writer,
+                incoming_traffic_moment,
+                print_detected,
+            )
+            .await;
+        }
+        ForwardProxyRemoteStream::Ssh(ssh_channel) => {
+            let (remote_reader, remote_writer) = futures::AsyncReadExt::split(ssh_channel);
+            let remote_writer = Arc::new(Mutex::new(remote_writer));
+
+            tokio::spawn(super::copy_to_ssh_loop(
+                client_reader,
+                remote_writer.clone(),
+                incoming_traffic_moment.clone(),
+                buffer_size,
+            ));
+            tokio::spawn(super::copy_from_ssh_loop(
+                remote_reader,
+                client_writer.clone(),
+                incoming_traffic_moment.clone(),
+                buffer_size,
+            ));
+
+            super::await_while_alive_with_ssh(
+                client_writer,
+                remote_writer,
+                incoming_traffic_moment,
+                print_detected,
+            )
+            .await;
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Credentials are compared without early exit, so the response time does not tell how many bytes match.
+// Only the length of the credentials can be learned
+pub fn are_credentials_same(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let mut diff = 0u8;
+
+    for (l, r) in left.iter().zip(right) {
+        diff |= l ^ r;
+    }
+
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_credentials_are_compared() {
+        assert!(super::are_credentials_same(
+            b"user:password",
+            b"user:password"
+        ));
+        assert!(!super::are_credentials_same(
+            b"user:password",
+            b"user:passwore"
+        ));
+        assert!(!super::are_credentials_same(
+            b"user:password",
+            b"user:passwor"
+        ));
+        assert!(super::are_credentials_same(b"", b""));
+    }
+}
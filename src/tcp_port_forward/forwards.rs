@@ -1,12 +1,13 @@
 use std::{sync::Arc, time::Duration};
 
-use my_ssh::SshAsyncChannel;
 use rust_extensions::date_time::{AtomicDateTimeAsMicroseconds, DateTimeAsMicroseconds};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     sync::Mutex,
 };
 
+use crate::ssh_jump_hosts::SshChannel;
+
 pub async fn copy_loop(
     mut reader: impl AsyncReadExt + Unpin,
     writer: Arc<Mutex<impl AsyncWriteExt + Unpin>>,
@@ -69,7 +70,7 @@ pub async fn copy_loop(
 
 pub async fn copy_to_ssh_loop(
     mut reader: impl AsyncReadExt + Unpin,
-    writer: Arc<Mutex<futures::io::WriteHalf<SshAsyncChannel>>>,
+    writer: Arc<Mutex<futures::io::WriteHalf<SshChannel>>>,
     incoming_traffic_moment: Arc<AtomicDateTimeAsMicroseconds>,
     buffer_size: usize,
 ) {
@@ -119,7 +120,7 @@ pub async fn copy_to_ssh_loop(
 }
 
 pub async fn copy_from_ssh_loop(
-    mut reader: futures::io::ReadHalf<SshAsyncChannel>,
+    mut reader: futures::io::ReadHalf<SshChannel>,
     writer: Arc<Mutex<impl AsyncWriteExt + Unpin>>,
     incoming_traffic_moment: Arc<AtomicDateTimeAsMicroseconds>,
     buffer_size: usize,
@@ -243,7 +244,7 @@ pub async fn await_while_alive(
 
 pub async fn await_while_alive_with_ssh(
     local_writer: Arc<Mutex<impl AsyncWriteExt + Unpin>>,
-    remote_writer: Arc<Mutex<futures::io::WriteHalf<SshAsyncChannel>>>,
+    remote_writer: Arc<Mutex<futures::io::WriteHalf<SshChannel>>>,
     incoming_traffic_moment: Arc<AtomicDateTimeAsMicroseconds>,
 
     print_detected: impl Fn() -> (),
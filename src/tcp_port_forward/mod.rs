@@ -3,3 +3,8 @@ pub use start_tcp::start_tcp;
 mod start_tcp_over_ssh;
 pub use start_tcp_over_ssh::start_tcp_over_ssh;
 mod forwards;
+pub use forwards::*;
+mod forward_proxy_connection;
+pub use forward_proxy_connection::*;
+mod forward_proxy_credentials;
+pub use forward_proxy_credentials::*;
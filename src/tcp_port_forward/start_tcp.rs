@@ -3,7 +3,7 @@ use std::sync::Arc;
 use rust_extensions::date_time::AtomicDateTimeAsMicroseconds;
 use tokio::{io::AsyncWriteExt, net::TcpStream, sync::Mutex};
 
-use crate::{app::AppContext, configurations::*};
+use crate::{app::AppContext, configurations::*, rate_limit::ConnectionPermit};
 
 pub fn start_tcp(
     app: Arc<AppContext>,
@@ -53,6 +53,24 @@ async fn tcp_server_accept_loop(
             continue;
         }
 
+        let connection_permit = match endpoint_info.connections_limit.as_ref() {
+            Some(connections_limit) => match connections_limit.try_acquire(socket_addr.ip()) {
+                Some(permit) => Some(permit),
+                None => {
+                    if endpoint_info.debug {
+                        println!(
+                            "Incoming connection from {} is over the connections limit. Closing it",
+                            socket_addr
+                        );
+                    }
+
+                    let _ = server_stream.shutdown().await;
+                    continue;
+                }
+            },
+            None => None,
+        };
+
         let remote_tcp_connection_result = tokio::time::timeout(
             app.connection_settings.remote_connect_timeout,
             TcpStream::connect(endpoint_info.remote_addr),
@@ -90,6 +108,7 @@ async fn tcp_server_accept_loop(
             remote_tcp_connection_result.unwrap(),
             app.connection_settings.buffer_size,
             endpoint_info.debug,
+            connection_permit,
         ));
     }
 }
@@ -101,6 +120,7 @@ async fn connection_loop(
     remote_stream: TcpStream,
     buffer_size: usize,
     debug: bool,
+    _connection_permit: Option<ConnectionPermit>,
 ) {
     let (tcp_server_reader, tcp_server_writer) = server_stream.into_split();
 
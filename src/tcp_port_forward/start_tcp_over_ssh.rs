@@ -1,10 +1,9 @@
 use std::sync::Arc;
 
-use my_ssh::{SshAsyncChannel, SshCredentials, SshSession};
 use rust_extensions::date_time::AtomicDateTimeAsMicroseconds;
 use tokio::{io::AsyncWriteExt, net::TcpStream, sync::Mutex};
 
-use crate::{app::AppContext, configurations::*};
+use crate::{app::AppContext, configurations::*, rate_limit::ConnectionPermit};
 
 pub fn start_tcp_over_ssh(
     app: Arc<AppContext>,
@@ -48,17 +47,60 @@ async fn tcp_server_accept_loop(
             return;
         }
 
-        let ssh_session = SshSession::new(endpoint_info.ssh_credentials.clone());
-
-        let ssh_channel = ssh_session
-            .connect_to_remote_host(
-                endpoint_info.remote_host.get_host(),
-                endpoint_info.remote_host.get_port(),
-                app.connection_settings.remote_connect_timeout,
-            )
-            .await;
+        let connection_permit = match endpoint_info.connections_limit.as_ref() {
+            Some(connections_limit) => match connections_limit.try_acquire(socket_addr.ip()) {
+                Some(permit) => Some(permit),
+                None => {
+                    if endpoint_info.debug {
+                        println!(
+                            "Incoming connection from {} is over the connections limit. Closing it",
+                            socket_addr
+                        );
+                    }
+
+                    let _ = server_stream.shutdown().await;
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        // Ssh session may be established for seconds, so new connections are not awaited for it
+        tokio::spawn(connection_loop(
+            app.clone(),
+            listen_addr,
+            endpoint_info.clone(),
+            server_stream,
+            socket_addr,
+            connection_permit,
+        ));
+    }
+}
 
-        if let Err(err) = ssh_channel {
+async fn connection_loop(
+    app: Arc<AppContext>,
+    listen_addr: std::net::SocketAddr,
+    endpoint_info: Arc<TcpOverSshEndpointHostConfig>,
+    mut server_stream: TcpStream,
+    socket_addr: std::net::SocketAddr,
+    _connection_permit: Option<ConnectionPermit>,
+) {
+    let remote_stream = app
+        .ssh_sessions_pool
+        .connect_to_remote_host(
+            &endpoint_info.ssh_jump_hosts,
+            &endpoint_info.ssh_credentials,
+            &endpoint_info.ssh_host_keys,
+            endpoint_info.ssh_keep_alive_interval,
+            endpoint_info.remote_host.get_host(),
+            endpoint_info.remote_host.get_port(),
+            app.connection_settings.remote_connect_timeout,
+        )
+        .await;
+
+    let remote_stream = match remote_stream {
+        Ok(remote_stream) => remote_stream,
+        Err(err) => {
             if endpoint_info.debug {
                 println!(
                     "Error connecting to remote tcp {} over ssh {}->{} server. Closing incoming connection: {}. Err: {:?}",
@@ -70,30 +112,13 @@ async fn tcp_server_accept_loop(
                 );
             }
             let _ = server_stream.shutdown().await;
-            continue;
+            return;
         }
+    };
 
-        tokio::spawn(connection_loop(
-            listen_addr,
-            endpoint_info.ssh_credentials.clone(),
-            endpoint_info.remote_host.clone(),
-            server_stream,
-            ssh_channel.unwrap(),
-            app.connection_settings.buffer_size,
-            endpoint_info.debug,
-        ));
-    }
-}
+    let buffer_size = app.connection_settings.buffer_size;
+    let debug = endpoint_info.debug;
 
-async fn connection_loop(
-    listen_addr: std::net::SocketAddr,
-    ssh_credentials: Arc<SshCredentials>,
-    remote_host: Arc<RemoteHost>,
-    server_stream: TcpStream,
-    remote_stream: SshAsyncChannel,
-    buffer_size: usize,
-    debug: bool,
-) {
     let (tcp_server_reader, tcp_server_writer) = server_stream.into_split();
 
     let (remote_ssh_read, remote_ssh_writer) = futures::AsyncReadExt::split(remote_stream);
@@ -126,8 +151,8 @@ async fn connection_loop(
                 println!(
                     "Dead Tcp PortForward {}->{}->{} connection detected. Closing",
                     listen_addr,
-                    ssh_credentials.to_string(),
-                    remote_host.as_str()
+                    endpoint_info.ssh_credentials.to_string(),
+                    endpoint_info.remote_host.as_str()
                 );
             }
         },
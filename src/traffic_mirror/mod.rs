@@ -0,0 +1,2 @@
+mod traffic_mirror;
+pub use traffic_mirror::*;
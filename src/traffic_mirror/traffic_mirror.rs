@@ -0,0 +1,270 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use tokio::sync::Mutex;
+
+use crate::{
+    app::AppContext, configurations::ProxyPassLocationConfig,
+    http_content_source::RemoteHttpContentSource, http_proxy_pass::ProxyPassError,
+    settings::ProxyPassTo,
+};
+
+pub struct TrafficMirrorParams {
+    // Percent of requests which are copied to the mirror
+    pub percent: f64,
+    pub timeout: Duration,
+    pub max_in_flight: usize,
+}
+
+pub struct TrafficMirrorSnapshot {
+    pub upstream: String,
+    pub percent: f64,
+    pub mirrored: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    pub in_flight: usize,
+}
+
+pub struct TrafficMirror {
+    pub proxy_pass_to: ProxyPassTo,
+    pub params: TrafficMirrorParams,
+    requests: AtomicU64,
+    mirrored: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    in_flight: AtomicUsize,
+    // Connection is created on the first mirrored request and is shared by all the client connections
+    content_source: Mutex<Option<RemoteHttpContentSource>>,
+}
+
+impl TrafficMirror {
+    pub fn new(proxy_pass_to: ProxyPassTo, params: TrafficMirrorParams) -> Self {
+        Self {
+            proxy_pass_to,
+            params,
+            requests: AtomicU64::new(0),
+            mirrored: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            in_flight: AtomicUsize::new(0),
+            content_source: Mutex::new(None),
+        }
+    }
+
+    // Every request gets its share of percent. Request is mirrored when the shares sum up to the next whole request
+    pub fn should_mirror(&self) -> bool {
+        let request_no = self.requests.fetch_add(1, Ordering::Relaxed);
+        let before = (request_no as f64 * self.params.percent / 100.0).floor();
+        let after = ((request_no + 1) as f64 * self.params.percent / 100.0).floor();
+        after > before
+    }
+
+    // Mirror which is slower than the traffic is not queued up. Extra requests are dropped
+    pub fn try_acquire(&self) -> bool {
+        let acquired = self
+            .in_flight
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |in_flight| {
+                if in_flight < self.params.max_in_flight {
+                    Some(in_flight + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok();
+
+        if !acquired {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+
+        acquired
+    }
+
+    fn release(&self, failed: bool) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.mirrored.fetch_add(1, Ordering::Relaxed);
+
+        if failed {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn release_dropped(&self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_snapshot(&self) -> TrafficMirrorSnapshot {
+        TrafficMirrorSnapshot {
+            upstream: self.proxy_pass_to.to_string(),
+            percent: self.params.percent,
+            mirrored: self.mirrored.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+        }
+    }
+
+    // Lock is kept only to connect and to pass the request to the connection, so http2 mirrored requests go in parallel.
+    // Http1 connection serves one request at a time. Request which finds it busy is dropped, not queued up.
+    // Returns false if the request is dropped
+    async fn send(
+        &self,
+        app: &AppContext,
+        location_config: &ProxyPassLocationConfig,
+        request: hyper::Request<Full<Bytes>>,
+        debug: bool,
+    ) -> Result<bool, ProxyPassError> {
+        let (future1, future2, connected_moment) = {
+            let mut content_source = self.content_source.lock().await;
+
+            if content_source.is_none() {
+                *content_source = location_config.create_mirror_content_source(debug);
+            }
+
+            let content_source = match content_source.as_mut() {
+                Some(content_source) => content_source,
+                // Only remote http upstreams are accepted as mirror by the settings
+                None => return Ok(true),
+            };
+
+            content_source
+                .connect_if_require(app, &location_config.domain_name, debug)
+                .await?;
+
+            let connected_moment = content_source
+                .get_connected_moment()
+                .map(|itm| itm.unix_microseconds);
+
+            if content_source.remote_endpoint.is_http1() {
+                if !content_source.is_http1_ready()? {
+                    return Ok(false);
+                }
+
+                let future = content_source.send_http1_request(request)?;
+                (Some(future), None, connected_moment)
+            } else {
+                let future = content_source.send_http2_request(request)?;
+                (None, Some(future), connected_moment)
+            }
+        };
+
+        let result = if let Some(future1) = future1 {
+            read_response(future1, self.params.timeout).await
+        } else if let Some(future2) = future2 {
+            read_response(future2, self.params.timeout).await
+        } else {
+            Ok(())
+        };
+
+        if result.is_err() {
+            self.dispose(connected_moment).await;
+        }
+
+        result.map(|_| true)
+    }
+
+    // Connection state is unknown after the failure.
+    // It is not disposed if the other mirrored request has already replaced it with the new one
+    async fn dispose(&self, connected_moment: Option<i64>) {
+        if let Some(content_source) = self.content_source.lock().await.as_mut() {
+            let current_moment = content_source
+                .get_connected_moment()
+                .map(|itm| itm.unix_microseconds);
+
+            if current_moment == connected_moment {
+                content_source.dispose();
+            }
+        }
+    }
+}
+
+async fn read_response(
+    response: impl Future<Output = Result<hyper::Response<Incoming>, hyper::Error>>,
+    timeout: Duration,
+) -> Result<(), ProxyPassError> {
+    let response = async {
+        let response = response.await?;
+
+        // Body is read to the end to keep the connection reusable
+        response.into_body().collect().await?;
+
+        Ok::<(), ProxyPassError>(())
+    };
+
+    match tokio::time::timeout(timeout, response).await {
+        Ok(result) => result,
+        Err(_) => Err(ProxyPassError::Timeout),
+    }
+}
+
+// Response of the mirror is discarded. Errors are only counted and logged
+pub async fn send_mirror_request(
+    app: Arc<AppContext>,
+    location_config: Arc<ProxyPassLocationConfig>,
+    request: hyper::Request<Full<Bytes>>,
+    debug: bool,
+) {
+    let mirror = match location_config.mirror.as_ref() {
+        Some(mirror) => mirror,
+        None => return,
+    };
+
+    match mirror.send(&app, &location_config, request, debug).await {
+        Ok(true) => mirror.release(false),
+        Ok(false) => {
+            if debug {
+                println!(
+                    "Mirror {} of location {} is busy. Request is not mirrored",
+                    mirror.proxy_pass_to.to_string(),
+                    location_config.path
+                );
+            }
+
+            mirror.release_dropped();
+        }
+        Err(err) => {
+            mirror.release(true);
+
+            println!(
+                "Mirror request of location {} to {} failed: {:?}",
+                location_config.path,
+                mirror.proxy_pass_to.to_string(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configurations::RemoteHost;
+
+    #[test]
+    fn test_should_mirror_percent() {
+        let mirror = TrafficMirror::new(
+            ProxyPassTo::Http(RemoteHost::new("http://localhost:5000".to_string())),
+            TrafficMirrorParams {
+                percent: 25.0,
+                timeout: Duration::from_secs(5),
+                max_in_flight: 1,
+            },
+        );
+
+        let mirrored = (0..100).filter(|_| mirror.should_mirror()).count();
+        assert_eq!(25, mirrored);
+
+        assert!(mirror.try_acquire());
+        assert!(!mirror.try_acquire());
+        assert_eq!(1, mirror.get_snapshot().dropped);
+    }
+}
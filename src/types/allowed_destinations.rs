@@ -0,0 +1,261 @@
+use std::net::IpAddr;
+
+enum DestinationHostRule {
+    Any,
+    Wildcard(String),
+    IpRange { ip_from: IpAddr, ip_to: IpAddr },
+}
+
+impl DestinationHostRule {
+    fn parse(src: &str) -> Result<Self, String> {
+        if src == "*" {
+            return Ok(Self::Any);
+        }
+
+        if let Ok(ip) = src.parse::<IpAddr>() {
+            return Ok(Self::IpRange {
+                ip_from: ip,
+                ip_to: ip,
+            });
+        }
+
+        if let Some((left, right)) = src.split_once('-') {
+            if let (Ok(ip_from), Ok(ip_to)) = (left.parse::<IpAddr>(), right.parse::<IpAddr>()) {
+                if ip_from.is_ipv4() != ip_to.is_ipv4() {
+                    return Err(format!(
+                        "Ip range '{}' must have both ips of the same version",
+                        src
+                    ));
+                }
+
+                return Ok(Self::IpRange { ip_from, ip_to });
+            }
+        }
+
+        if src.contains(':') {
+            return Err(format!("Invalid ip address '{}'", src));
+        }
+
+        Ok(Self::Wildcard(src.to_lowercase()))
+    }
+
+    fn is_match(&self, host: &str, ip: Option<IpAddr>) -> bool {
+        match self {
+            DestinationHostRule::Any => !is_local(host, ip),
+            DestinationHostRule::Wildcard(pattern) => {
+                !is_local(host, ip)
+                    && crate::http_proxy_pass::wildcard_match(pattern.as_bytes(), host.as_bytes())
+            }
+            DestinationHostRule::IpRange { ip_from, ip_to } => match ip {
+                Some(ip) => ip.is_ipv4() == ip_from.is_ipv4() && *ip_from <= ip && ip <= *ip_to,
+                None => false,
+            },
+        }
+    }
+}
+
+struct DestinationRule {
+    host: DestinationHostRule,
+    port_from: u16,
+    port_to: u16,
+}
+
+// Entries are in format host[:port]. Host can be * / wildcard / ip / ip range (ip_from-ip_to).
+// IPv6 ip with port must be in brackets: [::1]:443.
+// Port can be * / single port / port range (from-to). If port is missing - any port is allowed.
+// Empty list denies everything. Localhost, loopback, unspecified and link-local ips are allowed only by ip rules.
+// Private ranges (10.0.0.0/8, 192.168.0.0/16...) are matched by * and wildcards. To keep the internal network closed
+// destinations must be listed by explicit rules instead of *
+pub struct AllowedDestinations {
+    rules: Vec<DestinationRule>,
+}
+
+impl AllowedDestinations {
+    pub fn new(src: &[String]) -> Result<Self, String> {
+        let mut rules = Vec::with_capacity(src.len());
+
+        for itm in src {
+            let itm = itm.trim();
+
+            let (host, port) = split_host_port(itm)
+                .ok_or_else(|| format!("Invalid allowed destination '{}'", itm))?;
+
+            let (port_from, port_to) = parse_port(port).ok_or_else(|| {
+                format!("Invalid port '{}' in allowed destination '{}'", port, itm)
+            })?;
+
+            rules.push(DestinationRule {
+                host: DestinationHostRule::parse(host)?,
+                port_from,
+                port_to,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    // ip - resolved address of the host. None if host is resolved by the ssh server.
+    // In this case ip rules are matched only if host is ip itself
+    pub fn is_allowed(&self, host: &str, port: u16, ip: Option<IpAddr>) -> bool {
+        let host = host.to_lowercase();
+        let ip = ip
+            .or_else(|| host.parse().ok())
+            .map(|ip: IpAddr| ip.to_canonical());
+
+        for rule in &self.rules {
+            if rule.port_from <= port && port <= rule.port_to && rule.host.is_match(&host, ip) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+fn split_host_port(src: &str) -> Option<(&str, &str)> {
+    if let Some(src) = src.strip_prefix('[') {
+        let (host, rest) = src.split_once(']')?;
+
+        if rest.is_empty() {
+            return Some((host, "*"));
+        }
+
+        return Some((host, rest.strip_prefix(':')?));
+    }
+
+    // IPv6 without brackets can not have the port
+    if src.matches(':').count() > 1 {
+        return Some((src, "*"));
+    }
+
+    match src.rsplit_once(':') {
+        Some((host, port)) => Some((host, port)),
+        None => Some((src, "*")),
+    }
+}
+
+fn parse_port(src: &str) -> Option<(u16, u16)> {
+    if src == "*" {
+        return Some((0, u16::MAX));
+    }
+
+    if let Some((from, to)) = src.split_once('-') {
+        return Some((from.parse().ok()?, to.parse().ok()?));
+    }
+
+    let port = src.parse().ok()?;
+    Some((port, port))
+}
+
+fn is_local(host: &str, ip: Option<IpAddr>) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") {
+        return true;
+    }
+
+    match ip {
+        Some(ip) => ip.is_loopback() || ip.is_unspecified() || is_link_local(ip),
+        None => false,
+    }
+}
+
+// Link-local range has the cloud metadata endpoint 169.254.169.254
+fn is_link_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xffc0) == 0xfe80,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::AllowedDestinations;
+
+    #[test]
+    fn test_allowed_destinations() {
+        let list = AllowedDestinations::new(&[
+            "*.internal:443".to_string(),
+            "10.0.0.1-10.0.0.255:22".to_string(),
+            "db.local:5432-5433".to_string(),
+        ])
+        .unwrap();
+
+        assert!(list.is_allowed("api.internal", 443, None));
+        assert!(list.is_allowed("API.Internal", 443, None));
+        assert!(!list.is_allowed("api.internal", 80, None));
+
+        assert!(list.is_allowed("10.0.0.5", 22, None));
+        assert!(!list.is_allowed("10.0.1.5", 22, None));
+
+        assert!(list.is_allowed("db.local", 5433, None));
+        assert!(!list.is_allowed("db.local", 5434, None));
+    }
+
+    #[test]
+    fn test_empty_list_denies_everything() {
+        let list = AllowedDestinations::new(&[]).unwrap();
+        assert!(!list.is_allowed("google.com", 443, None));
+    }
+
+    #[test]
+    fn test_ip_rules_match_resolved_address() {
+        let list =
+            AllowedDestinations::new(&["*".to_string(), "10.0.0.1-10.0.0.255:22".to_string()])
+                .unwrap();
+
+        let internal: IpAddr = "10.0.0.5".parse().unwrap();
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+
+        assert!(list.is_allowed("ssh.company.com", 22, Some(internal)));
+        assert!(list.is_allowed("google.com", 443, Some("8.8.8.8".parse().unwrap())));
+
+        // Name which resolves to the loopback is not allowed by * or wildcard
+        assert!(!list.is_allowed("localtest.me", 8000, Some(localhost)));
+        assert!(!list.is_allowed("localhost", 8000, None));
+        assert!(!list.is_allowed("127.0.0.1", 8000, None));
+        assert!(!list.is_allowed("::1", 8000, None));
+    }
+
+    #[test]
+    fn test_link_local_is_allowed_only_by_ip_rules() {
+        let list = AllowedDestinations::new(&["*".to_string()]).unwrap();
+
+        assert!(!list.is_allowed("169.254.169.254", 80, None));
+        assert!(!list.is_allowed(
+            "metadata.google.internal",
+            80,
+            Some("169.254.169.254".parse().unwrap())
+        ));
+        assert!(!list.is_allowed("fe80::1", 80, None));
+        assert!(!list.is_allowed("::ffff:169.254.169.254", 80, None));
+        assert!(list.is_allowed("fec0::1", 80, None));
+
+        let list = AllowedDestinations::new(&["169.254.169.254:80".to_string()]).unwrap();
+        assert!(list.is_allowed("169.254.169.254", 80, None));
+    }
+
+    #[test]
+    fn test_ipv6_rules() {
+        let list = AllowedDestinations::new(&[
+            "[::1]:443".to_string(),
+            "2001:db8::1".to_string(),
+            "[2001:db8::10-2001:db8::20]:22".to_string(),
+        ])
+        .unwrap();
+
+        assert!(list.is_allowed("::1", 443, None));
+        assert!(!list.is_allowed("::1", 80, None));
+
+        assert!(list.is_allowed("2001:db8::1", 80, None));
+        assert!(list.is_allowed("example.com", 80, Some("2001:db8::1".parse().unwrap())));
+
+        assert!(list.is_allowed("2001:db8::15", 22, None));
+        assert!(!list.is_allowed("2001:db8::21", 22, None));
+        assert!(!list.is_allowed("10.0.0.1", 22, None));
+
+        assert!(AllowedDestinations::new(&["[::1:443".to_string()]).is_err());
+        assert!(AllowedDestinations::new(&["[::1]443".to_string()]).is_err());
+        assert!(AllowedDestinations::new(&["::1-10.0.0.1".to_string()]).is_err());
+    }
+}
@@ -4,3 +4,5 @@ mod whitelisted_ip_list;
 pub use whitelisted_ip_list::*;
 mod whitelisted_ip;
 pub use whitelisted_ip::*;
+mod allowed_destinations;
+pub use allowed_destinations::*;
@@ -43,6 +43,7 @@ impl IntoIp for IpAddr {
     }
 }
 
+#[derive(Clone)]
 pub enum WhitelistedIp {
     SingleIp(u32),
     Range { ip_from: u32, ip_to: u32 },
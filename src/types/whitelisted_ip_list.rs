@@ -1,5 +1,6 @@
 use super::{IntoIp, WhitelistedIp};
 
+#[derive(Clone)]
 pub struct WhiteListedIpList {
     items: Vec<WhitelistedIp>,
 }
@@ -0,0 +1,2 @@
+mod start_udp;
+pub use start_udp::start_udp;
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use rust_extensions::date_time::{AtomicDateTimeAsMicroseconds, DateTimeAsMicroseconds};
+use tokio::{net::UdpSocket, sync::Mutex};
+
+use crate::{app::AppContext, configurations::*, rate_limit::ConnectionPermit};
+
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
+struct UdpSession {
+    upstream: Arc<UdpSocket>,
+    last_activity: Arc<AtomicDateTimeAsMicroseconds>,
+}
+
+type UdpSessions = Arc<Mutex<HashMap<SocketAddr, UdpSession>>>;
+
+pub fn start_udp(
+    app: Arc<AppContext>,
+    listen_addr: SocketAddr,
+    endpoint_info: Arc<UdpEndpointHostConfig>,
+) {
+    tokio::spawn(udp_server_loop(app, listen_addr, endpoint_info));
+}
+
+async fn udp_server_loop(
+    app: Arc<AppContext>,
+    listen_addr: SocketAddr,
+    endpoint_info: Arc<UdpEndpointHostConfig>,
+) {
+    let listener = UdpSocket::bind(listen_addr).await;
+
+    if let Err(err) = listener {
+        println!(
+            "Error binding to udp port {} for forwarding to {} has Error: {:?}",
+            listen_addr, endpoint_info.remote_addr, err
+        );
+        return;
+    }
+
+    let listener = Arc::new(listener.unwrap());
+
+    println!(
+        "Enabled Udp PortForward: {} -> {}",
+        listen_addr, endpoint_info.remote_addr
+    );
+
+    let sessions: UdpSessions = Arc::new(Mutex::new(HashMap::new()));
+
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+
+    loop {
+        let (size, client_addr) = match listener.recv_from(&mut buffer).await {
+            Ok(result) => result,
+            Err(err) => {
+                // Windows reports ICMP port unreachable of previous send_to as recv error
+                if endpoint_info.debug {
+                    println!("Udp {} receive error. Err: {:?}", listen_addr, err);
+                }
+                continue;
+            }
+        };
+
+        if app.states.is_shutting_down() {
+            return;
+        }
+
+        if !endpoint_info
+            .whitelisted_ip
+            .is_whitelisted(&client_addr.ip())
+        {
+            if endpoint_info.debug {
+                println!(
+                    "Incoming udp datagram from {} is not whitelisted. Dropping it",
+                    client_addr
+                );
+            }
+            continue;
+        }
+
+        let upstream = {
+            let mut sessions_access = sessions.lock().await;
+
+            match sessions_access.get(&client_addr) {
+                Some(session) => {
+                    session.last_activity.update(DateTimeAsMicroseconds::now());
+                    session.upstream.clone()
+                }
+                None => {
+                    let connection_permit = match endpoint_info.connections_limit.as_ref() {
+                        Some(connections_limit) => {
+                            match connections_limit.try_acquire(client_addr.ip()) {
+                                Some(permit) => Some(permit),
+                                None => {
+                                    if endpoint_info.debug {
+                                        println!(
+                                            "Incoming udp session from {} is over the connections limit. Dropping datagram",
+                                            client_addr
+                                        );
+                                    }
+                                    continue;
+                                }
+                            }
+                        }
+                        None => None,
+                    };
+
+                    let upstream = match connect_upstream(endpoint_info.remote_addr).await {
+                        Ok(upstream) => Arc::new(upstream),
+                        Err(err) => {
+                            println!(
+                                "Udp {} can not create upstream socket to {}. Err: {:?}",
+                                listen_addr, endpoint_info.remote_addr, err
+                            );
+                            continue;
+                        }
+                    };
+
+                    if endpoint_info.debug {
+                        println!(
+                            "New udp session {} -> {}",
+                            client_addr, endpoint_info.remote_addr
+                        );
+                    }
+
+                    let last_activity = Arc::new(AtomicDateTimeAsMicroseconds::now());
+
+                    sessions_access.insert(
+                        client_addr,
+                        UdpSession {
+                            upstream: upstream.clone(),
+                            last_activity: last_activity.clone(),
+                        },
+                    );
+
+                    tokio::spawn(upstream_to_client_loop(
+                        listener.clone(),
+                        upstream.clone(),
+                        client_addr,
+                        last_activity,
+                        sessions.clone(),
+                        endpoint_info.clone(),
+                        connection_permit,
+                    ));
+
+                    upstream
+                }
+            }
+        };
+
+        if let Err(err) = upstream.send(&buffer[..size]).await {
+            if endpoint_info.debug {
+                println!(
+                    "Udp {} can not send datagram to {}. Err: {:?}",
+                    client_addr, endpoint_info.remote_addr, err
+                );
+            }
+        }
+    }
+}
+
+async fn connect_upstream(remote_addr: SocketAddr) -> std::io::Result<UdpSocket> {
+    let bind_addr: SocketAddr = if remote_addr.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+
+    let upstream = UdpSocket::bind(bind_addr).await?;
+    upstream.connect(remote_addr).await?;
+    Ok(upstream)
+}
+
+async fn upstream_to_client_loop(
+    listener: Arc<UdpSocket>,
+    upstream: Arc<UdpSocket>,
+    client_addr: SocketAddr,
+    last_activity: Arc<AtomicDateTimeAsMicroseconds>,
+    sessions: UdpSessions,
+    endpoint_info: Arc<UdpEndpointHostConfig>,
+    connection_permit: Option<ConnectionPermit>,
+) {
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+
+    loop {
+        match tokio::time::timeout(endpoint_info.idle_timeout, upstream.recv(&mut buffer)).await {
+            Ok(Ok(size)) => {
+                last_activity.update(DateTimeAsMicroseconds::now());
+
+                if let Err(err) = listener.send_to(&buffer[..size], client_addr).await {
+                    if endpoint_info.debug {
+                        println!(
+                            "Udp can not send datagram back to {}. Err: {:?}",
+                            client_addr, err
+                        );
+                    }
+                }
+            }
+            Ok(Err(err)) => {
+                // ICMP port unreachable from remote comes here. Session keeps alive until idle timeout
+                if endpoint_info.debug {
+                    println!(
+                        "Udp {} receive error from {}. Err: {:?}",
+                        client_addr, endpoint_info.remote_addr, err
+                    );
+                }
+            }
+            Err(_) => {}
+        }
+
+        if get_idle_duration(&last_activity) < endpoint_info.idle_timeout {
+            continue;
+        }
+
+        // Checking again under the lock - client datagram could arrive and refresh the session
+        let mut sessions_access = sessions.lock().await;
+        if get_idle_duration(&last_activity) >= endpoint_info.idle_timeout {
+            sessions_access.remove(&client_addr);
+            break;
+        }
+    }
+
+    if endpoint_info.debug {
+        println!(
+            "Udp session {} -> {} is closed by idle timeout",
+            client_addr, endpoint_info.remote_addr
+        );
+    }
+
+    drop(connection_permit);
+}
+
+fn get_idle_duration(last_activity: &AtomicDateTimeAsMicroseconds) -> std::time::Duration {
+    let last_activity = DateTimeAsMicroseconds::new(last_activity.get_unix_microseconds());
+
+    DateTimeAsMicroseconds::now()
+        .duration_since(last_activity)
+        .as_positive_or_zero()
+}